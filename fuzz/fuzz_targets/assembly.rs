@@ -0,0 +1,43 @@
+#![no_main]
+use dotnet_rs::disasm::disassemble;
+use dotnet_rs::export::export;
+use dotnet_rs::runtime::{RowId, TypeLoader};
+use dotnet_rs::metadata::ValidationMode;
+use dotnet_rs::Assembly;
+use libfuzzer_sys::fuzz_target;
+
+// The whole pipeline: PE, CLI header, metadata root, tables and heaps on load, then everything that is decoded lazily.
+fuzz_target!(|data: &[u8]| {
+    let _ = Assembly::from_bytes_with(data, ValidationMode::Strict);
+    let assembly = match Assembly::from_bytes(data) {
+        Ok(assembly) => assembly,
+        Err(_) => return,
+    };
+    let _ = assembly.entry_point();
+    let _ = assembly.vtable_fixups();
+    for ty in assembly.types() {
+        let _ = ty.full_name();
+        let _ = ty.base_type();
+        ty.interfaces().for_each(drop);
+    }
+    let mut types = TypeLoader::new(&assembly);
+    for ty in assembly.types() {
+        let _ = types.type_definition(RowId::new(0, ty.rid));
+    }
+    for ty in 1..=assembly.tables().type_refs.len() as u32 {
+        let _ = assembly.type_reference(ty).map(|x| x.full_name());
+    }
+    for method in assembly.methods() {
+        let _ = method.signature();
+        let _ = method.locals();
+        let _ = method.instructions();
+    }
+    for field in assembly.fields() {
+        let _ = field.signature();
+    }
+    for resource in &assembly.tables().manifest_resources {
+        let _ = assembly.manifest_resource_data(resource);
+    }
+    let _ = export(&assembly);
+    let _ = disassemble(&assembly, &mut std::io::sink());
+});
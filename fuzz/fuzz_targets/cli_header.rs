@@ -0,0 +1,11 @@
+#![no_main]
+use dotnet_rs::cli_header::CliHeader;
+use libfuzzer_sys::fuzz_target;
+use scroll::Pread;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = data.pread_with::<CliHeader>(0, scroll::LE) {
+        let _ = header.entry_point_token();
+        let _ = header.entry_point_rva();
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+use dotnet_rs::heaps::{BlobHeap, GuidHeap, StringHeap, UserStringHeap};
+use libfuzzer_sys::fuzz_target;
+
+// Input is read as each of the heaps in turn.
+fuzz_target!(|data: &[u8]| {
+    let strings = StringHeap::new(data);
+    let user_strings = UserStringHeap::new(data);
+    let blobs = BlobHeap::new(data);
+    let guids = GuidHeap::new(data);
+    strings.iter().for_each(drop);
+    user_strings.iter().for_each(drop);
+    blobs.iter().for_each(drop);
+    guids.iter().for_each(drop);
+    for index in 0..=data.len() as u32 {
+        let _ = strings.get(index);
+        let _ = user_strings.get(index);
+        let _ = blobs.get(index);
+        let _ = guids.get(index);
+    }
+});
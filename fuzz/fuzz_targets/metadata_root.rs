@@ -0,0 +1,14 @@
+#![no_main]
+use dotnet_rs::metadata::MetadataRoot;
+use libfuzzer_sys::fuzz_target;
+use scroll::Pread;
+
+// Input is the metadata directory, the root followed by its streams.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(root) = data.pread_with::<MetadataRoot>(0, scroll::LE) {
+        let _ = root.validate(data.len());
+        for name in &["#~", "#-", "#Strings", "#US", "#Blob", "#GUID"] {
+            let _ = root.stream(data, name);
+        }
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+use dotnet_rs::instruction::decode_instructions;
+use dotnet_rs::method_body::MethodBody;
+use libfuzzer_sys::fuzz_target;
+use scroll::Pread;
+
+// Input is a method body starting at its header.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(body) = data.pread_with::<MethodBody>(0, scroll::LE) {
+        let _ = decode_instructions(body.code, None);
+    }
+});
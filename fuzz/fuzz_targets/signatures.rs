@@ -0,0 +1,16 @@
+#![no_main]
+use dotnet_rs::signature::{
+    FieldSignature, LocalVarSignature, MethodSignature, MethodSpecSignature, PropertySignature, Type,
+};
+use libfuzzer_sys::fuzz_target;
+use scroll::Pread;
+
+// Input is a #Blob entry without its length, read as every kind of signature.
+fuzz_target!(|data: &[u8]| {
+    let _ = data.pread_with::<MethodSignature>(0, scroll::LE);
+    let _ = data.pread_with::<FieldSignature>(0, scroll::LE);
+    let _ = data.pread_with::<PropertySignature>(0, scroll::LE);
+    let _ = data.pread_with::<LocalVarSignature>(0, scroll::LE);
+    let _ = data.pread_with::<MethodSpecSignature>(0, scroll::LE);
+    let _ = data.pread_with::<Type>(0, scroll::LE);
+});
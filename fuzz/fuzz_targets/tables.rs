@@ -0,0 +1,32 @@
+#![no_main]
+use dotnet_rs::tables::{TableId, TildaStream};
+use dotnet_rs::token::{Token, TokenKind};
+use libfuzzer_sys::fuzz_target;
+use scroll::Pread;
+
+// Input is a #~ stream.
+fuzz_target!(|data: &[u8]| {
+    let tables = match data.pread_with::<TildaStream>(0, scroll::LE) {
+        Ok(tables) => tables,
+        Err(_) => return,
+    };
+    for table in (0..=0x2c).filter_map(TableId::from_u8) {
+        // One past the end on both sides to hit the range checks
+        for rid in 0..=tables.row_count(table) as u32 + 1 {
+            let _ = tables.resolve(Token::new(TokenKind::Table(table), rid));
+        }
+    }
+    for rid in 1..=tables.type_defs.len() as u32 {
+        tables.type_fields(rid).for_each(drop);
+        tables.type_methods(rid).for_each(drop);
+    }
+    for rid in 1..=tables.methods.len() as u32 {
+        tables.method_params(rid).for_each(drop);
+    }
+    for rid in 1..=tables.event_maps.len() as u32 {
+        tables.event_map_events(rid).for_each(drop);
+    }
+    for rid in 1..=tables.property_maps.len() as u32 {
+        tables.property_map_properties(rid).for_each(drop);
+    }
+});
@@ -0,0 +1,859 @@
+use crate::cli_header::{CliHeader, VTableFixup};
+use crate::coded_index::{CustomAttributeType, HasCustomAttribute, ResolutionScope, TypeDefOrRef, TypeOrMethodDef};
+use crate::error::Error;
+use crate::heaps::{BlobHeap, GuidHeap, StringHeap, UserStringHeap};
+use crate::instruction::{decode_instructions, Instruction};
+use crate::metadata::{MetadataRoot, ValidationMode, Violation};
+use crate::method_body::MethodBody;
+use crate::platform::Platform;
+use crate::signature::{FieldSignature, LocalVarSignature, MethodSignature, Type};
+use crate::tables::{
+    CustomAttribute, Field, GenericParam, ManifestResource, MethodDef, TableId, TildaStream, TypeDef, TypeRef, TypeSpec,
+};
+use crate::token::{Row, Token, TokenKind};
+use goblin::pe::data_directories::DataDirectory;
+use goblin::pe::header::{Header, SIZEOF_COFF_HEADER, SIZEOF_PE_MAGIC};
+use goblin::pe::optional_header::MAGIC_64;
+use goblin::pe::section_table::SectionTable;
+use goblin::pe::utils::{find_offset, get_data};
+use scroll::{self, Pread};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::Path;
+
+/// Type names nest through TypeSpecs and enclosing types, hostile images could make that loop forever.
+pub(crate) const MAX_DEPTH: u32 = 64;
+
+/// A loaded managed image: its CLI header, metadata tables and heaps.
+///
+/// The image bytes are either borrowed from the caller or owned by the assembly,
+/// everything else is parsed once on load and then served from them.
+pub struct Assembly<'a> {
+    data: Cow<'a, [u8]>,
+    machine: u16,
+    pe32_plus: bool,
+    sections: Vec<SectionTable>,
+    file_alignment: u32,
+    cli_header: CliHeader,
+    metadata: Range<usize>,
+    tables: TildaStream,
+    strings: Range<usize>,
+    user_strings: Range<usize>,
+    blobs: Range<usize>,
+    guids: Range<usize>,
+    diagnostics: Vec<Violation>,
+    hierarchy: Hierarchy,
+}
+
+/// Relations between TypeDefs that the tables only store from one side, indexed by RID - 1.
+struct Hierarchy {
+    /// Owning TypeDef of each MethodDef
+    method_owners: Vec<u32>,
+    /// Owning TypeDef of each Field
+    field_owners: Vec<u32>,
+    /// Enclosing TypeDef of each TypeDef, 0 for top-level types
+    enclosing_types: Vec<u32>,
+}
+
+impl Hierarchy {
+    fn new(tables: &TildaStream) -> Self {
+        let mut method_owners = vec![0; tables.methods.len()];
+        let mut field_owners = vec![0; tables.fields.len()];
+        let mut enclosing_types = vec![0; tables.type_defs.len()];
+        let slot = |owners: &mut Vec<u32>, rid: u32, owner: u32| {
+            if let Some(x) = owners.get_mut((rid as usize).wrapping_sub(1)) {
+                *x = owner;
+            }
+        };
+        for ty in 1..=tables.type_defs.len() as u32 {
+            for method in tables.type_methods(ty) {
+                slot(&mut method_owners, method, ty);
+            }
+            for field in tables.type_fields(ty) {
+                slot(&mut field_owners, field, ty);
+            }
+        }
+        for nested in &tables.nested_classes {
+            slot(&mut enclosing_types, nested.nested_class, nested.enclosing_class);
+        }
+        Self {
+            method_owners,
+            field_owners,
+            enclosing_types,
+        }
+    }
+
+    fn get(rids: &[u32], rid: u32) -> u32 {
+        rids.get((rid as usize).wrapping_sub(1)).cloned().unwrap_or(0)
+    }
+}
+
+/// Images are loaded leniently by default, `*_with` constructors take the validation mode.
+impl Assembly<'static> {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_path_with(path, ValidationMode::Lenient)
+    }
+
+    pub fn from_path_with<P: AsRef<Path>>(path: P, mode: ValidationMode) -> Result<Self, Error> {
+        Self::from_vec_with(std::fs::read(path)?, mode)
+    }
+
+    pub fn from_vec(data: Vec<u8>) -> Result<Self, Error> {
+        Self::from_vec_with(data, ValidationMode::Lenient)
+    }
+
+    pub fn from_vec_with(data: Vec<u8>, mode: ValidationMode) -> Result<Self, Error> {
+        Self::parse(Cow::Owned(data), mode)
+    }
+}
+
+impl<'a> Assembly<'a> {
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, Error> {
+        Self::from_bytes_with(data, ValidationMode::Lenient)
+    }
+
+    pub fn from_bytes_with(data: &'a [u8], mode: ValidationMode) -> Result<Self, Error> {
+        Self::parse(Cow::Borrowed(data), mode)
+    }
+
+    fn parse(data: Cow<'a, [u8]>, mode: ValidationMode) -> Result<Self, Error> {
+        let file = &*data;
+        let header = Header::parse(file)?;
+        let optional_header = header.optional_header.ok_or(Error::NotManaged)?;
+        let file_alignment = optional_header.windows_fields.file_alignment;
+        // goblin masks offsets with `file_alignment - 1` and would underflow on 0
+        if !file_alignment.is_power_of_two() {
+            return Err(Error::Pe(goblin::error::Error::Malformed(format!(
+                "File alignment 0x{:x} is not a power of two",
+                file_alignment
+            ))));
+        }
+        // Only the section table is needed, `PE::parse` would also read imports, exports and debug data
+        let offset = &mut (header.dos_header.pe_pointer as usize
+            + SIZEOF_PE_MAGIC
+            + SIZEOF_COFF_HEADER
+            + header.coff_header.size_of_optional_header as usize);
+        let sections = header.coff_header.sections(file, offset)?;
+        let machine = header.coff_header.machine;
+        let pe32_plus = optional_header.standard_fields.magic == MAGIC_64;
+        // Managed images of any machine type are recognized by the CLR runtime header directory
+        let cli_header = optional_header
+            .data_directories
+            .get_clr_runtime_header()
+            .filter(|x| x.virtual_address != 0 && x.size != 0)
+            .ok_or(Error::NotManaged)?;
+
+        let cli_header: CliHeader = get_data(file, &sections, cli_header, file_alignment)?;
+
+        let rva = cli_header.metadata.virtual_address;
+        let metadata_root_offset =
+            find_offset(rva as usize, &sections, file_alignment).ok_or(Error::UnmappedRva(rva))?;
+        let metadata_size = cli_header.metadata.size as usize;
+        let metadata_range = metadata_root_offset..metadata_root_offset + metadata_size;
+        let metadata = file.get(metadata_range.clone()).ok_or(Error::OutOfBounds {
+            what: "Metadata directory",
+            offset: metadata_root_offset,
+            size: metadata_size,
+        })?;
+        let root: MetadataRoot =
+            metadata
+                .pread_with(0, scroll::LE)
+                .map_err(|error: Error| Error::InvalidMetadataRoot {
+                    offset: metadata_root_offset,
+                    reason: error.to_string(),
+                })?;
+        let diagnostics = root.validate(metadata.len());
+        if mode == ValidationMode::Strict && !diagnostics.is_empty() {
+            return Err(Error::MetadataViolations {
+                offset: metadata_root_offset,
+                violations: diagnostics,
+            });
+        }
+
+        let stream = |name| {
+            root.stream_range(name)
+                .filter(|x| x.end <= metadata.len())
+                .map(|x| metadata_root_offset + x.start..metadata_root_offset + x.end)
+        };
+        let tables_range = stream("#~")
+            .or_else(|| stream("#-"))
+            .ok_or(Error::MissingStream("#~"))?;
+        let tables: TildaStream = file[tables_range].pread_with(0, scroll::LE)?;
+        let strings = stream("#Strings").ok_or(Error::MissingStream("#Strings"))?;
+        let user_strings = stream("#US").unwrap_or(0..0);
+        let blobs = stream("#Blob").unwrap_or(0..0);
+        let guids = stream("#GUID").unwrap_or(0..0);
+        let hierarchy = Hierarchy::new(&tables);
+
+        Ok(Self {
+            data,
+            machine,
+            pe32_plus,
+            sections,
+            file_alignment,
+            cli_header,
+            metadata: metadata_range,
+            tables,
+            strings,
+            user_strings,
+            blobs,
+            guids,
+            diagnostics,
+            hierarchy,
+        })
+    }
+
+    /// The whole image.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn cli_header(&self) -> &CliHeader {
+        &self.cli_header
+    }
+
+    /// COFF machine type as stored in the image.
+    pub fn machine(&self) -> u16 {
+        self.machine
+    }
+
+    /// Whether the image has a 64-bit (PE32+) optional header.
+    pub fn is_pe32_plus(&self) -> bool {
+        self.pe32_plus
+    }
+
+    /// Simple name from the Assembly table, `None` for modules that aren't an assembly manifest.
+    pub fn name(&self) -> Option<&str> {
+        let row = self.tables.assemblies.first()?;
+        self.strings().get(row.name).ok()
+    }
+
+    pub fn platform(&self) -> Platform {
+        Platform::new(self.machine, self.cli_header.flags)
+    }
+
+    pub fn metadata_root(&self) -> Result<MetadataRoot<'_>, Error> {
+        self.data[self.metadata.clone()].pread_with(0, scroll::LE)
+    }
+
+    /// Violations of ECMA-335 found in the metadata root, always empty for images loaded in strict mode.
+    pub fn diagnostics(&self) -> &[Violation] {
+        &self.diagnostics
+    }
+
+    pub fn tables(&self) -> &TildaStream {
+        &self.tables
+    }
+
+    pub fn strings(&self) -> StringHeap<'_> {
+        StringHeap::new(&self.data[self.strings.clone()])
+    }
+
+    pub fn user_strings(&self) -> UserStringHeap<'_> {
+        UserStringHeap::new(&self.data[self.user_strings.clone()])
+    }
+
+    pub fn blobs(&self) -> BlobHeap<'_> {
+        BlobHeap::new(&self.data[self.blobs.clone()])
+    }
+
+    pub fn guids(&self) -> GuidHeap<'_> {
+        GuidHeap::new(&self.data[self.guids.clone()])
+    }
+
+    /// Maps a relative virtual address to a file offset.
+    pub fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        find_offset(rva as usize, &self.sections, self.file_alignment)
+    }
+
+    /// Image bytes starting at `rva`.
+    pub fn data_at_rva(&self, rva: u32) -> Option<&[u8]> {
+        self.data.get(self.rva_to_offset(rva)?..)
+    }
+
+    /// Contents of one of the CLI header directories, `None` if the image doesn't have it.
+    pub fn directory(&self, directory: &DataDirectory) -> Result<Option<&[u8]>, Error> {
+        if directory.virtual_address == 0 || directory.size == 0 {
+            return Ok(None);
+        }
+        let offset = self
+            .rva_to_offset(directory.virtual_address)
+            .ok_or(Error::UnmappedRva(directory.virtual_address))?;
+        let size = directory.size as usize;
+        self.data
+            .get(offset..offset + size)
+            .map(Some)
+            .ok_or(Error::OutOfBounds {
+                what: "Directory",
+                offset,
+                size,
+            })
+    }
+
+    pub fn resources(&self) -> Result<Option<&[u8]>, Error> {
+        self.directory(&self.cli_header.resources)
+    }
+
+    pub fn strong_name_signature(&self) -> Result<Option<&[u8]>, Error> {
+        self.directory(&self.cli_header.strong_name_signature)
+    }
+
+    pub fn code_manager_table(&self) -> Result<Option<&[u8]>, Error> {
+        self.directory(&self.cli_header.code_manager_table)
+    }
+
+    pub fn export_address_table_jumps(&self) -> Result<Option<&[u8]>, Error> {
+        self.directory(&self.cli_header.export_address_table_jumps)
+    }
+
+    pub fn managed_native_header(&self) -> Result<Option<&[u8]>, Error> {
+        self.directory(&self.cli_header.managed_native_header)
+    }
+
+    pub fn vtable_fixups(&self) -> Result<Vec<VTableFixup>, Error> {
+        let data = match self.directory(&self.cli_header.vtable_fixups)? {
+            Some(data) => data,
+            None => return Ok(Vec::new()),
+        };
+        let offset = &mut 0;
+        let mut fixups = Vec::with_capacity(data.len() / 8);
+        while *offset + 8 <= data.len() {
+            fixups.push(data.gread_with(offset, scroll::LE)?);
+        }
+        Ok(fixups)
+    }
+
+    /// Bytes of a resource embedded in this image, `None` for resources living in other files.
+    pub fn manifest_resource_data(&self, resource: &ManifestResource) -> Result<Option<&[u8]>, Error> {
+        if !resource.implementation.is_null() {
+            return Ok(None);
+        }
+        let resources = self.resources()?.ok_or(Error::OutOfBounds {
+            what: "Resource",
+            offset: resource.offset as usize,
+            size: 4,
+        })?;
+        // Each resource is prefixed with its length
+        let offset = &mut (resource.offset as usize);
+        let length = resources.gread_with::<u32>(offset, scroll::LE)? as usize;
+        resources
+            .get(*offset..*offset + length)
+            .map(Some)
+            .ok_or(Error::OutOfBounds {
+                what: "Resource",
+                offset: *offset,
+                size: length,
+            })
+    }
+
+    pub fn resolve(&self, token: Token) -> Result<Row<'_>, Error> {
+        self.tables.resolve(token)
+    }
+
+    /// The entry point method, `None` for libraries, native entry points and entry points in another module.
+    pub fn entry_point(&self) -> Result<Option<MethodDefinition<'_>>, Error> {
+        let token = match self.cli_header.entry_point_token() {
+            Some(value) => Token::decode(value)?,
+            None => return Ok(None),
+        };
+        match token.kind {
+            TokenKind::Table(TableId::MethodDef) => Ok(Some(self.method(token.rid).ok_or(Error::TokenOutOfRange {
+                token,
+                rows: self.tables.methods.len(),
+            })?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn type_definition(&self, rid: u32) -> Option<TypeDefinition<'_>> {
+        let row = self.tables.type_defs.get(rid.checked_sub(1)? as usize)?;
+        Some(TypeDefinition {
+            assembly: self,
+            rid,
+            row,
+        })
+    }
+
+    pub fn type_reference(&self, rid: u32) -> Option<TypeReference<'_>> {
+        let row = self.tables.type_refs.get(rid.checked_sub(1)? as usize)?;
+        Some(TypeReference {
+            assembly: self,
+            rid,
+            row,
+        })
+    }
+
+    pub fn type_specification(&self, rid: u32) -> Option<TypeSpecification<'_>> {
+        let row = self.tables.type_specs.get(rid.checked_sub(1)? as usize)?;
+        Some(TypeSpecification {
+            assembly: self,
+            rid,
+            row,
+        })
+    }
+
+    /// The row a TypeDefOrRef coded index points to, `None` for a null index.
+    pub fn type_handle(&self, index: TypeDefOrRef) -> Result<Option<TypeHandle<'_>>, Error> {
+        if index.is_null() {
+            return Ok(None);
+        }
+        let handle = match index {
+            TypeDefOrRef::TypeDef(rid) => self.type_definition(rid).map(TypeHandle::Definition),
+            TypeDefOrRef::TypeRef(rid) => self.type_reference(rid).map(TypeHandle::Reference),
+            TypeDefOrRef::TypeSpec(rid) => self.type_specification(rid).map(TypeHandle::Specification),
+        };
+        handle.map(Some).ok_or(Error::TokenOutOfRange {
+            token: Token::new(TokenKind::Table(index.table()), index.row()),
+            rows: self.tables.row_count(index.table()),
+        })
+    }
+
+    /// The TypeDef named `full_name`, nested types as `Namespace.Outer+Inner`.
+    pub fn find_type(&self, full_name: &str) -> Option<TypeDefinition<'_>> {
+        self.types().find(|x| x.full_name().ok().as_deref() == Some(full_name))
+    }
+
+    pub fn method(&self, rid: u32) -> Option<MethodDefinition<'_>> {
+        let row = self.tables.methods.get(rid.checked_sub(1)? as usize)?;
+        Some(MethodDefinition {
+            assembly: self,
+            rid,
+            row,
+        })
+    }
+
+    pub fn field(&self, rid: u32) -> Option<FieldDefinition<'_>> {
+        let row = self.tables.fields.get(rid.checked_sub(1)? as usize)?;
+        Some(FieldDefinition {
+            assembly: self,
+            rid,
+            row,
+        })
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = TypeDefinition<'_>> {
+        (1..=self.tables.type_defs.len() as u32).filter_map(move |rid| self.type_definition(rid))
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = MethodDefinition<'_>> {
+        (1..=self.tables.methods.len() as u32).filter_map(move |rid| self.method(rid))
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldDefinition<'_>> {
+        (1..=self.tables.fields.len() as u32).filter_map(move |rid| self.field(rid))
+    }
+
+    pub fn custom_attributes(&self) -> impl Iterator<Item = Attribute<'_>> {
+        self.tables
+            .custom_attributes
+            .iter()
+            .enumerate()
+            .map(move |(i, row)| Attribute {
+                assembly: self,
+                rid: i as u32 + 1,
+                row,
+            })
+    }
+
+    /// Custom attributes applied to `parent`.
+    pub fn custom_attributes_of(&self, parent: HasCustomAttribute) -> impl Iterator<Item = Attribute<'_>> {
+        self.custom_attributes().filter(move |x| x.row.parent == parent)
+    }
+
+    /// Generic parameters of a generic type or method.
+    pub fn generic_params_of(&self, owner: TypeOrMethodDef) -> impl Iterator<Item = GenericParameter<'_>> {
+        self.tables
+            .generic_params
+            .iter()
+            .enumerate()
+            .filter(move |(_, row)| row.owner == owner)
+            .map(move |(i, row)| GenericParameter {
+                assembly: self,
+                rid: i as u32 + 1,
+                row,
+            })
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct TypeDefinition<'a> {
+    assembly: &'a Assembly<'a>,
+    pub rid: u32,
+    pub row: &'a TypeDef,
+}
+
+impl<'a> TypeDefinition<'a> {
+    pub fn token(&self) -> Token {
+        Token::new(TokenKind::Table(TableId::TypeDef), self.rid)
+    }
+
+    pub fn name(&self) -> Result<&'a str, Error> {
+        self.assembly.strings().get(self.row.type_name)
+    }
+
+    pub fn namespace(&self) -> Result<&'a str, Error> {
+        self.assembly.strings().get(self.row.type_namespace)
+    }
+
+    /// Name qualified with the namespace and the enclosing types, e.g. `Namespace.Outer+Inner`.
+    pub fn full_name(&self) -> Result<String, Error> {
+        let mut names = Vec::new();
+        let mut ty = Some(*self);
+        while let Some(x) = ty {
+            if names.len() as u32 > MAX_DEPTH {
+                return Err(Error::NestedTooDeeply(self.token()));
+            }
+            names.push(qualified_name(x.namespace()?, x.name()?));
+            ty = x.enclosing_type();
+        }
+        names.reverse();
+        Ok(names.join("+"))
+    }
+
+    /// The type this one is nested in.
+    pub fn enclosing_type(&self) -> Option<TypeDefinition<'a>> {
+        let rid = Hierarchy::get(&self.assembly.hierarchy.enclosing_types, self.rid);
+        self.assembly.type_definition(rid)
+    }
+
+    pub fn nested_types(&self) -> impl Iterator<Item = TypeDefinition<'a>> + 'a {
+        let rid = self.rid;
+        let assembly = self.assembly;
+        assembly
+            .types()
+            .filter(move |x| Hierarchy::get(&assembly.hierarchy.enclosing_types, x.rid) == rid)
+    }
+
+    /// The base type, `None` for interfaces, `System.Object` and `<Module>`.
+    pub fn base_type(&self) -> Result<Option<TypeHandle<'a>>, Error> {
+        self.assembly.type_handle(self.row.extends)
+    }
+
+    /// Interfaces this type declares to implement, not including those inherited from base types.
+    pub fn interfaces(&self) -> impl Iterator<Item = Result<TypeHandle<'a>, Error>> + 'a {
+        let rid = self.rid;
+        let assembly = self.assembly;
+        assembly
+            .tables
+            .interface_impls
+            .iter()
+            .filter(move |x| x.class == rid)
+            .filter_map(move |x| assembly.type_handle(x.interface).transpose())
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = MethodDefinition<'a>> + 'a {
+        let assembly = self.assembly;
+        assembly
+            .tables
+            .type_methods(self.rid)
+            .filter_map(move |rid| assembly.method(rid))
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldDefinition<'a>> + 'a {
+        let assembly = self.assembly;
+        assembly
+            .tables
+            .type_fields(self.rid)
+            .filter_map(move |rid| assembly.field(rid))
+    }
+
+    pub fn custom_attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a {
+        self.assembly
+            .custom_attributes_of(HasCustomAttribute::TypeDef(self.rid))
+    }
+
+    pub fn generic_params(&self) -> impl Iterator<Item = GenericParameter<'a>> + 'a {
+        self.assembly.generic_params_of(TypeOrMethodDef::TypeDef(self.rid))
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct MethodDefinition<'a> {
+    assembly: &'a Assembly<'a>,
+    pub rid: u32,
+    pub row: &'a MethodDef,
+}
+
+impl<'a> MethodDefinition<'a> {
+    pub fn token(&self) -> Token {
+        Token::new(TokenKind::Table(TableId::MethodDef), self.rid)
+    }
+
+    pub fn name(&self) -> Result<&'a str, Error> {
+        self.assembly.strings().get(self.row.name)
+    }
+
+    /// Raw signature blob.
+    pub fn signature_blob(&self) -> Result<&'a [u8], Error> {
+        self.assembly.blobs().get(self.row.signature)
+    }
+
+    pub fn signature(&self) -> Result<MethodSignature, Error> {
+        self.signature_blob()?.pread_with(0, scroll::LE)
+    }
+
+    /// The CIL body, `None` for abstract, extern and runtime-implemented methods.
+    pub fn body(&self) -> Result<Option<MethodBody<'a>>, Error> {
+        if self.row.rva == 0 {
+            return Ok(None);
+        }
+        let data = self
+            .assembly
+            .data_at_rva(self.row.rva)
+            .ok_or(Error::UnmappedRva(self.row.rva))?;
+        Ok(Some(data.pread_with(0, scroll::LE)?))
+    }
+
+    /// Decoded CIL of the body with token operands resolved, empty for methods without a body.
+    pub fn instructions(&self) -> Result<Vec<Instruction<'a>>, Error> {
+        match self.body()? {
+            Some(body) => decode_instructions(body.code, Some(&self.assembly.tables)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Local variables declared by the body.
+    pub fn locals(&self) -> Result<Option<LocalVarSignature>, Error> {
+        let token = match self.body()? {
+            Some(ref body) if body.local_var_sig_token != 0 => Token::decode(body.local_var_sig_token)?,
+            _ => return Ok(None),
+        };
+        match self.assembly.resolve(token)? {
+            Row::StandAloneSig(sig) => Ok(Some(
+                self.assembly.blobs().get(sig.signature)?.pread_with(0, scroll::LE)?,
+            )),
+            _ => Err(Error::UnexpectedToken {
+                token,
+                expected: TableId::StandAloneSig,
+            }),
+        }
+    }
+
+    /// The TypeDef this method belongs to, `<Module>` for global methods.
+    pub fn declaring_type(&self) -> Option<TypeDefinition<'a>> {
+        let rid = Hierarchy::get(&self.assembly.hierarchy.method_owners, self.rid);
+        self.assembly.type_definition(rid)
+    }
+
+    pub fn custom_attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a {
+        self.assembly
+            .custom_attributes_of(HasCustomAttribute::MethodDef(self.rid))
+    }
+
+    pub fn generic_params(&self) -> impl Iterator<Item = GenericParameter<'a>> + 'a {
+        self.assembly.generic_params_of(TypeOrMethodDef::MethodDef(self.rid))
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct FieldDefinition<'a> {
+    assembly: &'a Assembly<'a>,
+    pub rid: u32,
+    pub row: &'a Field,
+}
+
+impl<'a> FieldDefinition<'a> {
+    pub fn token(&self) -> Token {
+        Token::new(TokenKind::Table(TableId::Field), self.rid)
+    }
+
+    pub fn name(&self) -> Result<&'a str, Error> {
+        self.assembly.strings().get(self.row.name)
+    }
+
+    /// Raw signature blob.
+    pub fn signature_blob(&self) -> Result<&'a [u8], Error> {
+        self.assembly.blobs().get(self.row.signature)
+    }
+
+    pub fn signature(&self) -> Result<FieldSignature, Error> {
+        self.signature_blob()?.pread_with(0, scroll::LE)
+    }
+
+    /// The TypeDef this field belongs to, `<Module>` for global fields.
+    pub fn declaring_type(&self) -> Option<TypeDefinition<'a>> {
+        let rid = Hierarchy::get(&self.assembly.hierarchy.field_owners, self.rid);
+        self.assembly.type_definition(rid)
+    }
+
+    pub fn custom_attributes(&self) -> impl Iterator<Item = Attribute<'a>> + 'a {
+        self.assembly.custom_attributes_of(HasCustomAttribute::Field(self.rid))
+    }
+}
+
+/// A type parameter of a generic type or method.
+#[derive(Copy, Clone)]
+pub struct GenericParameter<'a> {
+    assembly: &'a Assembly<'a>,
+    pub rid: u32,
+    pub row: &'a GenericParam,
+}
+
+impl<'a> GenericParameter<'a> {
+    pub fn token(&self) -> Token {
+        Token::new(TokenKind::Table(TableId::GenericParam), self.rid)
+    }
+
+    pub fn name(&self) -> Result<&'a str, Error> {
+        self.assembly.strings().get(self.row.name)
+    }
+
+    /// Index of the parameter in the type or method arguments, what VAR and MVAR refer to it by.
+    pub fn number(&self) -> u16 {
+        self.row.number
+    }
+
+    /// Base type and interfaces the type argument must derive from or implement.
+    pub fn constraints(&self) -> impl Iterator<Item = TypeDefOrRef> + 'a {
+        let rid = self.rid;
+        self.assembly
+            .tables
+            .generic_param_constraints
+            .iter()
+            .filter(move |x| x.owner == rid)
+            .map(|x| x.constraint)
+    }
+}
+
+/// A type defined in another module or assembly.
+#[derive(Copy, Clone)]
+pub struct TypeReference<'a> {
+    assembly: &'a Assembly<'a>,
+    pub rid: u32,
+    pub row: &'a TypeRef,
+}
+
+impl<'a> TypeReference<'a> {
+    pub fn token(&self) -> Token {
+        Token::new(TokenKind::Table(TableId::TypeRef), self.rid)
+    }
+
+    pub fn name(&self) -> Result<&'a str, Error> {
+        self.assembly.strings().get(self.row.type_name)
+    }
+
+    pub fn namespace(&self) -> Result<&'a str, Error> {
+        self.assembly.strings().get(self.row.type_namespace)
+    }
+
+    /// Where the type is defined: an AssemblyRef, a ModuleRef, this module or, for nested types, the enclosing TypeRef.
+    pub fn resolution_scope(&self) -> ResolutionScope {
+        self.row.resolution_scope
+    }
+
+    /// The reference of the enclosing type for nested types.
+    pub fn enclosing_type(&self) -> Result<Option<TypeReference<'a>>, Error> {
+        match self.row.resolution_scope {
+            ResolutionScope::TypeRef(rid) => {
+                self.assembly
+                    .type_reference(rid)
+                    .map(Some)
+                    .ok_or(Error::TokenOutOfRange {
+                        token: Token::new(TokenKind::Table(TableId::TypeRef), rid),
+                        rows: self.assembly.tables.type_refs.len(),
+                    })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Name qualified with the namespace and the enclosing types, e.g. `Namespace.Outer+Inner`.
+    pub fn full_name(&self) -> Result<String, Error> {
+        let mut names = Vec::new();
+        let mut ty = Some(*self);
+        while let Some(x) = ty {
+            if names.len() as u32 > MAX_DEPTH {
+                return Err(Error::NestedTooDeeply(self.token()));
+            }
+            names.push(qualified_name(x.namespace()?, x.name()?));
+            ty = x.enclosing_type()?;
+        }
+        names.reverse();
+        Ok(names.join("+"))
+    }
+}
+
+/// A constructed type: a generic instantiation, an array, a pointer and so on.
+#[derive(Copy, Clone)]
+pub struct TypeSpecification<'a> {
+    assembly: &'a Assembly<'a>,
+    pub rid: u32,
+    pub row: &'a TypeSpec,
+}
+
+impl<'a> TypeSpecification<'a> {
+    pub fn token(&self) -> Token {
+        Token::new(TokenKind::Table(TableId::TypeSpec), self.rid)
+    }
+
+    /// Raw signature blob.
+    pub fn signature_blob(&self) -> Result<&'a [u8], Error> {
+        self.assembly.blobs().get(self.row.signature)
+    }
+
+    pub fn signature(&self) -> Result<Type, Error> {
+        self.signature_blob()?.pread_with(0, scroll::LE)
+    }
+}
+
+/// The row a TypeDefOrRef coded index points to.
+#[derive(Copy, Clone)]
+pub enum TypeHandle<'a> {
+    Definition(TypeDefinition<'a>),
+    Reference(TypeReference<'a>),
+    Specification(TypeSpecification<'a>),
+}
+
+impl<'a> TypeHandle<'a> {
+    pub fn token(&self) -> Token {
+        match self {
+            TypeHandle::Definition(x) => x.token(),
+            TypeHandle::Reference(x) => x.token(),
+            TypeHandle::Specification(x) => x.token(),
+        }
+    }
+
+    /// Full name of a TypeDef or TypeRef, `None` for TypeSpecs which are signatures rather than names.
+    pub fn full_name(&self) -> Result<Option<String>, Error> {
+        match self {
+            TypeHandle::Definition(x) => x.full_name().map(Some),
+            TypeHandle::Reference(x) => x.full_name().map(Some),
+            TypeHandle::Specification(_) => Ok(None),
+        }
+    }
+}
+
+pub(crate) fn qualified_name(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", namespace, name)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Attribute<'a> {
+    assembly: &'a Assembly<'a>,
+    pub rid: u32,
+    pub row: &'a CustomAttribute,
+}
+
+impl<'a> Attribute<'a> {
+    pub fn token(&self) -> Token {
+        Token::new(TokenKind::Table(TableId::CustomAttribute), self.rid)
+    }
+
+    pub fn parent(&self) -> HasCustomAttribute {
+        self.row.parent
+    }
+
+    /// Attribute constructor, a MethodDef or a MemberRef.
+    pub fn constructor(&self) -> CustomAttributeType {
+        self.row.attribute_type
+    }
+
+    /// Raw value blob with the fixed and named arguments.
+    pub fn value(&self) -> Result<&'a [u8], Error> {
+        self.assembly.blobs().get(self.row.value)
+    }
+}
@@ -0,0 +1,106 @@
+use goblin::pe::data_directories::DataDirectory;
+use scroll::{self, Pread};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// The 72-byte CLI header (ECMA-335 II.25.3.3) pointed to by the CLR runtime header directory.
+#[repr(C)]
+#[derive(Debug, Pread, Serialize)]
+pub struct CliHeader {
+    pub cb: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    #[serde(serialize_with = "serialize_directory")]
+    pub metadata: DataDirectory,
+    pub flags: u32,
+    /// MethodDef or File token, or an RVA when the entry point is native
+    pub entry_point_token: u32,
+    #[serde(serialize_with = "serialize_directory")]
+    pub resources: DataDirectory,
+    #[serde(serialize_with = "serialize_directory")]
+    pub strong_name_signature: DataDirectory,
+    #[serde(serialize_with = "serialize_directory")]
+    pub code_manager_table: DataDirectory,
+    #[serde(serialize_with = "serialize_directory")]
+    pub vtable_fixups: DataDirectory,
+    #[serde(serialize_with = "serialize_directory")]
+    pub export_address_table_jumps: DataDirectory,
+    /// ReadyToRun header in precompiled images
+    #[serde(serialize_with = "serialize_directory")]
+    pub managed_native_header: DataDirectory,
+}
+
+/// goblin's directories aren't serializable, written as `{"rva": .., "size": ..}`.
+pub fn serialize_directory<S: Serializer>(directory: &DataDirectory, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("DataDirectory", 2)?;
+    state.serialize_field("rva", &directory.virtual_address)?;
+    state.serialize_field("size", &directory.size)?;
+    state.end()
+}
+
+pub const COMIMAGE_FLAGS_ILONLY: u32 = 0x0000_0001;
+pub const COMIMAGE_FLAGS_32BITREQUIRED: u32 = 0x0000_0002;
+pub const COMIMAGE_FLAGS_IL_LIBRARY: u32 = 0x0000_0004;
+pub const COMIMAGE_FLAGS_STRONGNAMESIGNED: u32 = 0x0000_0008;
+pub const COMIMAGE_FLAGS_NATIVE_ENTRYPOINT: u32 = 0x0000_0010;
+pub const COMIMAGE_FLAGS_TRACKDEBUGDATA: u32 = 0x0001_0000;
+pub const COMIMAGE_FLAGS_32BITPREFERRED: u32 = 0x0002_0000;
+
+impl CliHeader {
+    pub fn is_il_only(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_ILONLY != 0
+    }
+
+    pub fn is_32bit_required(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_32BITREQUIRED != 0
+    }
+
+    pub fn is_32bit_preferred(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_32BITPREFERRED != 0
+    }
+
+    pub fn is_strong_name_signed(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_STRONGNAMESIGNED != 0
+    }
+
+    pub fn has_native_entry_point(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_NATIVE_ENTRYPOINT != 0
+    }
+
+    pub fn track_debug_data(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_TRACKDEBUGDATA != 0
+    }
+
+    /// Entry point token, `None` if there is no entry point or it is native code.
+    pub fn entry_point_token(&self) -> Option<u32> {
+        if self.has_native_entry_point() || self.entry_point_token == 0 {
+            None
+        } else {
+            Some(self.entry_point_token)
+        }
+    }
+
+    /// RVA of the native entry point in mixed-mode images.
+    pub fn entry_point_rva(&self) -> Option<u32> {
+        if self.has_native_entry_point() {
+            Some(self.entry_point_token)
+        } else {
+            None
+        }
+    }
+}
+
+/// Entry of the VTableFixups directory: `count` slots of `kind` at `rva` to be patched with method addresses.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pread)]
+pub struct VTableFixup {
+    pub rva: u32,
+    pub count: u16,
+    pub kind: u16,
+}
+
+pub const COR_VTABLE_32BIT: u16 = 0x01;
+pub const COR_VTABLE_64BIT: u16 = 0x02;
+pub const COR_VTABLE_FROM_UNMANAGED: u16 = 0x04;
+pub const COR_VTABLE_FROM_UNMANAGED_RETAIN_APPDOMAIN: u16 = 0x08;
+pub const COR_VTABLE_CALL_MOST_DERIVED: u16 = 0x10;
@@ -0,0 +1,208 @@
+//! Coded indices (ECMA-335 II.24.2.6): a single column that may point into one of
+//! several tables, the low `tag_bits` bits selecting the table.
+
+use crate::error::Error;
+use crate::tables::{TableContext, TableId};
+use scroll::ctx::TryFromCtx;
+use serde::Serialize;
+
+/// Shape of a coded index column, used to compute its width in a particular image.
+pub struct CodedIndexKind {
+    pub tag_bits: u32,
+    pub tables: &'static [TableId],
+}
+
+macro_rules! coded_index {
+    ($name:ident, $kind:ident, $tag_bits:expr, { $($tag:expr => $table:ident),+ $(,)* }) => {
+        #[allow(clippy::enum_variant_names)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
+        pub enum $name {
+            $($table(u32),)+
+        }
+
+        pub const $kind: CodedIndexKind = CodedIndexKind {
+            tag_bits: $tag_bits,
+            tables: &[$(TableId::$table),+],
+        };
+
+        impl $name {
+            pub fn decode(value: u32) -> Result<Self, Error> {
+                let row = value >> $tag_bits;
+                match value & ((1 << $tag_bits) - 1) {
+                    $($tag => Ok($name::$table(row)),)+
+                    tag => Err(Error::InvalidCodedIndex {
+                        kind: stringify!($name),
+                        tag,
+                    }),
+                }
+            }
+
+            pub fn table(&self) -> TableId {
+                match *self {
+                    $($name::$table(_) => TableId::$table,)+
+                }
+            }
+
+            /// 1-based row in `table()`, 0 when the index is null.
+            pub fn row(&self) -> u32 {
+                match *self {
+                    $($name::$table(row) => row,)+
+                }
+            }
+
+            pub fn is_null(&self) -> bool {
+                self.row() == 0
+            }
+        }
+
+        impl<'a> TryFromCtx<'a, &TableContext> for $name {
+            type Error = Error;
+            fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+                let offset = &mut 0;
+                let value = ctx.read_coded_index(src, offset, &$kind)?;
+                Ok((Self::decode(value)?, *offset))
+            }
+        }
+    };
+}
+
+coded_index!(TypeDefOrRef, TYPE_DEF_OR_REF, 2, {
+    0 => TypeDef,
+    1 => TypeRef,
+    2 => TypeSpec,
+});
+
+coded_index!(HasConstant, HAS_CONSTANT, 2, {
+    0 => Field,
+    1 => Param,
+    2 => Property,
+});
+
+coded_index!(HasCustomAttribute, HAS_CUSTOM_ATTRIBUTE, 5, {
+    0 => MethodDef,
+    1 => Field,
+    2 => TypeRef,
+    3 => TypeDef,
+    4 => Param,
+    5 => InterfaceImpl,
+    6 => MemberRef,
+    7 => Module,
+    8 => DeclSecurity,
+    9 => Property,
+    10 => Event,
+    11 => StandAloneSig,
+    12 => ModuleRef,
+    13 => TypeSpec,
+    14 => Assembly,
+    15 => AssemblyRef,
+    16 => File,
+    17 => ExportedType,
+    18 => ManifestResource,
+    19 => GenericParam,
+    20 => GenericParamConstraint,
+    21 => MethodSpec,
+});
+
+coded_index!(HasFieldMarshal, HAS_FIELD_MARSHAL, 1, {
+    0 => Field,
+    1 => Param,
+});
+
+coded_index!(HasDeclSecurity, HAS_DECL_SECURITY, 2, {
+    0 => TypeDef,
+    1 => MethodDef,
+    2 => Assembly,
+});
+
+coded_index!(MemberRefParent, MEMBER_REF_PARENT, 3, {
+    0 => TypeDef,
+    1 => TypeRef,
+    2 => ModuleRef,
+    3 => MethodDef,
+    4 => TypeSpec,
+});
+
+coded_index!(HasSemantics, HAS_SEMANTICS, 1, {
+    0 => Event,
+    1 => Property,
+});
+
+coded_index!(MethodDefOrRef, METHOD_DEF_OR_REF, 1, {
+    0 => MethodDef,
+    1 => MemberRef,
+});
+
+coded_index!(MemberForwarded, MEMBER_FORWARDED, 1, {
+    0 => Field,
+    1 => MethodDef,
+});
+
+coded_index!(Implementation, IMPLEMENTATION, 2, {
+    0 => File,
+    1 => AssemblyRef,
+    2 => ExportedType,
+});
+
+// Tags 0, 1 and 4 are reserved and never used.
+coded_index!(CustomAttributeType, CUSTOM_ATTRIBUTE_TYPE, 3, {
+    2 => MethodDef,
+    3 => MemberRef,
+});
+
+coded_index!(ResolutionScope, RESOLUTION_SCOPE, 2, {
+    0 => Module,
+    1 => ModuleRef,
+    2 => AssemblyRef,
+    3 => TypeRef,
+});
+
+coded_index!(TypeOrMethodDef, TYPE_OR_METHOD_DEF, 1, {
+    0 => TypeDef,
+    1 => MethodDef,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scroll::Pread;
+
+    #[test]
+    fn decode_tags() {
+        assert_eq!(TypeDefOrRef::decode(0x0d).unwrap(), TypeDefOrRef::TypeRef(3));
+        assert_eq!(TypeDefOrRef::decode(0x0e).unwrap(), TypeDefOrRef::TypeSpec(3));
+        assert!(TypeDefOrRef::decode(0x0f).is_err());
+        assert_eq!(
+            HasCustomAttribute::decode(21 | 7 << 5).unwrap(),
+            HasCustomAttribute::MethodSpec(7)
+        );
+        assert_eq!(
+            CustomAttributeType::decode(0x1b).unwrap(),
+            CustomAttributeType::MemberRef(3)
+        );
+        assert!(CustomAttributeType::decode(0x19).is_err());
+
+        let index = ResolutionScope::decode(0x0a).unwrap();
+        assert_eq!((index.table(), index.row()), (TableId::AssemblyRef, 2));
+        assert!(TypeOrMethodDef::decode(0x01).unwrap().is_null());
+    }
+
+    #[test]
+    fn widths_follow_the_largest_table() {
+        // 2 tag bits leave 14 bits for the row in a 2-byte index
+        let ctx = TableContext::new(0, &[(TableId::TypeSpec as u32, (1 << 14) - 1)]);
+        assert_eq!(ctx.coded_index_size(&TYPE_DEF_OR_REF), 2);
+        let ctx = TableContext::new(0, &[(TableId::TypeSpec as u32, 1 << 14)]);
+        assert_eq!(ctx.coded_index_size(&TYPE_DEF_OR_REF), 4);
+        assert_eq!(ctx.coded_index_size(&HAS_CONSTANT), 2);
+
+        // 5 tag bits leave 11
+        let ctx = TableContext::new(0, &[(TableId::MethodSpec as u32, 1 << 11)]);
+        assert_eq!(ctx.coded_index_size(&HAS_CUSTOM_ATTRIBUTE), 4);
+        let index: HasCustomAttribute = [0x35, 0x01, 0x00, 0x00][..].pread_with(0, &ctx).unwrap();
+        assert_eq!(index, HasCustomAttribute::MethodSpec(9));
+
+        let ctx = TableContext::new(0, &[]);
+        let index: HasCustomAttribute = [0x35, 0x01][..].pread_with(0, &ctx).unwrap();
+        assert_eq!(index, HasCustomAttribute::MethodSpec(9));
+    }
+}
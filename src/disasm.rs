@@ -0,0 +1,1018 @@
+//! ildasm-style IL listing of an assembly.
+
+use crate::assembly::{Assembly, FieldDefinition, MethodDefinition, TypeDefinition, MAX_DEPTH};
+use crate::coded_index::{
+    CustomAttributeType, HasCustomAttribute, MemberRefParent, MethodDefOrRef, ResolutionScope, TypeDefOrRef,
+    TypeOrMethodDef,
+};
+use crate::error::Error;
+use crate::instruction::{Instruction, OpCode, Operand, OperandKind, Prefix};
+use crate::method_body::{ExceptionClause, ExceptionClauseKind};
+use crate::signature::{
+    CallingConvention, CustomMod, FieldSignature, MethodSignature, MethodSpecSignature, Param, Type, SIG_FIELD,
+    SIG_KIND_MASK,
+};
+use crate::tables::{MemberRef, TableId};
+use crate::token::{get_row, Token, TokenKind};
+use scroll::{self, Pread};
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Width of the mnemonic column, as ildasm pads it.
+const MNEMONIC_WIDTH: usize = 11;
+
+const TYPE_VISIBILITY: [&str; 8] = [
+    "private",
+    "public",
+    "nested public",
+    "nested private",
+    "nested family",
+    "nested assembly",
+    "nested famandassem",
+    "nested famorassem",
+];
+
+const MEMBER_ACCESS: [&str; 8] = [
+    "privatescope",
+    "private",
+    "famandassem",
+    "assembly",
+    "family",
+    "famorassem",
+    "public",
+    "",
+];
+
+/// Quotes a name the way ilasm expects when it isn't a plain identifier.
+fn ident(name: &str) -> Cow<'_, str> {
+    let is_plain = |x: char| x.is_alphanumeric() || "_$@`?.".contains(x);
+    let starts_plain = name
+        .chars()
+        .next()
+        .filter(|&x| !x.is_numeric() && is_plain(x))
+        .is_some();
+    if starts_plain && name.chars().all(is_plain) {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'")))
+    }
+}
+
+fn quote_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02X}", x)).collect::<Vec<_>>().join(" ")
+}
+
+fn flags(value: u32, names: &[(u32, &str)]) -> String {
+    names
+        .iter()
+        .filter(|&&(flag, _)| value & flag != 0)
+        .map(|&(_, name)| name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn label(offset: u32) -> String {
+    format!("IL_{:04x}", offset)
+}
+
+fn table_token(table: TableId, rid: u32) -> Token {
+    Token::new(TokenKind::Table(table), rid)
+}
+
+fn table_row<T>(rows: &[T], table: TableId, rid: u32) -> Result<&T, Error> {
+    get_row(rows, table_token(table, rid))
+}
+
+fn line<W: Write>(out: &mut W, indent: usize, text: &str) -> Result<(), Error> {
+    writeln!(out, "{:indent$}{}", "", text, indent = indent)?;
+    Ok(())
+}
+
+#[derive(Copy, Clone)]
+enum BlockKind {
+    Try,
+    Filter,
+    Handler(ExceptionClauseKind),
+}
+
+/// A protected, filter or handler region of a method body, `start..end` in code offsets.
+#[derive(Copy, Clone)]
+struct Block {
+    start: u32,
+    end: u32,
+    kind: BlockKind,
+}
+
+/// Formats metadata the way ildasm does: type and member names, signatures and instructions.
+pub struct Disassembler<'a> {
+    assembly: &'a Assembly<'a>,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(assembly: &'a Assembly<'a>) -> Self {
+        Self { assembly }
+    }
+
+    fn field_definition(&self, rid: u32) -> Result<FieldDefinition<'a>, Error> {
+        self.assembly.field(rid).ok_or(Error::TokenOutOfRange {
+            token: table_token(TableId::Field, rid),
+            rows: self.assembly.tables().fields.len(),
+        })
+    }
+
+    fn enclosing_type(&self, rid: u32) -> u32 {
+        self.assembly
+            .type_definition(rid)
+            .and_then(|x| x.enclosing_type())
+            .map_or(0, |x| x.rid)
+    }
+
+    fn method_owner(&self, rid: u32) -> u32 {
+        self.assembly
+            .method(rid)
+            .and_then(|x| x.declaring_type())
+            .map_or(0, |x| x.rid)
+    }
+
+    fn generic_params(&self, owner: TypeOrMethodDef) -> Result<String, Error> {
+        let mut params = self
+            .assembly
+            .tables()
+            .generic_params
+            .iter()
+            .filter(|x| x.owner == owner)
+            .collect::<Vec<_>>();
+        if params.is_empty() {
+            return Ok(String::new());
+        }
+        params.sort_by_key(|x| x.number);
+        let names = params
+            .iter()
+            .map(|x| Ok(ident(self.assembly.strings().get(x.name)?).into_owned()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(format!("<{}>", names.join(", ")))
+    }
+
+    /// Full name of a TypeDef, nested types as `Outer/Inner`.
+    pub fn type_def_name(&self, rid: u32) -> Result<String, Error> {
+        let mut names = Vec::new();
+        let mut rid = rid;
+        while rid != 0 {
+            let token = table_token(TableId::TypeDef, rid);
+            if names.len() as u32 > MAX_DEPTH {
+                return Err(Error::NestedTooDeeply(token));
+            }
+            let ty = self.assembly.type_definition(rid).ok_or(Error::TokenOutOfRange {
+                token,
+                rows: self.assembly.tables().type_defs.len(),
+            })?;
+            let namespace = ty.namespace()?;
+            let name = if namespace.is_empty() {
+                ident(ty.name()?).into_owned()
+            } else {
+                ident(&format!("{}.{}", namespace, ty.name()?)).into_owned()
+            };
+            names.push(name);
+            rid = self.enclosing_type(rid);
+        }
+        names.reverse();
+        Ok(names.join("/"))
+    }
+
+    fn type_ref_name(&self, rid: u32, depth: u32) -> Result<String, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::NestedTooDeeply(table_token(TableId::TypeRef, rid)));
+        }
+        let row = table_row(&self.assembly.tables().type_refs, TableId::TypeRef, rid)?;
+        let strings = self.assembly.strings();
+        let namespace = strings.get(row.type_namespace)?;
+        let name = if namespace.is_empty() {
+            ident(strings.get(row.type_name)?).into_owned()
+        } else {
+            ident(&format!("{}.{}", namespace, strings.get(row.type_name)?)).into_owned()
+        };
+        let tables = self.assembly.tables();
+        Ok(match row.resolution_scope {
+            ResolutionScope::AssemblyRef(scope) => {
+                let assembly_ref = table_row(&tables.assembly_refs, TableId::AssemblyRef, scope)?;
+                format!("[{}]{}", ident(strings.get(assembly_ref.name)?), name)
+            }
+            ResolutionScope::ModuleRef(scope) => {
+                let module_ref = table_row(&tables.module_refs, TableId::ModuleRef, scope)?;
+                format!("[.module {}]{}", ident(strings.get(module_ref.name)?), name)
+            }
+            ResolutionScope::TypeRef(scope) => format!("{}/{}", self.type_ref_name(scope, depth + 1)?, name),
+            ResolutionScope::Module(_) => name,
+        })
+    }
+
+    fn type_spec(&self, rid: u32, depth: u32) -> Result<String, Error> {
+        let row = table_row(&self.assembly.tables().type_specs, TableId::TypeSpec, rid)?;
+        let ty: Type = self.assembly.blobs().get(row.signature)?.pread_with(0, scroll::LE)?;
+        self.type_signature(&ty, depth + 1)
+    }
+
+    fn type_name_depth(&self, index: TypeDefOrRef, depth: u32) -> Result<String, Error> {
+        match index {
+            TypeDefOrRef::TypeDef(rid) => self.type_def_name(rid),
+            TypeDefOrRef::TypeRef(rid) => self.type_ref_name(rid, depth),
+            TypeDefOrRef::TypeSpec(rid) => self.type_spec(rid, depth),
+        }
+    }
+
+    /// Name of a TypeDef or TypeRef, the type signature of a TypeSpec.
+    pub fn type_name(&self, index: TypeDefOrRef) -> Result<String, Error> {
+        self.type_name_depth(index, 0)
+    }
+
+    fn custom_mods(&self, custom_mods: &[CustomMod], depth: u32) -> Result<String, Error> {
+        let mut result = String::new();
+        for custom_mod in custom_mods {
+            let keyword = if custom_mod.required { "modreq" } else { "modopt" };
+            result += &format!(" {}({})", keyword, self.type_name_depth(custom_mod.modifier, depth)?);
+        }
+        Ok(result)
+    }
+
+    fn type_signature(&self, ty: &Type, depth: u32) -> Result<String, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::BadSignature {
+                offset: 0,
+                reason: "Type signature is nested too deeply".to_string(),
+            });
+        }
+        let depth = depth + 1;
+        Ok(match ty {
+            Type::Void => "void".to_string(),
+            Type::Boolean => "bool".to_string(),
+            Type::Char => "char".to_string(),
+            Type::I1 => "int8".to_string(),
+            Type::U1 => "uint8".to_string(),
+            Type::I2 => "int16".to_string(),
+            Type::U2 => "uint16".to_string(),
+            Type::I4 => "int32".to_string(),
+            Type::U4 => "uint32".to_string(),
+            Type::I8 => "int64".to_string(),
+            Type::U8 => "uint64".to_string(),
+            Type::R4 => "float32".to_string(),
+            Type::R8 => "float64".to_string(),
+            Type::String => "string".to_string(),
+            Type::Object => "object".to_string(),
+            Type::I => "native int".to_string(),
+            Type::U => "native uint".to_string(),
+            Type::TypedByRef => "typedref".to_string(),
+            Type::ValueType(index) => format!("valuetype {}", self.type_name_depth(*index, depth)?),
+            Type::Class(index) => format!("class {}", self.type_name_depth(*index, depth)?),
+            Type::Ptr(custom_mods, ty) => format!(
+                "{}{}*",
+                self.type_signature(ty, depth)?,
+                self.custom_mods(custom_mods, depth)?
+            ),
+            Type::ByRef(ty) => format!("{}&", self.type_signature(ty, depth)?),
+            Type::FnPtr(signature) => format!("method {}", self.method_signature(signature, "*", None, depth)?),
+            Type::SzArray(custom_mods, ty) => format!(
+                "{}{}[]",
+                self.type_signature(ty, depth)?,
+                self.custom_mods(custom_mods, depth)?
+            ),
+            Type::Array(ty, shape) => {
+                let dimensions = (0..shape.rank as usize)
+                    .map(|i| match (shape.lower_bounds.get(i), shape.sizes.get(i)) {
+                        (Some(lower), Some(&size)) => {
+                            format!("{}...{}", lower, i64::from(*lower) + i64::from(size) - 1)
+                        }
+                        (Some(lower), None) => format!("{}...", lower),
+                        (None, Some(size)) => size.to_string(),
+                        (None, None) => String::new(),
+                    })
+                    .collect::<Vec<_>>();
+                format!("{}[{}]", self.type_signature(ty, depth)?, dimensions.join(","))
+            }
+            Type::GenericInst {
+                is_value_type,
+                generic_type,
+                args,
+            } => format!(
+                "{} {}<{}>",
+                if *is_value_type { "valuetype" } else { "class" },
+                self.type_name_depth(*generic_type, depth)?,
+                self.type_list(args, depth)?
+            ),
+            Type::Var(number) => format!("!{}", number),
+            Type::MVar(number) => format!("!!{}", number),
+        })
+    }
+
+    /// A type as it appears in signatures, e.g. `class [mscorlib]System.String[]`.
+    pub fn type_sig(&self, ty: &Type) -> Result<String, Error> {
+        self.type_signature(ty, 0)
+    }
+
+    fn type_list(&self, types: &[Type], depth: u32) -> Result<String, Error> {
+        Ok(types
+            .iter()
+            .map(|x| self.type_signature(x, depth))
+            .collect::<Result<Vec<_>, Error>>()?
+            .join(", "))
+    }
+
+    fn param(&self, param: &Param, depth: u32) -> Result<String, Error> {
+        Ok(format!(
+            "{}{}{}",
+            self.type_signature(&param.ty, depth)?,
+            if param.by_ref { "&" } else { "" },
+            self.custom_mods(&param.custom_mods, depth)?
+        ))
+    }
+
+    fn method_signature(
+        &self,
+        signature: &MethodSignature,
+        name: &str,
+        param_names: Option<&[String]>,
+        depth: u32,
+    ) -> Result<String, Error> {
+        let mut result = String::new();
+        match signature.calling_convention {
+            CallingConvention::Default => {}
+            CallingConvention::VarArg => result += "vararg ",
+            CallingConvention::C => result += "unmanaged cdecl ",
+            CallingConvention::StdCall => result += "unmanaged stdcall ",
+            CallingConvention::ThisCall => result += "unmanaged thiscall ",
+            CallingConvention::FastCall => result += "unmanaged fastcall ",
+            CallingConvention::Unmanaged | CallingConvention::NativeVarArg => result += "unmanaged ",
+        }
+        if signature.has_this {
+            result += "instance ";
+        }
+        if signature.explicit_this {
+            result += "explicit ";
+        }
+        let mut params = Vec::new();
+        for (i, param) in signature.params.iter().enumerate() {
+            let param = self.param(param, depth)?;
+            match param_names.and_then(|x| x.get(i)).filter(|x| !x.is_empty()) {
+                Some(name) => params.push(format!("{} {}", param, ident(name))),
+                None => params.push(param),
+            }
+        }
+        if signature.calling_convention == CallingConvention::VarArg && !signature.vararg_params.is_empty() {
+            params.push("...".to_string());
+            for param in &signature.vararg_params {
+                params.push(self.param(param, depth)?);
+            }
+        }
+        result += &format!(
+            "{} {}({})",
+            self.param(&signature.return_type, depth)?,
+            name,
+            params.join(", ")
+        );
+        Ok(result)
+    }
+
+    fn member_parent(&self, parent: MemberRefParent) -> Result<String, Error> {
+        Ok(match parent {
+            MemberRefParent::TypeDef(rid) => self.type_def_name(rid)?,
+            MemberRefParent::TypeRef(rid) => self.type_ref_name(rid, 0)?,
+            MemberRefParent::TypeSpec(rid) => self.type_spec(rid, 0)?,
+            MemberRefParent::ModuleRef(rid) => {
+                let module_ref = table_row(&self.assembly.tables().module_refs, TableId::ModuleRef, rid)?;
+                format!("[.module {}]", ident(self.assembly.strings().get(module_ref.name)?))
+            }
+            MemberRefParent::MethodDef(rid) => self.type_def_name(self.method_owner(rid))?,
+        })
+    }
+
+    /// Name members are qualified with, empty for globals owned by `<Module>`.
+    fn owner_name(&self, owner: u32) -> Result<String, Error> {
+        if owner <= 1 {
+            Ok(String::new())
+        } else {
+            self.type_def_name(owner)
+        }
+    }
+
+    fn qualified(owner: String, name: &str) -> String {
+        if owner.is_empty() {
+            ident(name).into_owned()
+        } else {
+            format!("{}::{}", owner, ident(name))
+        }
+    }
+
+    fn method_def(&self, rid: u32, generic_args: &str) -> Result<String, Error> {
+        let method = self.assembly.method(rid).ok_or(Error::TokenOutOfRange {
+            token: table_token(TableId::MethodDef, rid),
+            rows: self.assembly.tables().methods.len(),
+        })?;
+        let owner = self.owner_name(method.declaring_type().map_or(0, |x| x.rid))?;
+        let name = Self::qualified(owner, method.name()?) + generic_args;
+        self.method_signature(&method.signature()?, &name, None, 0)
+    }
+
+    fn field_def(&self, rid: u32) -> Result<String, Error> {
+        let field = self.field_definition(rid)?;
+        let owner = self.owner_name(field.declaring_type().map_or(0, |x| x.rid))?;
+        Ok(format!(
+            "{} {}",
+            self.type_sig(&field.signature()?.ty)?,
+            Self::qualified(owner, field.name()?)
+        ))
+    }
+
+    fn member_ref_row(&self, rid: u32) -> Result<&'a MemberRef, Error> {
+        table_row(&self.assembly.tables().member_refs, TableId::MemberRef, rid)
+    }
+
+    fn is_field_ref(&self, rid: u32) -> Result<bool, Error> {
+        let blob = self.assembly.blobs().get(self.member_ref_row(rid)?.signature)?;
+        Ok(blob.first().map(|x| x & SIG_KIND_MASK) == Some(SIG_FIELD))
+    }
+
+    fn member_ref(&self, rid: u32, generic_args: &str) -> Result<String, Error> {
+        let row = self.member_ref_row(rid)?;
+        let name = Self::qualified(self.member_parent(row.class)?, self.assembly.strings().get(row.name)?);
+        let blob = self.assembly.blobs().get(row.signature)?;
+        if self.is_field_ref(rid)? {
+            let signature: FieldSignature = blob.pread_with(0, scroll::LE)?;
+            Ok(format!("{} {}", self.type_sig(&signature.ty)?, name))
+        } else {
+            let signature: MethodSignature = blob.pread_with(0, scroll::LE)?;
+            self.method_signature(&signature, &(name + generic_args), None, 0)
+        }
+    }
+
+    fn method_spec(&self, rid: u32) -> Result<String, Error> {
+        let row = table_row(&self.assembly.tables().method_specs, TableId::MethodSpec, rid)?;
+        let instantiation: MethodSpecSignature = self
+            .assembly
+            .blobs()
+            .get(row.instantiation)?
+            .pread_with(0, scroll::LE)?;
+        let generic_args = format!("<{}>", self.type_list(&instantiation.args, 0)?);
+        match row.method {
+            MethodDefOrRef::MethodDef(rid) => self.method_def(rid, &generic_args),
+            MethodDefOrRef::MemberRef(rid) => self.member_ref(rid, &generic_args),
+        }
+    }
+
+    /// The operand of an instruction or attribute referring to `token`, e.g. `void [mscorlib]System.Console::WriteLine(string)`.
+    pub fn token(&self, token: Token) -> Result<String, Error> {
+        let table = match token.kind {
+            TokenKind::Table(table) => table,
+            TokenKind::UserString => return Ok(quote_string(&self.assembly.user_strings().get(token.rid)?)),
+        };
+        match table {
+            TableId::TypeDef => self.type_def_name(token.rid),
+            TableId::TypeRef => self.type_ref_name(token.rid, 0),
+            TableId::TypeSpec => self.type_spec(token.rid, 0),
+            TableId::MethodDef => self.method_def(token.rid, ""),
+            TableId::MemberRef => self.member_ref(token.rid, ""),
+            TableId::MethodSpec => self.method_spec(token.rid),
+            TableId::Field => self.field_def(token.rid),
+            TableId::StandAloneSig => {
+                let row = get_row(&self.assembly.tables().stand_alone_sigs, token)?;
+                let signature: MethodSignature = self.assembly.blobs().get(row.signature)?.pread_with(0, scroll::LE)?;
+                self.method_signature(&signature, "", None, 0)
+            }
+            _ => Ok(format!("0x{:08x}", token.raw())),
+        }
+    }
+
+    fn operand(&self, instruction: &Instruction<'_>, args: &[String]) -> Result<String, Error> {
+        Ok(match &instruction.operand {
+            Operand::None => String::new(),
+            Operand::Int8(value) => value.to_string(),
+            Operand::UInt8(value) => format!("0x{:x}", value),
+            Operand::Int32(value) => format!("0x{:x}", value),
+            Operand::Int64(value) => format!("0x{:x}", value),
+            Operand::Float32(value) => format!("{:?}", value),
+            Operand::Float64(value) => format!("{:?}", value),
+            Operand::Var(index) => match instruction.opcode {
+                OpCode::LdargS | OpCode::LdargaS | OpCode::StargS | OpCode::Ldarg | OpCode::Ldarga | OpCode::Starg => {
+                    match args.get(*index as usize).filter(|x| !x.is_empty()) {
+                        Some(name) => ident(name).into_owned(),
+                        None => index.to_string(),
+                    }
+                }
+                _ => format!("V_{}", index),
+            },
+            Operand::Target(target) => label(*target),
+            Operand::Switch(targets) => format!(
+                "( {})",
+                targets.iter().map(|x| label(*x)).collect::<Vec<_>>().join(", ")
+            ),
+            Operand::Token(token) => {
+                let operand = self.token(*token)?;
+                if instruction.opcode.operand_kind() != OperandKind::Tok {
+                    return Ok(operand);
+                }
+                // ldtoken tells members apart with a keyword
+                match token.kind {
+                    TokenKind::Table(TableId::MethodDef) | TokenKind::Table(TableId::MethodSpec) => {
+                        format!("method {}", operand)
+                    }
+                    TokenKind::Table(TableId::Field) => format!("field {}", operand),
+                    TokenKind::Table(TableId::MemberRef) if self.is_field_ref(token.rid)? => {
+                        format!("field {}", operand)
+                    }
+                    TokenKind::Table(TableId::MemberRef) => format!("method {}", operand),
+                    _ => operand,
+                }
+            }
+        })
+    }
+
+    fn prefix(&self, prefix: &Prefix) -> Result<(String, u32), Error> {
+        Ok(match prefix {
+            Prefix::Unaligned(alignment) => (format!("unaligned. {}", alignment), 3),
+            Prefix::Volatile => ("volatile.".to_string(), 2),
+            Prefix::Tail => ("tail.".to_string(), 2),
+            Prefix::Constrained(token) => (format!("constrained. {}", self.token(*token)?), 6),
+            Prefix::Readonly => ("readonly.".to_string(), 2),
+            Prefix::No(flags) => (format!("no. 0x{:x}", flags), 3),
+        })
+    }
+
+    fn instruction<W: Write>(
+        &self,
+        out: &mut W,
+        indent: usize,
+        instruction: &Instruction<'_>,
+        args: &[String],
+    ) -> Result<(), Error> {
+        let mut offset = instruction.offset;
+        for prefix in &instruction.prefixes {
+            let (text, size) = self.prefix(prefix)?;
+            line(out, indent, &format!("{}:  {}", label(offset), text))?;
+            offset += size;
+        }
+        let operand = self.operand(instruction, args)?;
+        let text = if operand.is_empty() {
+            instruction.opcode.mnemonic().to_string()
+        } else {
+            format!(
+                "{:width$}{}",
+                instruction.opcode.mnemonic(),
+                operand,
+                width = MNEMONIC_WIDTH
+            )
+        };
+        line(out, indent, &format!("{}:  {}", label(offset), text))
+    }
+
+    fn custom_attributes<W: Write>(&self, out: &mut W, indent: usize, parent: HasCustomAttribute) -> Result<(), Error> {
+        for attribute in self.assembly.custom_attributes_of(parent) {
+            let constructor = match attribute.constructor() {
+                CustomAttributeType::MethodDef(rid) => self.method_def(rid, "")?,
+                CustomAttributeType::MemberRef(rid) => self.member_ref(rid, "")?,
+            };
+            line(
+                out,
+                indent,
+                &format!(".custom {} = ( {} )", constructor, hex_bytes(attribute.value()?)),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn version(major: u16, minor: u16, build: u16, revision: u16) -> String {
+        format!(".ver {}:{}:{}:{}", major, minor, build, revision)
+    }
+
+    fn manifest<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        let tables = self.assembly.tables();
+        let strings = self.assembly.strings();
+        let blobs = self.assembly.blobs();
+        for assembly_ref in &tables.assembly_refs {
+            line(
+                out,
+                0,
+                &format!(".assembly extern {}", ident(strings.get(assembly_ref.name)?)),
+            )?;
+            line(out, 0, "{")?;
+            let public_key = blobs.get(assembly_ref.public_key_or_token)?;
+            if !public_key.is_empty() {
+                // AssemblyFlags.PublicKey tells the full key from its token
+                let keyword = if assembly_ref.flags & 0x1 != 0 {
+                    ".publickey"
+                } else {
+                    ".publickeytoken"
+                };
+                line(out, 2, &format!("{} = ({} )", keyword, hex_bytes(public_key)))?;
+            }
+            line(
+                out,
+                2,
+                &Self::version(
+                    assembly_ref.major_version,
+                    assembly_ref.minor_version,
+                    assembly_ref.build_number,
+                    assembly_ref.revision_number,
+                ),
+            )?;
+            line(out, 0, "}")?;
+        }
+        for (i, assembly) in tables.assemblies.iter().enumerate() {
+            line(out, 0, &format!(".assembly {}", ident(strings.get(assembly.name)?)))?;
+            line(out, 0, "{")?;
+            self.custom_attributes(out, 2, HasCustomAttribute::Assembly(i as u32 + 1))?;
+            let public_key = blobs.get(assembly.public_key)?;
+            if !public_key.is_empty() {
+                line(out, 2, &format!(".publickey = ({} )", hex_bytes(public_key)))?;
+            }
+            line(out, 2, &format!(".hash algorithm 0x{:08x}", assembly.hash_alg_id))?;
+            line(
+                out,
+                2,
+                &Self::version(
+                    assembly.major_version,
+                    assembly.minor_version,
+                    assembly.build_number,
+                    assembly.revision_number,
+                ),
+            )?;
+            line(out, 0, "}")?;
+        }
+        for module in &tables.modules {
+            line(out, 0, &format!(".module {}", ident(strings.get(module.name)?)))?;
+            if let Some(mvid) = self.assembly.guids().get(module.mvid)? {
+                line(out, 0, &format!("// MVID: {{{}}}", mvid))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn field<W: Write>(&self, out: &mut W, indent: usize, rid: u32) -> Result<(), Error> {
+        let field = self.field_definition(rid)?;
+        let flags_value = u32::from(field.row.flags);
+        let mut text = format!(".field {}", MEMBER_ACCESS[(flags_value & 0x7) as usize]);
+        let attributes = flags(
+            flags_value,
+            &[
+                (0x0010, "static"),
+                (0x0020, "initonly"),
+                (0x0040, "literal"),
+                (0x0080, "notserialized"),
+                (0x0200, "specialname"),
+                (0x0400, "rtspecialname"),
+                (0x2000, "pinvokeimpl"),
+            ],
+        );
+        if !attributes.is_empty() {
+            text += " ";
+            text += &attributes;
+        }
+        let signature = field.signature()?;
+        text += &format!(
+            " {}{} {}",
+            self.type_sig(&signature.ty)?,
+            self.custom_mods(&signature.custom_mods, 0)?,
+            ident(field.name()?)
+        );
+        line(out, indent, &text)?;
+        self.custom_attributes(out, indent, HasCustomAttribute::Field(rid))
+    }
+
+    fn method_header(
+        &self,
+        method: &MethodDefinition<'_>,
+        signature: &MethodSignature,
+        param_names: &[String],
+    ) -> Result<String, Error> {
+        let flags_value = u32::from(method.row.flags);
+        let mut text = format!(".method {}", MEMBER_ACCESS[(flags_value & 0x7) as usize]);
+        let attributes = flags(
+            flags_value,
+            &[
+                (0x0020, "final"),
+                (0x0080, "hidebysig"),
+                (0x0100, "newslot"),
+                (0x0200, "strict"),
+                (0x0800, "specialname"),
+                (0x1000, "rtspecialname"),
+                (0x0400, "abstract"),
+                (0x0040, "virtual"),
+                (0x0010, "static"),
+                (0x2000, "pinvokeimpl"),
+            ],
+        );
+        if !attributes.is_empty() {
+            text += " ";
+            text += &attributes;
+        }
+        let name = ident(method.name()?).into_owned() + &self.generic_params(TypeOrMethodDef::MethodDef(method.rid))?;
+        text += " ";
+        text += &self.method_signature(signature, &name, Some(param_names), 0)?;
+
+        let impl_flags = u32::from(method.row.impl_flags);
+        text += match impl_flags & 0x3 {
+            0 => " cil",
+            1 => " native",
+            2 => " optil",
+            _ => " runtime",
+        };
+        text += if impl_flags & 0x4 != 0 {
+            " unmanaged"
+        } else {
+            " managed"
+        };
+        let attributes = flags(
+            impl_flags,
+            &[
+                (0x0008, "noinlining"),
+                (0x0010, "forwardref"),
+                (0x0020, "synchronized"),
+                (0x0040, "nooptimization"),
+                (0x0080, "preservesig"),
+                (0x0100, "aggressiveinlining"),
+                (0x0200, "aggressiveoptimization"),
+                (0x1000, "internalcall"),
+            ],
+        );
+        if !attributes.is_empty() {
+            text += " ";
+            text += &attributes;
+        }
+        Ok(text)
+    }
+
+    fn exception_blocks(clauses: &[ExceptionClause]) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for clause in clauses {
+            let try_range = clause.try_range();
+            // Clauses protecting the same range share a single .try
+            let is_new_try = !blocks.iter().any(|x| match x.kind {
+                BlockKind::Try => x.start == try_range.start && x.end == try_range.end,
+                _ => false,
+            });
+            if is_new_try {
+                blocks.push(Block {
+                    start: try_range.start,
+                    end: try_range.end,
+                    kind: BlockKind::Try,
+                });
+            }
+            if let ExceptionClauseKind::Filter(filter_offset) = clause.kind {
+                blocks.push(Block {
+                    start: filter_offset,
+                    end: clause.handler_offset,
+                    kind: BlockKind::Filter,
+                });
+            }
+            let handler_range = clause.handler_range();
+            blocks.push(Block {
+                start: handler_range.start,
+                end: handler_range.end,
+                kind: BlockKind::Handler(clause.kind),
+            });
+        }
+        // Outer blocks open first
+        blocks.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        blocks
+    }
+
+    fn open_block<W: Write>(&self, out: &mut W, indent: usize, block: &Block) -> Result<(), Error> {
+        let header = match block.kind {
+            BlockKind::Try => ".try".to_string(),
+            BlockKind::Filter => "filter".to_string(),
+            BlockKind::Handler(ExceptionClauseKind::Catch(token)) => format!("catch {}", self.token(token)?),
+            BlockKind::Handler(ExceptionClauseKind::Filter(_)) => String::new(),
+            BlockKind::Handler(ExceptionClauseKind::Finally) => "finally".to_string(),
+            BlockKind::Handler(ExceptionClauseKind::Fault) => "fault".to_string(),
+        };
+        if !header.is_empty() {
+            line(out, indent, &header)?;
+        }
+        line(out, indent, "{")
+    }
+
+    /// Closes the open blocks ending at or before `offset`, innermost first.
+    fn close_blocks<W: Write>(
+        &self,
+        out: &mut W,
+        indent: usize,
+        open: &mut Vec<Block>,
+        offset: u32,
+    ) -> Result<(), Error> {
+        while let Some(block) = open.last().cloned().filter(|x| x.end <= offset) {
+            let kind = match block.kind {
+                BlockKind::Try => ".try",
+                BlockKind::Filter => "filter",
+                BlockKind::Handler(_) => "handler",
+            };
+            open.pop();
+            line(out, indent + open.len() * 2, &format!("}}  // end {}", kind))?;
+        }
+        Ok(())
+    }
+
+    fn method<W: Write>(
+        &self,
+        out: &mut W,
+        indent: usize,
+        method: &MethodDefinition<'a>,
+        owner: &str,
+    ) -> Result<(), Error> {
+        let tables = self.assembly.tables();
+        let signature = method.signature()?;
+        let mut param_names = vec![String::new(); signature.params.len()];
+        for rid in tables.method_params(method.rid) {
+            if let Some(param) = tables.params.get((rid as usize).wrapping_sub(1)) {
+                if let Some(name) = param_names.get_mut((param.sequence as usize).wrapping_sub(1)) {
+                    *name = self.assembly.strings().get(param.name)?.to_string();
+                }
+            }
+        }
+        line(out, indent, &self.method_header(method, &signature, &param_names)?)?;
+        line(out, indent, "{")?;
+        let inner = indent + 2;
+        let entry_point = self.assembly.cli_header().entry_point_token();
+        if entry_point == Some(method.token().raw()) {
+            line(out, inner, ".entrypoint")?;
+        }
+        self.custom_attributes(out, inner, HasCustomAttribute::MethodDef(method.rid))?;
+
+        if let Some(body) = method.body()? {
+            line(
+                out,
+                inner,
+                &format!("// Code size       {} (0x{:x})", body.code.len(), body.code.len()),
+            )?;
+            line(out, inner, &format!(".maxstack  {}", body.max_stack))?;
+            if let Some(locals) = method.locals()? {
+                let locals = locals
+                    .locals
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| {
+                        Ok(format!(
+                            "[{}] {}{}{}{} V_{}",
+                            i,
+                            self.type_sig(&x.ty)?,
+                            self.custom_mods(&x.custom_mods, 0)?,
+                            if x.by_ref { "&" } else { "" },
+                            if x.pinned { " pinned" } else { "" },
+                            i
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let keyword = if body.init_locals { ".locals init" } else { ".locals" };
+                line(out, inner, &format!("{} ({})", keyword, locals.join(", ")))?;
+            }
+
+            // Arguments as `ldarg` numbers them, `this` first for instance methods
+            let mut args = param_names.clone();
+            if signature.has_this && !signature.explicit_this {
+                args.insert(0, String::new());
+            }
+            let blocks = Self::exception_blocks(&body.exception_clauses);
+            let mut open = Vec::new();
+            for instruction in method.instructions()? {
+                self.close_blocks(out, inner, &mut open, instruction.offset)?;
+                for block in blocks.iter().filter(|x| x.start == instruction.offset) {
+                    self.open_block(out, inner + open.len() * 2, block)?;
+                    open.push(*block);
+                }
+                self.instruction(out, inner + open.len() * 2, &instruction, &args)?;
+            }
+            // Blocks running past the code are malformed, still close them to keep the listing balanced
+            let end = open.iter().map(|x| x.end).max().unwrap_or(0);
+            self.close_blocks(out, inner, &mut open, end)?;
+        }
+        line(
+            out,
+            indent,
+            &format!(
+                "}} // end of method {}",
+                Self::qualified(owner.to_string(), method.name()?)
+            ),
+        )
+    }
+
+    fn class<W: Write>(&self, out: &mut W, indent: usize, ty: &TypeDefinition<'a>) -> Result<(), Error> {
+        let tables = self.assembly.tables();
+        let flags_value = ty.row.flags;
+        let mut text = ".class ".to_string();
+        if flags_value & 0x20 != 0 {
+            text += "interface ";
+        }
+        text += TYPE_VISIBILITY[(flags_value & 0x7) as usize];
+        text += match flags_value & 0x18 {
+            0x08 => " sequential",
+            0x10 => " explicit",
+            _ => " auto",
+        };
+        text += match flags_value & 0x30000 {
+            0x10000 => " unicode",
+            0x20000 => " autochar",
+            _ => " ansi",
+        };
+        let attributes = flags(
+            flags_value,
+            &[
+                (0x0000_0080, "abstract"),
+                (0x0000_0100, "sealed"),
+                (0x0000_0400, "specialname"),
+                (0x0000_0800, "rtspecialname"),
+                (0x0000_1000, "import"),
+                (0x0000_2000, "serializable"),
+                (0x0000_4000, "windowsruntime"),
+                (0x0010_0000, "beforefieldinit"),
+            ],
+        );
+        if !attributes.is_empty() {
+            text += " ";
+            text += &attributes;
+        }
+        let full_name = self.type_def_name(ty.rid)?;
+        let name = if self.enclosing_type(ty.rid) == 0 {
+            full_name.clone()
+        } else {
+            ident(ty.name()?).into_owned()
+        };
+        text += " ";
+        text += &name;
+        text += &self.generic_params(TypeOrMethodDef::TypeDef(ty.rid))?;
+        line(out, indent, &text)?;
+        if !ty.row.extends.is_null() {
+            line(out, indent + 7, &format!("extends {}", self.type_name(ty.row.extends)?))?;
+        }
+        let interfaces = tables
+            .interface_impls
+            .iter()
+            .filter(|x| x.class == ty.rid)
+            .map(|x| self.type_name(x.interface))
+            .collect::<Result<Vec<_>, Error>>()?;
+        if !interfaces.is_empty() {
+            line(
+                out,
+                indent + 7,
+                &format!("implements {}", interfaces.join(",\n                  ")),
+            )?;
+        }
+        line(out, indent, "{")?;
+        let inner = indent + 2;
+        self.custom_attributes(out, inner, HasCustomAttribute::TypeDef(ty.rid))?;
+        if let Some(layout) = tables.class_layouts.iter().find(|x| x.parent == ty.rid) {
+            line(out, inner, &format!(".pack {}", layout.packing_size))?;
+            line(out, inner, &format!(".size {}", layout.class_size))?;
+        }
+        for nested in ty.nested_types() {
+            self.class(out, inner, &nested)?;
+        }
+        for field in ty.fields() {
+            self.field(out, inner, field.rid)?;
+        }
+        for method in ty.methods() {
+            self.method(out, inner, &method, &full_name)?;
+        }
+        line(out, indent, &format!("}} // end of class {}", name))
+    }
+
+    /// Writes the whole listing: manifest, global members and every type with its members.
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        self.manifest(out)?;
+        for ty in self.assembly.types() {
+            // The first TypeDef holds the global fields and methods
+            if ty.rid == 1 {
+                for field in ty.fields() {
+                    line(out, 0, "")?;
+                    self.field(out, 0, field.rid)?;
+                }
+                for method in ty.methods() {
+                    line(out, 0, "")?;
+                    self.method(out, 0, &method, "")?;
+                }
+            } else if self.enclosing_type(ty.rid) == 0 {
+                line(out, 0, "")?;
+                self.class(out, 0, &ty)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes an ildasm-style listing of `assembly` to `out`.
+pub fn disassemble<W: Write>(assembly: &Assembly<'_>, out: &mut W) -> Result<(), Error> {
+    Disassembler::new(assembly).write(out)
+}
@@ -0,0 +1,250 @@
+//! Errors returned while loading and decoding an image.
+//!
+//! Images come from untrusted sources, every malformed structure is reported here instead of panicking.
+//! Offsets are relative to the structure named by the variant: the file for PE level errors, the
+//! `#~` stream for tables, the blob for signatures and the method body or code for IL.
+
+use crate::metadata::Violation;
+use crate::tables::TableId;
+use crate::token::Token;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The PE container itself is malformed
+    Pe(goblin::error::Error),
+    /// The image has no optional header or no CLR runtime header directory
+    NotManaged,
+    /// An RVA that doesn't fall into any section
+    UnmappedRva(u32),
+    /// `size` bytes at file offset `offset` don't fit into the image or the enclosing directory
+    OutOfBounds {
+        what: &'static str,
+        offset: usize,
+        size: usize,
+    },
+    /// The metadata root at file offset `offset` is malformed
+    InvalidMetadataRoot {
+        offset: usize,
+        reason: String,
+    },
+    /// The metadata root at file offset `offset` breaks ECMA-335 and the image is loaded in strict mode
+    MetadataViolations {
+        offset: usize,
+        violations: Vec<Violation>,
+    },
+    /// A stream the image can't be read without
+    MissingStream(&'static str),
+    /// The `#~` stream ends inside the rows of `table`
+    TruncatedTable {
+        table: TableId,
+        row: u32,
+        offset: usize,
+    },
+    /// The valid mask of the `#~` stream has a bit set past the last known table
+    UnknownTable(u8),
+    /// A token whose high byte is not a table or the #US heap
+    InvalidToken(u32),
+    /// A token with RID 0 or past the end of its table
+    TokenOutOfRange {
+        token: Token,
+        rows: usize,
+    },
+    /// A token of the wrong table, e.g. a locals signature that isn't a StandAloneSig
+    UnexpectedToken {
+        token: Token,
+        expected: TableId,
+    },
+    /// A coded index whose tag selects no table
+    InvalidCodedIndex {
+        kind: &'static str,
+        tag: u32,
+    },
+    HeapIndexOutOfRange {
+        heap: &'static str,
+        index: u32,
+        size: usize,
+    },
+    InvalidCompressedInteger {
+        offset: usize,
+        prefix: u8,
+    },
+    BadSignature {
+        offset: usize,
+        reason: String,
+    },
+    BadMethodBody {
+        offset: usize,
+        reason: String,
+    },
+    BadInstruction {
+        offset: usize,
+        reason: String,
+    },
+    /// A referenced assembly the resolver can't find
+    UnresolvedAssembly(String),
+    /// A TypeRef to a type its scope neither defines nor forwards
+    UnresolvedType {
+        name: String,
+        scope: String,
+    },
+    /// A MemberRef whose parent has no member of that name and signature
+    UnresolvedMember {
+        parent: String,
+        name: String,
+    },
+    /// Running an image without an entry point
+    NoEntryPoint,
+    /// An exception the interpreted program raised and didn't catch
+    UnhandledException {
+        exception: String,
+        method: Token,
+        offset: u32,
+    },
+    /// CIL the interpreter can't execute as written, e.g. a stack underflow or mismatched operand types
+    InvalidProgram {
+        method: Token,
+        offset: u32,
+        reason: String,
+    },
+    /// Valid CIL the interpreter doesn't implement yet
+    NotSupported {
+        method: Token,
+        offset: u32,
+        reason: String,
+    },
+    /// A type whose layout or method table can't be built, e.g. one that contains itself
+    TypeLoad {
+        token: Token,
+        reason: String,
+    },
+    /// Enclosing types, resolution scopes or TypeSpecs refer to each other deeper than the reader follows
+    NestedTooDeeply(Token),
+    /// Reading past the end of the data or a primitive that doesn't decode, e.g. a non UTF-8 name
+    Read(scroll::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Pe(error) => write!(f, "Malformed PE image: {}", error),
+            Error::NotManaged => write!(f, "Is not a .Net executable"),
+            Error::UnmappedRva(rva) => write!(f, "Cannot map rva 0x{:08x} into offset", rva),
+            Error::OutOfBounds { what, offset, size } => write!(
+                f,
+                "{} of {} bytes at offset 0x{:x} is out of bounds",
+                what, size, offset
+            ),
+            Error::InvalidMetadataRoot { offset, reason } => {
+                write!(f, "Invalid metadata root at offset 0x{:x}: {}", offset, reason)
+            }
+            Error::MetadataViolations { offset, violations } => {
+                write!(f, "Metadata root at offset 0x{:x} is invalid: ", offset)?;
+                for (i, violation) in violations.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", violation)?;
+                }
+                Ok(())
+            }
+            Error::MissingStream(name) => write!(f, "No {} stream", name),
+            Error::TruncatedTable { table, row, offset } => write!(
+                f,
+                "{} table is truncated at row {}, stream offset 0x{:x}",
+                table.name(),
+                row,
+                offset
+            ),
+            Error::UnknownTable(table) => write!(f, "Unknown metadata table 0x{:02x}", table),
+            Error::InvalidToken(value) => write!(f, "Invalid token 0x{:08x}", value),
+            Error::TokenOutOfRange { token, rows } => write!(
+                f,
+                "Token 0x{:08x} is out of range, table has {} rows",
+                token.raw(),
+                rows
+            ),
+            Error::UnexpectedToken { token, expected } => {
+                write!(f, "Token 0x{:08x} is not a {}", token.raw(), expected.name())
+            }
+            Error::InvalidCodedIndex { kind, tag } => write!(f, "Invalid {} tag {}", kind, tag),
+            Error::HeapIndexOutOfRange { heap, index, size } => write!(
+                f,
+                "{} heap index 0x{:x} is out of range, heap size is 0x{:x}",
+                heap, index, size
+            ),
+            Error::InvalidCompressedInteger { offset, prefix } => write!(
+                f,
+                "Invalid compressed integer prefix 0x{:02x} at offset {}",
+                prefix, offset
+            ),
+            Error::BadSignature { offset, reason } => write!(f, "{} at signature offset {}", reason, offset),
+            Error::BadMethodBody { offset, reason } => write!(f, "{} at method body offset {}", reason, offset),
+            Error::BadInstruction { offset, reason } => write!(f, "{} at IL_{:04x}", reason, offset),
+            Error::UnresolvedAssembly(name) => write!(f, "Cannot find assembly {}", name),
+            Error::UnresolvedType { name, scope } => write!(f, "Cannot resolve type {} in {}", name, scope),
+            Error::UnresolvedMember { parent, name } => write!(f, "Cannot resolve member {}::{}", parent, name),
+            Error::NoEntryPoint => write!(f, "Image has no entry point"),
+            Error::UnhandledException {
+                exception,
+                method,
+                offset,
+            } => write!(
+                f,
+                "Unhandled {} in method 0x{:08x} at IL_{:04x}",
+                exception,
+                method.raw(),
+                offset
+            ),
+            Error::InvalidProgram { method, offset, reason } => write!(
+                f,
+                "Invalid program in method 0x{:08x} at IL_{:04x}: {}",
+                method.raw(),
+                offset,
+                reason
+            ),
+            Error::NotSupported { method, offset, reason } => write!(
+                f,
+                "Not supported in method 0x{:08x} at IL_{:04x}: {}",
+                method.raw(),
+                offset,
+                reason
+            ),
+            Error::TypeLoad { token, reason } => write!(f, "Cannot load type 0x{:08x}: {}", token.raw(), reason),
+            Error::NestedTooDeeply(token) => write!(f, "Token 0x{:08x} is nested too deeply", token.raw()),
+            Error::Read(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Pe(error) => Some(error),
+            Error::Read(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<goblin::error::Error> for Error {
+    fn from(error: goblin::error::Error) -> Self {
+        Error::Pe(error)
+    }
+}
+
+impl From<scroll::Error> for Error {
+    fn from(error: scroll::Error) -> Self {
+        Error::Read(error)
+    }
+}
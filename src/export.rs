@@ -0,0 +1,249 @@
+//! Serializable model of the whole decoded metadata.
+//!
+//! The JSON layout is described by `schema/metadata.schema.json`, changes that break
+//! consumers bump `SCHEMA_VERSION`.
+
+use crate::assembly::Assembly;
+use crate::cli_header::CliHeader;
+use crate::disasm::Disassembler;
+use crate::error::Error;
+use crate::metadata::MetadataRoot;
+use crate::tables::{ColumnKind, TableId};
+use crate::token::{Token, TokenKind};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Metadata<'a> {
+    pub schema_version: u32,
+    pub image: Image,
+    pub cli_header: &'a CliHeader,
+    pub metadata_root: MetadataRoot<'a>,
+    pub tables: Tables,
+    pub heaps: Heaps<'a>,
+}
+
+/// PE level facts about the image.
+#[derive(Debug, Serialize)]
+pub struct Image {
+    pub machine: u16,
+    pub platform: String,
+    pub pe32_plus: bool,
+}
+
+/// Header of the `#~` stream and the rows of every present table keyed by table name.
+#[derive(Debug, Serialize)]
+pub struct Tables {
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub heap_sizes: u8,
+    /// Bit vector of present tables as a hex string, 64-bit numbers don't survive every JSON parser
+    pub valid: String,
+    pub sorted: String,
+    pub rows: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Heaps<'a> {
+    pub strings: Vec<HeapString<'a>>,
+    pub user_strings: Vec<HeapString<'a>>,
+    pub blobs: Vec<HeapBlob>,
+    pub guids: Vec<HeapGuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeapString<'a> {
+    pub offset: u32,
+    pub value: Cow<'a, str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeapBlob {
+    pub offset: u32,
+    /// Lowercase hex without separators
+    pub data: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeapGuid {
+    pub index: u32,
+    pub value: String,
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// Tables whose rows ildasm can name, e.g. `[mscorlib]System.Object`.
+const NAMED_TABLES: [TableId; 7] = [
+    TableId::TypeDef,
+    TableId::TypeRef,
+    TableId::TypeSpec,
+    TableId::Field,
+    TableId::MethodDef,
+    TableId::MemberRef,
+    TableId::MethodSpec,
+];
+
+struct Exporter<'a> {
+    assembly: &'a Assembly<'a>,
+    disassembler: Disassembler<'a>,
+}
+
+impl<'a> Exporter<'a> {
+    fn display(&self, table: TableId, rid: u32) -> Option<String> {
+        if !NAMED_TABLES.contains(&table) {
+            return None;
+        }
+        self.disassembler.token(Token::new(TokenKind::Table(table), rid)).ok()
+    }
+
+    /// `{"table": .., "rid": .., "display": ..}`, the display name only when the row can be named.
+    fn reference(&self, table: TableId, rid: u32) -> Value {
+        let mut reference = Map::new();
+        reference.insert("table".to_string(), json!(table.name()));
+        reference.insert("rid".to_string(), json!(rid));
+        if rid != 0 {
+            if let Some(display) = self.display(table, rid) {
+                reference.insert("display".to_string(), json!(display));
+            }
+        }
+        Value::Object(reference)
+    }
+
+    fn column(&self, kind: ColumnKind, value: Value) -> Value {
+        let index = value.as_u64().unwrap_or(0) as u32;
+        match kind {
+            ColumnKind::U8 | ColumnKind::U16 | ColumnKind::U32 => value,
+            ColumnKind::String => self
+                .assembly
+                .strings()
+                .get(index)
+                .map(|x| json!(x))
+                .unwrap_or(Value::Null),
+            ColumnKind::Guid => match self.assembly.guids().get(index) {
+                Ok(Some(guid)) => json!(guid.to_string()),
+                _ => Value::Null,
+            },
+            ColumnKind::Blob => self
+                .assembly
+                .blobs()
+                .get(index)
+                .map(|x| json!(hex(x)))
+                .unwrap_or(Value::Null),
+            ColumnKind::Table(table) => self.reference(table, index),
+            // Coded indices serialize as `{"TypeRef": 1}`
+            ColumnKind::Coded => match value {
+                Value::Object(variant) => match variant.iter().next() {
+                    Some((table, rid)) => match TableId::from_name(table) {
+                        Some(table) => self.reference(table, rid.as_u64().unwrap_or(0) as u32),
+                        None => Value::Null,
+                    },
+                    None => Value::Null,
+                },
+                _ => Value::Null,
+            },
+        }
+    }
+
+    fn rows(&self, table: TableId) -> Result<Vec<Value>, Error> {
+        let tables = self.assembly.tables();
+        let mut rows = Vec::with_capacity(tables.row_count(table));
+        for rid in 1..=tables.row_count(table) as u32 {
+            let token = Token::new(TokenKind::Table(table), rid);
+            // Rows are plain structs of integers and coded indices, they always serialize
+            let mut columns = match serde_json::to_value(self.assembly.resolve(token)?) {
+                Ok(Value::Object(columns)) => columns,
+                _ => Map::new(),
+            };
+            let mut row = Map::new();
+            row.insert("rid".to_string(), json!(rid));
+            row.insert("token".to_string(), json!(token.raw()));
+            if let Some(display) = self.display(table, rid) {
+                row.insert("display".to_string(), json!(display));
+            }
+            for &(name, kind) in table.columns() {
+                let value = columns.remove(name).unwrap_or(Value::Null);
+                row.insert(name.to_string(), self.column(kind, value));
+            }
+            rows.push(Value::Object(row));
+        }
+        Ok(rows)
+    }
+
+    fn tables(&self) -> Result<Tables, Error> {
+        let tables = self.assembly.tables();
+        let mut rows = Map::new();
+        for table in (0..=0x2c).filter_map(TableId::from_u8) {
+            if tables.row_count(table) != 0 {
+                rows.insert(table.name().to_string(), Value::Array(self.rows(table)?));
+            }
+        }
+        Ok(Tables {
+            major_version: tables.major_version,
+            minor_version: tables.minor_version,
+            heap_sizes: tables.heap_sizes,
+            valid: format!("0x{:016x}", tables.valid),
+            sorted: format!("0x{:016x}", tables.sorted),
+            rows,
+        })
+    }
+
+    fn heaps(&self) -> Heaps<'a> {
+        let assembly = self.assembly;
+        Heaps {
+            strings: assembly
+                .strings()
+                .iter()
+                .map(|(offset, value)| HeapString { offset, value })
+                .collect(),
+            user_strings: assembly
+                .user_strings()
+                .iter()
+                .map(|(offset, value)| HeapString {
+                    offset,
+                    value: Cow::Owned(value),
+                })
+                .collect(),
+            blobs: assembly
+                .blobs()
+                .iter()
+                .map(|(offset, data)| HeapBlob {
+                    offset,
+                    data: hex(data),
+                })
+                .collect(),
+            guids: assembly
+                .guids()
+                .iter()
+                .map(|(index, guid)| HeapGuid {
+                    index,
+                    value: guid.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Collects everything decoded from `assembly` into a serializable document.
+pub fn export<'a>(assembly: &'a Assembly<'a>) -> Result<Metadata<'a>, Error> {
+    let exporter = Exporter {
+        assembly,
+        disassembler: Disassembler::new(assembly),
+    };
+    Ok(Metadata {
+        schema_version: SCHEMA_VERSION,
+        image: Image {
+            machine: assembly.machine(),
+            platform: format!("{:?}", assembly.platform()),
+            pe32_plus: assembly.is_pe32_plus(),
+        },
+        cli_header: assembly.cli_header(),
+        metadata_root: assembly.metadata_root()?,
+        tables: exporter.tables()?,
+        heaps: exporter.heaps(),
+    })
+}
@@ -0,0 +1,290 @@
+//! Accessors for the #Strings, #US, #Blob and #GUID metadata heaps.
+
+use crate::error::Error;
+use scroll::{self, Pread};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Reads an ECMA-335 compressed unsigned integer (II.23.2).
+pub fn read_compressed_u32(src: &[u8], offset: &mut usize) -> Result<u32, Error> {
+    let first: u8 = src.gread(offset)?;
+    if first & 0x80 == 0 {
+        Ok(u32::from(first))
+    } else if first & 0xc0 == 0x80 {
+        let second: u8 = src.gread(offset)?;
+        Ok(u32::from(first & 0x3f) << 8 | u32::from(second))
+    } else if first & 0xe0 == 0xc0 {
+        let rest: [u8; 3] = [src.gread(offset)?, src.gread(offset)?, src.gread(offset)?];
+        Ok(u32::from(first & 0x1f) << 24 | u32::from(rest[0]) << 16 | u32::from(rest[1]) << 8 | u32::from(rest[2]))
+    } else {
+        Err(Error::InvalidCompressedInteger {
+            offset: *offset - 1,
+            prefix: first,
+        })
+    }
+}
+
+/// Reads an ECMA-335 compressed signed integer: the sign bit is rotated into the lowest bit.
+pub fn read_compressed_i32(src: &[u8], offset: &mut usize) -> Result<i32, Error> {
+    let start = *offset;
+    let value = read_compressed_u32(src, offset)?;
+    let bits = match *offset - start {
+        1 => 7,
+        2 => 14,
+        _ => 29,
+    };
+    let magnitude = (value >> 1) as i32;
+    Ok(if value & 1 == 0 {
+        magnitude
+    } else {
+        magnitude - (1 << (bits - 1))
+    })
+}
+
+fn out_of_range(heap: &'static str, index: u32, size: usize) -> Error {
+    Error::HeapIndexOutOfRange { heap, index, size }
+}
+
+/// #Strings: null-terminated UTF-8 identifiers.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StringHeap<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StringHeap<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn get(&self, index: u32) -> Result<&'a str, Error> {
+        if index as usize >= self.data.len() {
+            return Err(out_of_range("#Strings", index, self.data.len()));
+        }
+        Ok(self.data.pread(index as usize)?)
+    }
+
+    /// All strings with their heap offsets, invalid UTF-8 replaced.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Cow<'a, str>)> + 'a {
+        let data = self.data;
+        let mut offset = 0;
+        data.split(|&x| x == 0)
+            .map(move |x| {
+                let entry = (offset as u32, String::from_utf8_lossy(x));
+                offset += x.len() + 1;
+                entry
+            })
+            // The heap ends with a terminator, splitting yields an empty tail after it
+            .take_while(move |x| (x.0 as usize) < data.len())
+    }
+}
+
+/// #US: length-prefixed UTF-16 string literals referenced by `ldstr`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct UserStringHeap<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> UserStringHeap<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn get(&self, index: u32) -> Result<String, Error> {
+        let bytes = BlobHeap::new(self.data)
+            .get(index)
+            .map_err(|_| out_of_range("#US", index, self.data.len()))?;
+        // The last byte is a flag telling whether any char needs special handling, not part of the string
+        let chars = bytes[..bytes.len().saturating_sub(1)]
+            .chunks(2)
+            .map(|x| u16::from(x[0]) | x.get(1).map_or(0, |&x| u16::from(x) << 8))
+            .collect::<Vec<u16>>();
+        Ok(String::from_utf16_lossy(&chars))
+    }
+
+    /// All string literals with their heap offsets, the `ldstr` token RIDs.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, String)> + 'a {
+        let heap = *self;
+        BlobHeap::new(self.data)
+            .iter()
+            .filter_map(move |(offset, _)| heap.get(offset).ok().map(|x| (offset, x)))
+    }
+}
+
+/// #Blob: compressed-length-prefixed byte arrays (signatures, constants, custom attribute values).
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BlobHeap<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BlobHeap<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn get(&self, index: u32) -> Result<&'a [u8], Error> {
+        let offset = &mut (index as usize);
+        if *offset >= self.data.len() {
+            return Err(out_of_range("#Blob", index, self.data.len()));
+        }
+        let length = read_compressed_u32(self.data, offset)? as usize;
+        self.data
+            .get(*offset..*offset + length)
+            .ok_or_else(|| out_of_range("#Blob", index, self.data.len()))
+    }
+
+    /// All blobs with their heap offsets, stopping at the first malformed length.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &'a [u8])> + 'a {
+        let heap = *self;
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            if offset >= heap.data.len() {
+                return None;
+            }
+            let start = offset;
+            let length = read_compressed_u32(heap.data, &mut offset).ok()? as usize;
+            let blob = heap.data.get(offset..offset + length)?;
+            offset += length;
+            Some((start as u32, blob))
+        })
+    }
+}
+
+/// #GUID: array of 16-byte GUIDs addressed by a 1-based index, 0 being null.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct GuidHeap<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> GuidHeap<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn get(&self, index: u32) -> Result<Option<Guid>, Error> {
+        if index == 0 {
+            return Ok(None);
+        }
+        let offset = (index as usize - 1) * 16;
+        let mut guid = [0; 16];
+        guid.copy_from_slice(
+            self.data
+                .get(offset..offset + 16)
+                .ok_or_else(|| out_of_range("#GUID", index, self.data.len()))?,
+        );
+        Ok(Some(Guid(guid)))
+    }
+
+    /// All GUIDs with their 1-based indices.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Guid)> + 'a {
+        let heap = *self;
+        (1..=(self.data.len() / 16) as u32).filter_map(move |index| Some((index, heap.get(index).ok()??)))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Guid(pub [u8; 16]);
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let b = &self.0;
+        write!(
+            f,
+            "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+            b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]
+        )
+    }
+}
+
+impl fmt::Debug for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{{}}}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compressed_u32(src: &[u8]) -> u32 {
+        let offset = &mut 0;
+        let value = read_compressed_u32(src, offset).unwrap();
+        assert_eq!(*offset, src.len());
+        value
+    }
+
+    fn compressed_i32(src: &[u8]) -> i32 {
+        let offset = &mut 0;
+        let value = read_compressed_i32(src, offset).unwrap();
+        assert_eq!(*offset, src.len());
+        value
+    }
+
+    #[test]
+    fn compressed_u32_boundaries() {
+        assert_eq!(compressed_u32(&[0x00]), 0);
+        assert_eq!(compressed_u32(&[0x7f]), 0x7f);
+        assert_eq!(compressed_u32(&[0x80, 0x80]), 0x80);
+        assert_eq!(compressed_u32(&[0xbf, 0xff]), 0x3fff);
+        assert_eq!(compressed_u32(&[0xc0, 0x00, 0x40, 0x00]), 0x4000);
+        assert_eq!(compressed_u32(&[0xdf, 0xff, 0xff, 0xff]), 0x1fff_ffff);
+        assert!(read_compressed_u32(&[0xe0, 0, 0, 0], &mut 0).is_err());
+        assert!(read_compressed_u32(&[0xc0, 0], &mut 0).is_err());
+    }
+
+    #[test]
+    fn compressed_i32_spec_examples() {
+        assert_eq!(compressed_i32(&[0x06]), 3);
+        assert_eq!(compressed_i32(&[0x7b]), -3);
+        assert_eq!(compressed_i32(&[0x80, 0x80]), 64);
+        assert_eq!(compressed_i32(&[0x01]), -64);
+        assert_eq!(compressed_i32(&[0xc0, 0x00, 0x40, 0x00]), 8192);
+        assert_eq!(compressed_i32(&[0x80, 0x01]), -8192);
+        assert_eq!(compressed_i32(&[0xdf, 0xff, 0xff, 0xfe]), 268_435_455);
+        assert_eq!(compressed_i32(&[0xc0, 0x00, 0x00, 0x01]), -268_435_456);
+    }
+
+    #[test]
+    fn strings_and_blobs() {
+        let strings = StringHeap::new(b"\0Foo\0Bar\0");
+        assert_eq!(strings.get(1).unwrap(), "Foo");
+        assert_eq!(strings.get(2).unwrap(), "oo");
+        assert_eq!(strings.get(5).unwrap(), "Bar");
+        assert!(strings.get(9).is_err());
+
+        let blobs = BlobHeap::new(&[0x00, 0x02, 0xaa, 0xbb, 0x03, 0xcc]);
+        assert_eq!(blobs.get(0).unwrap(), &[] as &[u8]);
+        assert_eq!(blobs.get(1).unwrap(), &[0xaa, 0xbb]);
+        assert!(blobs.get(4).is_err());
+    }
+
+    #[test]
+    fn user_strings_drop_the_trailing_flag() {
+        let heap = UserStringHeap::new(&[0x00, 0x05, b'h', 0, b'i', 0, 0x00, 0x03, 0xe9, 0x00, 0x01, 0x01, 0x00]);
+        assert_eq!(heap.get(1).unwrap(), "hi");
+        assert_eq!(heap.get(7).unwrap(), "\u{e9}");
+        assert_eq!(heap.get(11).unwrap(), "");
+        assert!(heap.get(13).is_err());
+        assert_eq!(
+            heap.iter().collect::<Vec<_>>(),
+            vec![
+                (0, String::new()),
+                (1, "hi".to_string()),
+                (7, "\u{e9}".to_string()),
+                (11, String::new())
+            ]
+        );
+    }
+
+    #[test]
+    fn guids_are_one_based() {
+        let mut data = [0; 32];
+        data[0] = 1;
+        data[16] = 2;
+        let heap = GuidHeap::new(&data);
+        assert_eq!(heap.get(0).unwrap(), None);
+        assert_eq!(heap.get(1).unwrap().unwrap().0[0], 1);
+        assert_eq!(heap.get(2).unwrap().unwrap().0[0], 2);
+        assert!(heap.get(3).is_err());
+        assert_eq!(heap.iter().map(|x| x.0).collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
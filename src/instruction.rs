@@ -0,0 +1,584 @@
+//! CIL instruction stream decoding (ECMA-335 III).
+
+use crate::error::Error;
+use crate::tables::TildaStream;
+use crate::token::{Row, Token};
+use scroll::{self, Pread};
+use std::convert::TryFrom;
+
+/// Kind of the inline operand following an opcode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OperandKind {
+    /// InlineNone
+    None,
+    /// ShortInlineI, a signed byte (an unsigned one for `unaligned.` and `no.`)
+    ShortI,
+    /// InlineI
+    I,
+    /// InlineI8
+    I8,
+    /// ShortInlineR
+    ShortR,
+    /// InlineR
+    R,
+    /// ShortInlineVar, an unsigned byte argument or local index
+    ShortVar,
+    /// InlineVar
+    Var,
+    /// ShortInlineBrTarget, a signed byte offset from the next instruction
+    ShortBrTarget,
+    /// InlineBrTarget
+    BrTarget,
+    /// InlineSwitch
+    Switch,
+    /// InlineMethod
+    Method,
+    /// InlineField
+    Field,
+    /// InlineType
+    Type,
+    /// InlineTok, a TypeDef, TypeRef, TypeSpec, MethodDef, MemberRef or Field token
+    Tok,
+    /// InlineString, a user string token
+    String,
+    /// InlineSig, a StandAloneSig token
+    Sig,
+}
+
+macro_rules! opcodes {
+    ($($value:expr, $name:ident, $mnemonic:expr, $operand:ident;)+) => {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum OpCode {
+            $($name,)+
+        }
+
+        impl OpCode {
+            /// Looks up an opcode by its value, two-byte opcodes are `0xfeXX`.
+            pub fn from_u16(value: u16) -> Option<Self> {
+                match value {
+                    $($value => Some(OpCode::$name),)+
+                    _ => None,
+                }
+            }
+
+            pub fn value(self) -> u16 {
+                match self {
+                    $(OpCode::$name => $value,)+
+                }
+            }
+
+            /// Assembler name, e.g. `ldc.i4.s`.
+            pub fn mnemonic(self) -> &'static str {
+                match self {
+                    $(OpCode::$name => $mnemonic,)+
+                }
+            }
+
+            pub fn operand_kind(self) -> OperandKind {
+                match self {
+                    $(OpCode::$name => OperandKind::$operand,)+
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    0x00, Nop, "nop", None;
+    0x01, Break, "break", None;
+    0x02, Ldarg0, "ldarg.0", None;
+    0x03, Ldarg1, "ldarg.1", None;
+    0x04, Ldarg2, "ldarg.2", None;
+    0x05, Ldarg3, "ldarg.3", None;
+    0x06, Ldloc0, "ldloc.0", None;
+    0x07, Ldloc1, "ldloc.1", None;
+    0x08, Ldloc2, "ldloc.2", None;
+    0x09, Ldloc3, "ldloc.3", None;
+    0x0a, Stloc0, "stloc.0", None;
+    0x0b, Stloc1, "stloc.1", None;
+    0x0c, Stloc2, "stloc.2", None;
+    0x0d, Stloc3, "stloc.3", None;
+    0x0e, LdargS, "ldarg.s", ShortVar;
+    0x0f, LdargaS, "ldarga.s", ShortVar;
+    0x10, StargS, "starg.s", ShortVar;
+    0x11, LdlocS, "ldloc.s", ShortVar;
+    0x12, LdlocaS, "ldloca.s", ShortVar;
+    0x13, StlocS, "stloc.s", ShortVar;
+    0x14, Ldnull, "ldnull", None;
+    0x15, LdcI4M1, "ldc.i4.m1", None;
+    0x16, LdcI40, "ldc.i4.0", None;
+    0x17, LdcI41, "ldc.i4.1", None;
+    0x18, LdcI42, "ldc.i4.2", None;
+    0x19, LdcI43, "ldc.i4.3", None;
+    0x1a, LdcI44, "ldc.i4.4", None;
+    0x1b, LdcI45, "ldc.i4.5", None;
+    0x1c, LdcI46, "ldc.i4.6", None;
+    0x1d, LdcI47, "ldc.i4.7", None;
+    0x1e, LdcI48, "ldc.i4.8", None;
+    0x1f, LdcI4S, "ldc.i4.s", ShortI;
+    0x20, LdcI4, "ldc.i4", I;
+    0x21, LdcI8, "ldc.i8", I8;
+    0x22, LdcR4, "ldc.r4", ShortR;
+    0x23, LdcR8, "ldc.r8", R;
+    0x25, Dup, "dup", None;
+    0x26, Pop, "pop", None;
+    0x27, Jmp, "jmp", Method;
+    0x28, Call, "call", Method;
+    0x29, Calli, "calli", Sig;
+    0x2a, Ret, "ret", None;
+    0x2b, BrS, "br.s", ShortBrTarget;
+    0x2c, BrfalseS, "brfalse.s", ShortBrTarget;
+    0x2d, BrtrueS, "brtrue.s", ShortBrTarget;
+    0x2e, BeqS, "beq.s", ShortBrTarget;
+    0x2f, BgeS, "bge.s", ShortBrTarget;
+    0x30, BgtS, "bgt.s", ShortBrTarget;
+    0x31, BleS, "ble.s", ShortBrTarget;
+    0x32, BltS, "blt.s", ShortBrTarget;
+    0x33, BneUnS, "bne.un.s", ShortBrTarget;
+    0x34, BgeUnS, "bge.un.s", ShortBrTarget;
+    0x35, BgtUnS, "bgt.un.s", ShortBrTarget;
+    0x36, BleUnS, "ble.un.s", ShortBrTarget;
+    0x37, BltUnS, "blt.un.s", ShortBrTarget;
+    0x38, Br, "br", BrTarget;
+    0x39, Brfalse, "brfalse", BrTarget;
+    0x3a, Brtrue, "brtrue", BrTarget;
+    0x3b, Beq, "beq", BrTarget;
+    0x3c, Bge, "bge", BrTarget;
+    0x3d, Bgt, "bgt", BrTarget;
+    0x3e, Ble, "ble", BrTarget;
+    0x3f, Blt, "blt", BrTarget;
+    0x40, BneUn, "bne.un", BrTarget;
+    0x41, BgeUn, "bge.un", BrTarget;
+    0x42, BgtUn, "bgt.un", BrTarget;
+    0x43, BleUn, "ble.un", BrTarget;
+    0x44, BltUn, "blt.un", BrTarget;
+    0x45, Switch, "switch", Switch;
+    0x46, LdindI1, "ldind.i1", None;
+    0x47, LdindU1, "ldind.u1", None;
+    0x48, LdindI2, "ldind.i2", None;
+    0x49, LdindU2, "ldind.u2", None;
+    0x4a, LdindI4, "ldind.i4", None;
+    0x4b, LdindU4, "ldind.u4", None;
+    0x4c, LdindI8, "ldind.i8", None;
+    0x4d, LdindI, "ldind.i", None;
+    0x4e, LdindR4, "ldind.r4", None;
+    0x4f, LdindR8, "ldind.r8", None;
+    0x50, LdindRef, "ldind.ref", None;
+    0x51, StindRef, "stind.ref", None;
+    0x52, StindI1, "stind.i1", None;
+    0x53, StindI2, "stind.i2", None;
+    0x54, StindI4, "stind.i4", None;
+    0x55, StindI8, "stind.i8", None;
+    0x56, StindR4, "stind.r4", None;
+    0x57, StindR8, "stind.r8", None;
+    0x58, Add, "add", None;
+    0x59, Sub, "sub", None;
+    0x5a, Mul, "mul", None;
+    0x5b, Div, "div", None;
+    0x5c, DivUn, "div.un", None;
+    0x5d, Rem, "rem", None;
+    0x5e, RemUn, "rem.un", None;
+    0x5f, And, "and", None;
+    0x60, Or, "or", None;
+    0x61, Xor, "xor", None;
+    0x62, Shl, "shl", None;
+    0x63, Shr, "shr", None;
+    0x64, ShrUn, "shr.un", None;
+    0x65, Neg, "neg", None;
+    0x66, Not, "not", None;
+    0x67, ConvI1, "conv.i1", None;
+    0x68, ConvI2, "conv.i2", None;
+    0x69, ConvI4, "conv.i4", None;
+    0x6a, ConvI8, "conv.i8", None;
+    0x6b, ConvR4, "conv.r4", None;
+    0x6c, ConvR8, "conv.r8", None;
+    0x6d, ConvU4, "conv.u4", None;
+    0x6e, ConvU8, "conv.u8", None;
+    0x6f, Callvirt, "callvirt", Method;
+    0x70, Cpobj, "cpobj", Type;
+    0x71, Ldobj, "ldobj", Type;
+    0x72, Ldstr, "ldstr", String;
+    0x73, Newobj, "newobj", Method;
+    0x74, Castclass, "castclass", Type;
+    0x75, Isinst, "isinst", Type;
+    0x76, ConvRUn, "conv.r.un", None;
+    0x79, Unbox, "unbox", Type;
+    0x7a, Throw, "throw", None;
+    0x7b, Ldfld, "ldfld", Field;
+    0x7c, Ldflda, "ldflda", Field;
+    0x7d, Stfld, "stfld", Field;
+    0x7e, Ldsfld, "ldsfld", Field;
+    0x7f, Ldsflda, "ldsflda", Field;
+    0x80, Stsfld, "stsfld", Field;
+    0x81, Stobj, "stobj", Type;
+    0x82, ConvOvfI1Un, "conv.ovf.i1.un", None;
+    0x83, ConvOvfI2Un, "conv.ovf.i2.un", None;
+    0x84, ConvOvfI4Un, "conv.ovf.i4.un", None;
+    0x85, ConvOvfI8Un, "conv.ovf.i8.un", None;
+    0x86, ConvOvfU1Un, "conv.ovf.u1.un", None;
+    0x87, ConvOvfU2Un, "conv.ovf.u2.un", None;
+    0x88, ConvOvfU4Un, "conv.ovf.u4.un", None;
+    0x89, ConvOvfU8Un, "conv.ovf.u8.un", None;
+    0x8a, ConvOvfIUn, "conv.ovf.i.un", None;
+    0x8b, ConvOvfUUn, "conv.ovf.u.un", None;
+    0x8c, Box, "box", Type;
+    0x8d, Newarr, "newarr", Type;
+    0x8e, Ldlen, "ldlen", None;
+    0x8f, Ldelema, "ldelema", Type;
+    0x90, LdelemI1, "ldelem.i1", None;
+    0x91, LdelemU1, "ldelem.u1", None;
+    0x92, LdelemI2, "ldelem.i2", None;
+    0x93, LdelemU2, "ldelem.u2", None;
+    0x94, LdelemI4, "ldelem.i4", None;
+    0x95, LdelemU4, "ldelem.u4", None;
+    0x96, LdelemI8, "ldelem.i8", None;
+    0x97, LdelemI, "ldelem.i", None;
+    0x98, LdelemR4, "ldelem.r4", None;
+    0x99, LdelemR8, "ldelem.r8", None;
+    0x9a, LdelemRef, "ldelem.ref", None;
+    0x9b, StelemI, "stelem.i", None;
+    0x9c, StelemI1, "stelem.i1", None;
+    0x9d, StelemI2, "stelem.i2", None;
+    0x9e, StelemI4, "stelem.i4", None;
+    0x9f, StelemI8, "stelem.i8", None;
+    0xa0, StelemR4, "stelem.r4", None;
+    0xa1, StelemR8, "stelem.r8", None;
+    0xa2, StelemRef, "stelem.ref", None;
+    0xa3, Ldelem, "ldelem", Type;
+    0xa4, Stelem, "stelem", Type;
+    0xa5, UnboxAny, "unbox.any", Type;
+    0xb3, ConvOvfI1, "conv.ovf.i1", None;
+    0xb4, ConvOvfU1, "conv.ovf.u1", None;
+    0xb5, ConvOvfI2, "conv.ovf.i2", None;
+    0xb6, ConvOvfU2, "conv.ovf.u2", None;
+    0xb7, ConvOvfI4, "conv.ovf.i4", None;
+    0xb8, ConvOvfU4, "conv.ovf.u4", None;
+    0xb9, ConvOvfI8, "conv.ovf.i8", None;
+    0xba, ConvOvfU8, "conv.ovf.u8", None;
+    0xc2, Refanyval, "refanyval", Type;
+    0xc3, Ckfinite, "ckfinite", None;
+    0xc6, Mkrefany, "mkrefany", Type;
+    0xd0, Ldtoken, "ldtoken", Tok;
+    0xd1, ConvU2, "conv.u2", None;
+    0xd2, ConvU1, "conv.u1", None;
+    0xd3, ConvI, "conv.i", None;
+    0xd4, ConvOvfI, "conv.ovf.i", None;
+    0xd5, ConvOvfU, "conv.ovf.u", None;
+    0xd6, AddOvf, "add.ovf", None;
+    0xd7, AddOvfUn, "add.ovf.un", None;
+    0xd8, MulOvf, "mul.ovf", None;
+    0xd9, MulOvfUn, "mul.ovf.un", None;
+    0xda, SubOvf, "sub.ovf", None;
+    0xdb, SubOvfUn, "sub.ovf.un", None;
+    0xdc, Endfinally, "endfinally", None;
+    0xdd, Leave, "leave", BrTarget;
+    0xde, LeaveS, "leave.s", ShortBrTarget;
+    0xdf, StindI, "stind.i", None;
+    0xe0, ConvU, "conv.u", None;
+    0xfe00, Arglist, "arglist", None;
+    0xfe01, Ceq, "ceq", None;
+    0xfe02, Cgt, "cgt", None;
+    0xfe03, CgtUn, "cgt.un", None;
+    0xfe04, Clt, "clt", None;
+    0xfe05, CltUn, "clt.un", None;
+    0xfe06, Ldftn, "ldftn", Method;
+    0xfe07, Ldvirtftn, "ldvirtftn", Method;
+    0xfe09, Ldarg, "ldarg", Var;
+    0xfe0a, Ldarga, "ldarga", Var;
+    0xfe0b, Starg, "starg", Var;
+    0xfe0c, Ldloc, "ldloc", Var;
+    0xfe0d, Ldloca, "ldloca", Var;
+    0xfe0e, Stloc, "stloc", Var;
+    0xfe0f, Localloc, "localloc", None;
+    0xfe11, Endfilter, "endfilter", None;
+    0xfe12, Unaligned, "unaligned.", ShortI;
+    0xfe13, Volatile, "volatile.", None;
+    0xfe14, Tail, "tail.", None;
+    0xfe15, Initobj, "initobj", Type;
+    0xfe16, Constrained, "constrained.", Type;
+    0xfe17, Cpblk, "cpblk", None;
+    0xfe18, Initblk, "initblk", None;
+    0xfe19, No, "no.", ShortI;
+    0xfe1a, Rethrow, "rethrow", None;
+    0xfe1c, Sizeof, "sizeof", Type;
+    0xfe1d, Refanytype, "refanytype", None;
+    0xfe1e, Readonly, "readonly.", None;
+}
+
+impl OpCode {
+    /// Size of the opcode itself, without the operand.
+    pub fn size(self) -> u32 {
+        if self.value() > 0xff {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+/// Flags of the `no.` prefix: checks the runtime may skip.
+pub const CIL_NO_TYPECHECK: u8 = 0x1;
+pub const CIL_NO_RANGECHECK: u8 = 0x2;
+pub const CIL_NO_NULLCHECK: u8 = 0x4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Prefix {
+    /// Alignment of the address, 1, 2 or 4
+    Unaligned(u8),
+    Volatile,
+    Tail,
+    /// Type the `callvirt` receiver is constrained to
+    Constrained(Token),
+    Readonly,
+    /// Combination of `CIL_NO_*` flags
+    No(u8),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    None,
+    Int8(i8),
+    /// Operand of `unaligned.` and `no.`
+    UInt8(u8),
+    Int32(i32),
+    Int64(i64),
+    Float32(f32),
+    Float64(f64),
+    /// Argument or local variable index
+    Var(u16),
+    /// Absolute code offset of the branch target
+    Target(u32),
+    /// Absolute code offsets of the switch targets
+    Switch(Vec<u32>),
+    Token(Token),
+}
+
+/// A decoded instruction together with the prefixes applied to it.
+#[derive(Debug, Clone)]
+pub struct Instruction<'a> {
+    /// Code offset of the instruction, or of its first prefix
+    pub offset: u32,
+    /// Total size including prefixes
+    pub size: u32,
+    pub prefixes: Vec<Prefix>,
+    pub opcode: OpCode,
+    pub operand: Operand,
+    /// Row the token operand points to, when decoded against the metadata tables
+    pub resolved: Option<Row<'a>>,
+}
+
+impl<'a> Instruction<'a> {
+    /// Offset of the instruction that follows in the code stream.
+    pub fn next_offset(&self) -> u32 {
+        self.offset + self.size
+    }
+
+    pub fn token(&self) -> Option<Token> {
+        match self.operand {
+            Operand::Token(token) => Some(token),
+            _ => None,
+        }
+    }
+
+    pub fn has_prefix(&self, prefix: Prefix) -> bool {
+        self.prefixes.contains(&prefix)
+    }
+
+    /// The type token of a `constrained.` prefix.
+    pub fn constrained(&self) -> Option<Token> {
+        self.prefixes.iter().find_map(|x| match x {
+            Prefix::Constrained(token) => Some(*token),
+            _ => None,
+        })
+    }
+}
+
+fn branch_target(next: usize, delta: i64) -> Result<u32, Error> {
+    u32::try_from(next as i64 + delta).map_err(|_| Error::BadInstruction {
+        offset: next,
+        reason: format!("Branch targets offset {} out of range", next as i64 + delta),
+    })
+}
+
+fn read_opcode(code: &[u8], offset: &mut usize) -> Result<OpCode, Error> {
+    let start = *offset;
+    let first: u8 = code.gread(offset)?;
+    let value = if first == 0xfe {
+        0xfe00 | u16::from(code.gread::<u8>(offset)?)
+    } else {
+        u16::from(first)
+    };
+    OpCode::from_u16(value).ok_or_else(|| Error::BadInstruction {
+        offset: start,
+        reason: format!("Invalid opcode 0x{:02x}", value),
+    })
+}
+
+fn read_operand(code: &[u8], offset: &mut usize, opcode: OpCode) -> Result<Operand, Error> {
+    Ok(match opcode.operand_kind() {
+        OperandKind::None => Operand::None,
+        OperandKind::ShortI => match opcode {
+            OpCode::Unaligned | OpCode::No => Operand::UInt8(code.gread(offset)?),
+            _ => Operand::Int8(code.gread(offset)?),
+        },
+        OperandKind::I => Operand::Int32(code.gread_with(offset, scroll::LE)?),
+        OperandKind::I8 => Operand::Int64(code.gread_with(offset, scroll::LE)?),
+        OperandKind::ShortR => Operand::Float32(code.gread_with(offset, scroll::LE)?),
+        OperandKind::R => Operand::Float64(code.gread_with(offset, scroll::LE)?),
+        OperandKind::ShortVar => Operand::Var(u16::from(code.gread::<u8>(offset)?)),
+        OperandKind::Var => Operand::Var(code.gread_with(offset, scroll::LE)?),
+        OperandKind::ShortBrTarget => {
+            let delta: i8 = code.gread(offset)?;
+            Operand::Target(branch_target(*offset, i64::from(delta))?)
+        }
+        OperandKind::BrTarget => {
+            let delta: i32 = code.gread_with(offset, scroll::LE)?;
+            Operand::Target(branch_target(*offset, i64::from(delta))?)
+        }
+        OperandKind::Switch => {
+            let count: u32 = code.gread_with(offset, scroll::LE)?;
+            // Targets are relative to the end of the whole instruction
+            let next = (count as usize)
+                .checked_mul(4)
+                .and_then(|x| x.checked_add(*offset))
+                .filter(|&x| x <= code.len())
+                .ok_or_else(|| Error::BadInstruction {
+                    offset: *offset,
+                    reason: format!("Switch with {} targets is truncated", count),
+                })?;
+            let mut targets = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let delta: i32 = code.gread_with(offset, scroll::LE)?;
+                targets.push(branch_target(next, i64::from(delta))?);
+            }
+            Operand::Switch(targets)
+        }
+        OperandKind::Method
+        | OperandKind::Field
+        | OperandKind::Type
+        | OperandKind::Tok
+        | OperandKind::String
+        | OperandKind::Sig => Operand::Token(Token::decode(code.gread_with(offset, scroll::LE)?)?),
+    })
+}
+
+/// Decodes the instruction starting at `offset`, prefixes included.
+///
+/// Token operands are resolved against `tables` when given; tokens out of range are an error then.
+pub fn decode_instruction<'a>(
+    code: &[u8],
+    offset: &mut usize,
+    tables: Option<&'a TildaStream>,
+) -> Result<Instruction<'a>, Error> {
+    let start = *offset;
+    let mut prefixes = Vec::new();
+    let opcode = loop {
+        let opcode = read_opcode(code, offset)?;
+        let prefix = match opcode {
+            OpCode::Unaligned => Prefix::Unaligned(code.gread(offset)?),
+            OpCode::Volatile => Prefix::Volatile,
+            OpCode::Tail => Prefix::Tail,
+            OpCode::Constrained => Prefix::Constrained(Token::decode(code.gread_with(offset, scroll::LE)?)?),
+            OpCode::Readonly => Prefix::Readonly,
+            OpCode::No => Prefix::No(code.gread(offset)?),
+            _ => break opcode,
+        };
+        prefixes.push(prefix);
+    };
+    let operand = read_operand(code, offset, opcode)?;
+    let resolved = match (&operand, tables) {
+        (Operand::Token(token), Some(tables)) => Some(tables.resolve(*token)?),
+        _ => None,
+    };
+    Ok(Instruction {
+        offset: start as u32,
+        size: (*offset - start) as u32,
+        prefixes,
+        opcode,
+        operand,
+        resolved,
+    })
+}
+
+/// Decodes a whole method body code stream.
+pub fn decode_instructions<'a>(code: &[u8], tables: Option<&'a TildaStream>) -> Result<Vec<Instruction<'a>>, Error> {
+    let offset = &mut 0;
+    let mut instructions = Vec::new();
+    while *offset < code.len() {
+        instructions.push(decode_instruction(code, offset, tables)?);
+    }
+    Ok(instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tables::TableId;
+    use crate::token::TokenKind;
+
+    fn decode(code: &[u8]) -> Vec<Instruction<'static>> {
+        decode_instructions(code, None).unwrap()
+    }
+
+    #[test]
+    fn switch_targets_are_relative_to_the_next_instruction() {
+        let code = [0x45, 0x02, 0, 0, 0, 0x01, 0, 0, 0, 0xfe, 0xff, 0xff, 0xff, 0x2a];
+        let instructions = decode(&code);
+        assert_eq!((instructions[0].opcode, instructions[0].size), (OpCode::Switch, 13));
+        assert_eq!(instructions[0].operand, Operand::Switch(vec![14, 11]));
+        assert_eq!((instructions[1].opcode, instructions[1].offset), (OpCode::Ret, 13));
+
+        assert!(decode_instructions(&[0x45, 0xff, 0, 0, 0, 0, 0, 0, 0], None).is_err());
+    }
+
+    #[test]
+    fn prefixes_belong_to_the_next_instruction() {
+        // constrained. [1]TypeDef callvirt [3]MemberRef
+        let code = [0xfe, 0x16, 0x01, 0, 0, 0x02, 0x6f, 0x03, 0, 0, 0x0a];
+        let instructions = decode(&code);
+        assert_eq!(instructions.len(), 1);
+        let callvirt = &instructions[0];
+        assert_eq!(
+            (callvirt.opcode, callvirt.offset, callvirt.size),
+            (OpCode::Callvirt, 0, 11)
+        );
+        assert_eq!(
+            callvirt.constrained(),
+            Some(Token::new(TokenKind::Table(TableId::TypeDef), 1))
+        );
+        assert_eq!(
+            callvirt.token(),
+            Some(Token::new(TokenKind::Table(TableId::MemberRef), 3))
+        );
+
+        // unaligned. 1 volatile. ldind.i4
+        let instructions = decode(&[0xfe, 0x12, 0x01, 0xfe, 0x13, 0x4a]);
+        assert_eq!(instructions[0].opcode, OpCode::LdindI4);
+        assert_eq!(instructions[0].prefixes, vec![Prefix::Unaligned(1), Prefix::Volatile]);
+        assert!(instructions[0].has_prefix(Prefix::Volatile));
+    }
+
+    #[test]
+    fn operands() {
+        let code = [
+            0x1f, 0xff, // ldc.i4.s -1
+            0xfe, 0x09, 0x01, 0x00, // ldarg 1
+            0x2b, 0xf8, // br.s 0
+            0x38, 0x00, 0x00, 0x00, 0x00, // br 13
+        ];
+        let operands = decode(&code).into_iter().map(|x| x.operand).collect::<Vec<_>>();
+        assert_eq!(
+            operands,
+            vec![
+                Operand::Int8(-1),
+                Operand::Var(1),
+                Operand::Target(0),
+                Operand::Target(13)
+            ]
+        );
+
+        assert!(decode_instructions(&[0x2b, 0x80], None).is_err());
+        assert!(decode_instructions(&[0xfe, 0xff], None).is_err());
+        assert!(decode_instructions(&[0x20, 0x01], None).is_err());
+    }
+}
@@ -0,0 +1,2397 @@
+//! CIL interpreter (ECMA-335 III) running the methods of an image and of the assemblies it refers to.
+//!
+//! Values on the evaluation stack have one of the stack types of I.12.3.2.1: int32, int64, native
+//! int, F, O, & and value types. Arguments and locals keep the type of their signature, narrower
+//! integers are truncated when stored and extended again when loaded. Native ints are 64 bits wide
+//! whatever the platform of the image.
+//!
+//! Exceptions raised by the program, e.g. a division by zero, end the run: catch and filter
+//! handlers never execute, only finally handlers run when `leave` exits their protected block.
+//!
+//! The heap is collected between instructions once enough was allocated, and on `GC.Collect`.
+//! Finalizers run right after the collection that queued them, as if the finalizer thread got to
+//! them at once. `System.GC` and `System.Runtime.InteropServices.GCHandle` are built in, calls to
+//! any other method outside the images are not supported.
+//!
+//! `callvirt`, `ldvirtftn` and `constrained.` dispatch on the runtime type through the vtable and
+//! interface maps the type loader builds. Method pointers index the methods `ldftn` and `ldvirtftn` took.
+//!
+//! Generic types and methods are specialized: every instantiation gets code, a method table and
+//! static fields of its own, built on first use.
+//!
+//! With an `AssemblyResolver`, the assemblies the image refers to are loaded as their types are, and
+//! their methods run like those of the image: `List<T>` and the LINQ operators run the code of the
+//! mscorlib and System.Core the resolver finds. Without one, or for the assemblies it can't find,
+//! types only get a method table for their name and calling their methods fails with `Error::NotSupported`.
+
+use crate::assembly::{Assembly, MethodDefinition};
+use crate::coded_index::{MemberRefParent, MethodDefOrRef, TypeDefOrRef, TypeOrMethodDef};
+use crate::disasm::Disassembler;
+use crate::error::Error;
+use crate::instruction::{decode_instructions, Instruction, OpCode, Operand};
+use crate::method_body::{ExceptionClause, ExceptionClauseKind};
+use crate::resolver::AssemblyResolver;
+use crate::runtime::{
+    decode_reference, encode_reference, FieldSlot, Generics, Handle, HandleKind, Heap, Image, Kind, MethodTable,
+    Object, ObjectRef, RowId, TypeLoader, MAX_ARRAY_SIZE,
+};
+use crate::signature::{MethodSignature, MethodSpecSignature, Param, Type};
+use crate::tables::TableId;
+use crate::token::{Token, TokenKind};
+use scroll::{Pread, Pwrite};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Deeper recursion than this is reported as a stack overflow.
+const MAX_CALL_DEPTH: usize = 1024;
+
+const FIELD_ATTRIBUTES_STATIC: u16 = 0x10;
+const METHOD_ATTRIBUTES_STATIC: u16 = 0x10;
+const METHOD_ATTRIBUTES_VIRTUAL: u16 = 0x40;
+const METHOD_ATTRIBUTES_ABSTRACT: u16 = 0x400;
+
+/// A managed pointer, `frame` counts from the outermost call.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pointer {
+    Argument {
+        frame: usize,
+        index: u16,
+    },
+    Local {
+        frame: usize,
+        index: u16,
+    },
+    /// Into the data of an object, e.g. the `this` of a constructor called on a new value type
+    Heap {
+        object: ObjectRef,
+        offset: u32,
+    },
+    /// To a static field by its index in the statics of the interpreter
+    Static {
+        slot: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Int32(i32),
+    Int64(i64),
+    NativeInt(i64),
+    /// F, kept as a double whatever the precision of the slot it came from
+    Float(f64),
+    /// O, `None` is the null reference
+    Object(Option<ObjectRef>),
+    /// &
+    Pointer(Pointer),
+    /// Value type other than an enum or a primitive type, its data laid out as the method table says
+    ValueType(Rc<MethodTable>, Vec<u8>),
+}
+
+impl Value {
+    /// Name of the stack type as ECMA-335 spells it.
+    pub fn stack_type(&self) -> &'static str {
+        match self {
+            Value::Int32(_) => "int32",
+            Value::Int64(_) => "int64",
+            Value::NativeInt(_) => "native int",
+            Value::Float(_) => "F",
+            Value::Object(_) => "O",
+            Value::Pointer(_) => "&",
+            Value::ValueType(..) => "valuetype",
+        }
+    }
+}
+
+/// `value` converted the way storing it into a slot of `kind` does.
+fn store(kind: Kind, value: Value) -> Result<Value, Fault> {
+    let stack_type = value.stack_type();
+    let converted = match (kind, value) {
+        (Kind::I1, Value::Int32(x)) => Some(Value::Int32(i32::from(x as i8))),
+        (Kind::U1, Value::Int32(x)) => Some(Value::Int32(i32::from(x as u8))),
+        (Kind::I2, Value::Int32(x)) => Some(Value::Int32(i32::from(x as i16))),
+        (Kind::U2, Value::Int32(x)) => Some(Value::Int32(i32::from(x as u16))),
+        (Kind::I4, value @ Value::Int32(_)) => Some(value),
+        (Kind::I1, Value::NativeInt(x)) => Some(Value::Int32(i32::from(x as i8))),
+        (Kind::U1, Value::NativeInt(x)) => Some(Value::Int32(i32::from(x as u8))),
+        (Kind::I2, Value::NativeInt(x)) => Some(Value::Int32(i32::from(x as i16))),
+        (Kind::U2, Value::NativeInt(x)) => Some(Value::Int32(i32::from(x as u16))),
+        (Kind::I4, Value::NativeInt(x)) => Some(Value::Int32(x as i32)),
+        (Kind::I8, value @ Value::Int64(_)) => Some(value),
+        (Kind::I, Value::Int32(x)) => Some(Value::NativeInt(i64::from(x))),
+        (Kind::I, value @ Value::NativeInt(_)) => Some(value),
+        (Kind::R4, Value::Float(x)) => Some(Value::Float(f64::from(x as f32))),
+        (Kind::R8, value @ Value::Float(_)) => Some(value),
+        (Kind::Object, value @ Value::Object(_)) => Some(value),
+        // Unmanaged pointers may be stored in byref slots, unverifiable but valid
+        (Kind::Pointer, value @ Value::Pointer(_)) | (Kind::Pointer, value @ Value::NativeInt(_)) => Some(value),
+        (Kind::ValueType(id), Value::ValueType(ty, data)) if ty.id == id => Some(Value::ValueType(ty, data)),
+        _ => None,
+    };
+    converted.ok_or_else(|| Fault::Invalid(format!("Cannot store {} into a {:?} slot", stack_type, kind)))
+}
+
+/// Writes `value`, already converted by `store`, into object data.
+fn encode(bytes: &mut [u8], kind: Kind, value: &Value) -> Result<(), Fault> {
+    match (kind, value) {
+        (Kind::I1, Value::Int32(x)) | (Kind::U1, Value::Int32(x)) => bytes.pwrite(*x as u8, 0)?,
+        (Kind::I2, Value::Int32(x)) | (Kind::U2, Value::Int32(x)) => bytes.pwrite_with(*x as u16, 0, scroll::LE)?,
+        (Kind::I4, Value::Int32(x)) => bytes.pwrite_with(*x, 0, scroll::LE)?,
+        (Kind::I8, Value::Int64(x)) | (Kind::I, Value::NativeInt(x)) => bytes.pwrite_with(*x, 0, scroll::LE)?,
+        (Kind::R4, Value::Float(x)) => bytes.pwrite_with(*x as f32, 0, scroll::LE)?,
+        (Kind::R8, Value::Float(x)) => bytes.pwrite_with(*x, 0, scroll::LE)?,
+        (Kind::Object, Value::Object(x)) => bytes.pwrite_with(encode_reference(*x), 0, scroll::LE)?,
+        (Kind::ValueType(_), Value::ValueType(_, data)) => match bytes.get_mut(..data.len()) {
+            Some(bytes) => {
+                bytes.copy_from_slice(data);
+                data.len()
+            }
+            None => return Err(Fault::Invalid("Value type doesn't fit its field".to_string())),
+        },
+        _ => {
+            return Err(Fault::Invalid(format!(
+                "Cannot store {} into a {:?} field",
+                value.stack_type(),
+                kind
+            )))
+        }
+    };
+    Ok(())
+}
+
+/// Why an instruction couldn't complete, turned into an `Error` once the location is known.
+enum Fault {
+    Exception(&'static str),
+    Invalid(String),
+    NotSupported(String),
+    Error(Error),
+}
+
+impl Fault {
+    fn at(self, method: RowId, offset: u32) -> Error {
+        let method = Token::new(TokenKind::Table(TableId::MethodDef), method.rid);
+        match self {
+            Fault::Exception(exception) => Error::UnhandledException {
+                exception: exception.to_string(),
+                method,
+                offset,
+            },
+            Fault::Invalid(reason) => Error::InvalidProgram { method, offset, reason },
+            Fault::NotSupported(reason) => Error::NotSupported { method, offset, reason },
+            Fault::Error(error) => error,
+        }
+    }
+}
+
+impl From<Error> for Fault {
+    fn from(error: Error) -> Self {
+        Fault::Error(error)
+    }
+}
+
+impl From<scroll::Error> for Fault {
+    fn from(error: scroll::Error) -> Self {
+        Fault::Error(error.into())
+    }
+}
+
+fn underflow() -> Fault {
+    Fault::Invalid("Stack underflow".to_string())
+}
+
+fn null_reference() -> Fault {
+    Fault::Exception("System.NullReferenceException")
+}
+
+/// Operand types an instruction doesn't accept.
+fn mismatch(opcode: OpCode, values: &[&Value]) -> Fault {
+    let types = values.iter().map(|x| x.stack_type()).collect::<Vec<_>>();
+    Fault::Invalid(format!("{} on {}", opcode.mnemonic(), types.join(" and ")))
+}
+
+/// Integer stack type of an operation, binary operations on int32 and native int are native int.
+#[derive(Debug, Copy, Clone)]
+enum Width {
+    Int32,
+    Int64,
+    Native,
+}
+
+impl Width {
+    fn bits(self) -> u32 {
+        match self {
+            Width::Int32 => 32,
+            Width::Int64 | Width::Native => 64,
+        }
+    }
+
+    fn signed(self, x: i64) -> i128 {
+        match self {
+            Width::Int32 => i128::from(x as i32),
+            Width::Int64 | Width::Native => i128::from(x),
+        }
+    }
+
+    fn unsigned(self, x: i64) -> i128 {
+        match self {
+            Width::Int32 => i128::from(x as u32),
+            Width::Int64 | Width::Native => i128::from(x as u64),
+        }
+    }
+
+    /// The low bits of `x`.
+    fn wrap(self, x: i128) -> Value {
+        match self {
+            Width::Int32 => Value::Int32(x as i32),
+            Width::Int64 => Value::Int64(x as i64),
+            Width::Native => Value::NativeInt(x as i64),
+        }
+    }
+
+    fn checked(self, x: i128, unsigned: bool) -> Result<Value, Fault> {
+        if in_range(x, self.bits(), !unsigned) {
+            Ok(self.wrap(x))
+        } else {
+            Err(Fault::Exception("System.OverflowException"))
+        }
+    }
+}
+
+fn in_range(x: i128, bits: u32, signed: bool) -> bool {
+    if signed {
+        -(1 << (bits - 1)) <= x && x < 1 << (bits - 1)
+    } else {
+        0 <= x && x < 1 << bits
+    }
+}
+
+fn integers(a: &Value, b: &Value) -> Option<(Width, i64, i64)> {
+    match (a, b) {
+        (&Value::Int32(x), &Value::Int32(y)) => Some((Width::Int32, i64::from(x), i64::from(y))),
+        (&Value::Int64(x), &Value::Int64(y)) => Some((Width::Int64, x, y)),
+        (&Value::NativeInt(x), &Value::NativeInt(y)) => Some((Width::Native, x, y)),
+        (&Value::Int32(x), &Value::NativeInt(y)) => Some((Width::Native, i64::from(x), y)),
+        (&Value::NativeInt(x), &Value::Int32(y)) => Some((Width::Native, x, i64::from(y))),
+        _ => None,
+    }
+}
+
+fn binary(opcode: OpCode, a: &Value, b: &Value) -> Result<Value, Fault> {
+    if let (&Value::Float(x), &Value::Float(y)) = (a, b) {
+        return Ok(Value::Float(match opcode {
+            OpCode::Add => x + y,
+            OpCode::Sub => x - y,
+            OpCode::Mul => x * y,
+            OpCode::Div => x / y,
+            OpCode::Rem => x % y,
+            _ => return Err(mismatch(opcode, &[a, b])),
+        }));
+    }
+    let (width, x, y) = integers(a, b).ok_or_else(|| mismatch(opcode, &[a, b]))?;
+    let (signed_x, signed_y) = (width.signed(x), width.signed(y));
+    let (unsigned_x, unsigned_y) = (width.unsigned(x), width.unsigned(y));
+    match opcode {
+        OpCode::Add => Ok(width.wrap(signed_x + signed_y)),
+        OpCode::Sub => Ok(width.wrap(signed_x - signed_y)),
+        OpCode::Mul => Ok(width.wrap(signed_x * signed_y)),
+        OpCode::AddOvf => width.checked(signed_x + signed_y, false),
+        OpCode::AddOvfUn => width.checked(unsigned_x + unsigned_y, true),
+        OpCode::SubOvf => width.checked(signed_x - signed_y, false),
+        OpCode::SubOvfUn => width.checked(unsigned_x - unsigned_y, true),
+        OpCode::MulOvf => width.checked(signed_x * signed_y, false),
+        OpCode::MulOvfUn => width.checked(unsigned_x * unsigned_y, true),
+        OpCode::Div | OpCode::Rem => {
+            if signed_y == 0 {
+                return Err(Fault::Exception("System.DivideByZeroException"));
+            }
+            if signed_y == -1 && signed_x == -(1 << (width.bits() - 1)) {
+                return Err(Fault::Exception("System.OverflowException"));
+            }
+            Ok(width.wrap(if opcode == OpCode::Div {
+                signed_x / signed_y
+            } else {
+                signed_x % signed_y
+            }))
+        }
+        OpCode::DivUn | OpCode::RemUn => {
+            if unsigned_y == 0 {
+                return Err(Fault::Exception("System.DivideByZeroException"));
+            }
+            Ok(width.wrap(if opcode == OpCode::DivUn {
+                unsigned_x / unsigned_y
+            } else {
+                unsigned_x % unsigned_y
+            }))
+        }
+        OpCode::And => Ok(width.wrap(signed_x & signed_y)),
+        OpCode::Or => Ok(width.wrap(signed_x | signed_y)),
+        OpCode::Xor => Ok(width.wrap(signed_x ^ signed_y)),
+        _ => Err(mismatch(opcode, &[a, b])),
+    }
+}
+
+fn shift(opcode: OpCode, value: &Value, amount: &Value) -> Result<Value, Fault> {
+    let amount = match *amount {
+        Value::Int32(x) => x as u32,
+        Value::NativeInt(x) => x as u32,
+        _ => return Err(mismatch(opcode, &[value, amount])),
+    };
+    Ok(match (opcode, value) {
+        (OpCode::Shl, &Value::Int32(x)) => Value::Int32(x.wrapping_shl(amount)),
+        (OpCode::Shr, &Value::Int32(x)) => Value::Int32(x.wrapping_shr(amount)),
+        (OpCode::ShrUn, &Value::Int32(x)) => Value::Int32((x as u32).wrapping_shr(amount) as i32),
+        (OpCode::Shl, &Value::Int64(x)) => Value::Int64(x.wrapping_shl(amount)),
+        (OpCode::Shr, &Value::Int64(x)) => Value::Int64(x.wrapping_shr(amount)),
+        (OpCode::ShrUn, &Value::Int64(x)) => Value::Int64((x as u64).wrapping_shr(amount) as i64),
+        (OpCode::Shl, &Value::NativeInt(x)) => Value::NativeInt(x.wrapping_shl(amount)),
+        (OpCode::Shr, &Value::NativeInt(x)) => Value::NativeInt(x.wrapping_shr(amount)),
+        (OpCode::ShrUn, &Value::NativeInt(x)) => Value::NativeInt((x as u64).wrapping_shr(amount) as i64),
+        _ => return Err(mismatch(opcode, &[value, &Value::Int32(amount as i32)])),
+    })
+}
+
+fn unary(opcode: OpCode, value: &Value) -> Result<Value, Fault> {
+    Ok(match (opcode, value) {
+        (OpCode::Neg, &Value::Int32(x)) => Value::Int32(x.wrapping_neg()),
+        (OpCode::Neg, &Value::Int64(x)) => Value::Int64(x.wrapping_neg()),
+        (OpCode::Neg, &Value::NativeInt(x)) => Value::NativeInt(x.wrapping_neg()),
+        (OpCode::Neg, &Value::Float(x)) => Value::Float(-x),
+        (OpCode::Not, &Value::Int32(x)) => Value::Int32(!x),
+        (OpCode::Not, &Value::Int64(x)) => Value::Int64(!x),
+        (OpCode::Not, &Value::NativeInt(x)) => Value::NativeInt(!x),
+        _ => return Err(mismatch(opcode, &[value])),
+    })
+}
+
+/// Ordering of `a` and `b`, `None` when unordered: a NaN operand or distinct managed pointers.
+fn compare(opcode: OpCode, a: &Value, b: &Value, unsigned: bool) -> Result<Option<Ordering>, Fault> {
+    if let (&Value::Float(x), &Value::Float(y)) = (a, b) {
+        return Ok(x.partial_cmp(&y));
+    }
+    if let Some((width, x, y)) = integers(a, b) {
+        return Ok(Some(if unsigned {
+            width.unsigned(x).cmp(&width.unsigned(y))
+        } else {
+            width.signed(x).cmp(&width.signed(y))
+        }));
+    }
+    match (a, b) {
+        // Only equality and `cgt.un` against null make sense for references, any consistent order will do
+        (&Value::Object(x), &Value::Object(y)) => Ok(Some(encode_reference(x).cmp(&encode_reference(y)))),
+        (Value::Pointer(x), Value::Pointer(y)) if x == y => Ok(Some(Ordering::Equal)),
+        (Value::Pointer(_), Value::Pointer(_)) => Ok(None),
+        _ => Err(mismatch(opcode, &[a, b])),
+    }
+}
+
+/// Whether a comparison or conditional branch holds for `ordering`, unordered counting as true for `.un` forms.
+fn holds(opcode: OpCode, ordering: Option<Ordering>) -> bool {
+    use std::cmp::Ordering::*;
+    match opcode {
+        OpCode::Ceq | OpCode::Beq | OpCode::BeqS => ordering == Some(Equal),
+        OpCode::BneUn | OpCode::BneUnS => ordering != Some(Equal),
+        OpCode::Cgt | OpCode::Bgt | OpCode::BgtS => ordering == Some(Greater),
+        OpCode::CgtUn | OpCode::BgtUn | OpCode::BgtUnS => ordering != Some(Less) && ordering != Some(Equal),
+        OpCode::Clt | OpCode::Blt | OpCode::BltS => ordering == Some(Less),
+        OpCode::CltUn | OpCode::BltUn | OpCode::BltUnS => ordering != Some(Greater) && ordering != Some(Equal),
+        OpCode::Bge | OpCode::BgeS => ordering == Some(Greater) || ordering == Some(Equal),
+        OpCode::BgeUn | OpCode::BgeUnS => ordering != Some(Less),
+        OpCode::Ble | OpCode::BleS => ordering == Some(Less) || ordering == Some(Equal),
+        OpCode::BleUn | OpCode::BleUnS => ordering != Some(Greater),
+        _ => false,
+    }
+}
+
+fn is_unsigned_comparison(opcode: OpCode) -> bool {
+    use crate::instruction::OpCode::*;
+    [
+        CgtUn, CltUn, BneUn, BgeUn, BgtUn, BleUn, BltUn, BneUnS, BgeUnS, BgtUnS, BleUnS, BltUnS,
+    ]
+    .contains(&opcode)
+}
+
+fn truthy(opcode: OpCode, value: &Value) -> Result<bool, Fault> {
+    match *value {
+        Value::Int32(x) => Ok(x != 0),
+        Value::Int64(x) | Value::NativeInt(x) => Ok(x != 0),
+        Value::Object(x) => Ok(x.is_some()),
+        Value::Pointer(_) => Ok(true),
+        Value::Float(_) | Value::ValueType(..) => Err(mismatch(opcode, &[value])),
+    }
+}
+
+/// Target of an integer conversion: its width, signedness and whether it is a native int.
+struct Target {
+    bits: u32,
+    signed: bool,
+    native: bool,
+}
+
+fn convert_float(opcode: OpCode, value: &Value) -> Result<Value, Fault> {
+    let x = match *value {
+        Value::Float(x) => x,
+        Value::Int32(x) if opcode == OpCode::ConvRUn => f64::from(x as u32),
+        Value::Int64(x) | Value::NativeInt(x) if opcode == OpCode::ConvRUn => x as u64 as f64,
+        Value::Int32(x) => f64::from(x),
+        Value::Int64(x) | Value::NativeInt(x) => x as f64,
+        _ => return Err(mismatch(opcode, &[value])),
+    };
+    Ok(Value::Float(if opcode == OpCode::ConvR4 {
+        f64::from(x as f32)
+    } else {
+        x
+    }))
+}
+
+fn convert(opcode: OpCode, value: &Value) -> Result<Value, Fault> {
+    use crate::instruction::OpCode::*;
+    let target = |bits, signed, native| Target { bits, signed, native };
+    // Target, whether overflow is checked and whether the source is read as unsigned
+    let (target, checked, unsigned_source) = match opcode {
+        ConvR4 | ConvR8 | ConvRUn => return convert_float(opcode, value),
+        ConvI1 => (target(8, true, false), false, false),
+        ConvI2 => (target(16, true, false), false, false),
+        ConvI4 => (target(32, true, false), false, false),
+        ConvI8 => (target(64, true, false), false, false),
+        ConvI => (target(64, true, true), false, false),
+        ConvU1 => (target(8, false, false), false, false),
+        ConvU2 => (target(16, false, false), false, false),
+        ConvU4 => (target(32, false, false), false, false),
+        ConvU8 => (target(64, false, false), false, false),
+        ConvU => (target(64, false, true), false, false),
+        ConvOvfI1 => (target(8, true, false), true, false),
+        ConvOvfI2 => (target(16, true, false), true, false),
+        ConvOvfI4 => (target(32, true, false), true, false),
+        ConvOvfI8 => (target(64, true, false), true, false),
+        ConvOvfI => (target(64, true, true), true, false),
+        ConvOvfU1 => (target(8, false, false), true, false),
+        ConvOvfU2 => (target(16, false, false), true, false),
+        ConvOvfU4 => (target(32, false, false), true, false),
+        ConvOvfU8 => (target(64, false, false), true, false),
+        ConvOvfU => (target(64, false, true), true, false),
+        ConvOvfI1Un => (target(8, true, false), true, true),
+        ConvOvfI2Un => (target(16, true, false), true, true),
+        ConvOvfI4Un => (target(32, true, false), true, true),
+        ConvOvfI8Un => (target(64, true, false), true, true),
+        ConvOvfIUn => (target(64, true, true), true, true),
+        ConvOvfU1Un => (target(8, false, false), true, true),
+        ConvOvfU2Un => (target(16, false, false), true, true),
+        ConvOvfU4Un => (target(32, false, false), true, true),
+        ConvOvfU8Un => (target(64, false, false), true, true),
+        ConvOvfUUn => (target(64, false, true), true, true),
+        _ => return Err(mismatch(opcode, &[value])),
+    };
+    // Unchecked conversions to unsigned targets zero-extend, to signed ones sign-extend
+    let unsigned = if checked { unsigned_source } else { !target.signed };
+    let integer = |width: Width, x: i64| {
+        if unsigned {
+            width.unsigned(x)
+        } else {
+            width.signed(x)
+        }
+    };
+    let x = match *value {
+        Value::Int32(x) => integer(Width::Int32, i64::from(x)),
+        Value::Int64(x) => integer(Width::Int64, x),
+        Value::NativeInt(x) => integer(Width::Native, x),
+        Value::Float(x) if checked && !x.is_finite() => return Err(Fault::Exception("System.OverflowException")),
+        // Out of range values are unspecified without the check, saturating is as good as anything
+        Value::Float(x) => x.trunc() as i128,
+        _ => return Err(mismatch(opcode, &[value])),
+    };
+    if checked && !in_range(x, target.bits, target.signed) {
+        return Err(Fault::Exception("System.OverflowException"));
+    }
+    Ok(match (target.bits, target.signed) {
+        (8, true) => Value::Int32(i32::from(x as i8)),
+        (8, false) => Value::Int32(i32::from(x as u8)),
+        (16, true) => Value::Int32(i32::from(x as i16)),
+        (16, false) => Value::Int32(i32::from(x as u16)),
+        (32, _) => Value::Int32(x as i32),
+        _ if target.native => Value::NativeInt(x as i64),
+        _ => Value::Int64(x as i64),
+    })
+}
+
+/// Slot kind an `ldind.*`, `stind.*`, `ldelem.*` or `stelem.*` reads or writes, `None` for the forms taking a type token.
+fn opcode_kind(opcode: OpCode) -> Option<Kind> {
+    use crate::instruction::OpCode::*;
+    Some(match opcode {
+        LdindI1 | StindI1 | LdelemI1 | StelemI1 => Kind::I1,
+        LdindU1 | LdelemU1 => Kind::U1,
+        LdindI2 | StindI2 | LdelemI2 | StelemI2 => Kind::I2,
+        LdindU2 | LdelemU2 => Kind::U2,
+        LdindI4 | LdindU4 | StindI4 | LdelemI4 | LdelemU4 | StelemI4 => Kind::I4,
+        LdindI8 | StindI8 | LdelemI8 | StelemI8 => Kind::I8,
+        LdindI | StindI | LdelemI | StelemI => Kind::I,
+        LdindR4 | StindR4 | LdelemR4 | StelemR4 => Kind::R4,
+        LdindR8 | StindR8 | LdelemR8 | StelemR8 => Kind::R8,
+        LdindRef | StindRef | LdelemRef | StelemRef => Kind::Object,
+        _ => return None,
+    })
+}
+
+/// Whether elements of `element` may be accessed as `kind`, integers of the same size being interchangeable.
+fn is_compatible(kind: Kind, element: Kind) -> bool {
+    let integer = |x: Kind| match x {
+        Kind::R4 | Kind::R8 | Kind::Object | Kind::Pointer | Kind::ValueType(_) => None,
+        x => x.primitive_size(),
+    };
+    kind == element || integer(kind).is_some() && integer(kind) == integer(element)
+}
+
+/// Decoded body and slot kinds of a method, built on its first call.
+struct Code<'a> {
+    instructions: Vec<Instruction<'a>>,
+    clauses: Vec<ExceptionClause>,
+    max_stack: usize,
+    /// `this` comes first for instance methods
+    args: Vec<Kind>,
+    locals: Vec<Kind>,
+    returns: Option<Kind>,
+    /// Type arguments of the instantiation the code belongs to
+    generics: Rc<Generics>,
+}
+
+impl<'a> Code<'a> {
+    /// Index of the instruction starting at code offset `offset`.
+    fn index(&self, offset: u32) -> Result<usize, Fault> {
+        self.instructions
+            .binary_search_by_key(&offset, |x| x.offset)
+            .map_err(|_| Fault::Invalid(format!("Branch to IL_{:04x} which is not an instruction", offset)))
+    }
+}
+
+/// Finally handlers a `leave` still has to run before it reaches its target.
+struct Unwind {
+    /// Handler offsets, the next to run last
+    handlers: Vec<u32>,
+    target: u32,
+}
+
+/// Methods outside the images the interpreter implements itself.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Intrinsic {
+    /// `System.Object::.ctor`, which does nothing and ends every constructor chain
+    ObjectConstructor,
+    Collect,
+    KeepAlive,
+    WaitForPendingFinalizers,
+    SuppressFinalize,
+    ReRegisterForFinalize,
+    GetTotalMemory,
+    CollectionCount,
+    AllocHandle,
+    HandleTarget,
+    SetHandleTarget,
+    FreeHandle,
+    IsHandleAllocated,
+}
+
+impl Intrinsic {
+    fn of(parent: &str, name: &str) -> Option<Self> {
+        Some(match (parent, name) {
+            ("System.Object", ".ctor") => Intrinsic::ObjectConstructor,
+            ("System.GC", "Collect") => Intrinsic::Collect,
+            ("System.GC", "KeepAlive") => Intrinsic::KeepAlive,
+            ("System.GC", "WaitForPendingFinalizers") => Intrinsic::WaitForPendingFinalizers,
+            ("System.GC", "SuppressFinalize") => Intrinsic::SuppressFinalize,
+            ("System.GC", "ReRegisterForFinalize") => Intrinsic::ReRegisterForFinalize,
+            ("System.GC", "GetTotalMemory") => Intrinsic::GetTotalMemory,
+            ("System.GC", "CollectionCount") => Intrinsic::CollectionCount,
+            ("System.Runtime.InteropServices.GCHandle", "Alloc") => Intrinsic::AllocHandle,
+            ("System.Runtime.InteropServices.GCHandle", "get_Target") => Intrinsic::HandleTarget,
+            ("System.Runtime.InteropServices.GCHandle", "set_Target") => Intrinsic::SetHandleTarget,
+            ("System.Runtime.InteropServices.GCHandle", "Free") => Intrinsic::FreeHandle,
+            ("System.Runtime.InteropServices.GCHandle", "get_IsAllocated") => Intrinsic::IsHandleAllocated,
+            _ => return None,
+        })
+    }
+}
+
+/// A method a token refers to, with the type declaring it and the type arguments it runs with.
+struct Callee {
+    rid: RowId,
+    owner: Rc<MethodTable>,
+    generics: Rc<Generics>,
+}
+
+/// The object `newobj` pushes once the constructor returns.
+#[derive(Copy, Clone)]
+enum Construction {
+    Object(ObjectRef),
+    /// A value type built in a temporary box, pushed unboxed
+    ValueType(ObjectRef),
+}
+
+struct Frame<'a> {
+    method: RowId,
+    code: Rc<Code<'a>>,
+    /// Index of the current instruction
+    pc: usize,
+    args: Vec<Value>,
+    locals: Vec<Value>,
+    stack: Vec<Value>,
+    unwinds: Vec<Unwind>,
+    constructing: Option<Construction>,
+}
+
+/// What the loop does after an instruction.
+enum Flow {
+    Next,
+    Jump(u32),
+    Call(RowId, Rc<Generics>, Vec<Value>, Option<Construction>),
+    /// Run a type initializer, then the instruction again
+    Initialize(RowId, Rc<Generics>),
+    Return(Option<Value>),
+}
+
+/// Runs methods of an image, keeping decoded bodies, method tables and the heap between calls.
+pub struct Interpreter<'a> {
+    code: HashMap<(RowId, Rc<Generics>), Rc<Code<'a>>>,
+    frames: Vec<Frame<'a>>,
+    types: TypeLoader<'a>,
+    heap: Heap,
+    /// `ldstr` yields the same object for the same token
+    interned: HashMap<RowId, ObjectRef>,
+    /// Static fields, created on first access
+    statics: Vec<(Kind, Value)>,
+    /// Index in `statics` by Field and type arguments of the declaring type
+    static_slots: HashMap<(RowId, Vec<Type>), usize>,
+    /// Instantiations of TypeDefs whose type initializer has run or is running
+    initialized: HashSet<(RowId, Vec<Type>)>,
+    /// Methods `ldftn` and `ldvirtftn` took, method pointers are indexes in it
+    functions: Vec<(RowId, Rc<Generics>)>,
+    /// Whether finalizers are running, those a nested collection queues wait for the outer loop
+    finalizing: bool,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Interpreter for the methods of `assembly`, those of other assemblies aren't run.
+    pub fn new(assembly: &'a Assembly<'a>) -> Self {
+        Self::with_types(TypeLoader::new(assembly))
+    }
+
+    /// Interpreter for the methods of `assembly` and of the assemblies `resolver` finds for its references.
+    pub fn with_resolver<R: AssemblyResolver + 'a>(assembly: Rc<Assembly<'static>>, resolver: R) -> Self {
+        Self::with_types(TypeLoader::with_resolver(assembly, resolver))
+    }
+
+    fn with_types(types: TypeLoader<'a>) -> Self {
+        Self {
+            code: HashMap::new(),
+            frames: Vec::new(),
+            types,
+            heap: Heap::new(),
+            interned: HashMap::new(),
+            statics: Vec::new(),
+            static_slots: HashMap::new(),
+            initialized: HashSet::new(),
+            functions: Vec::new(),
+            finalizing: false,
+        }
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    /// Contents of a string object.
+    pub fn string(&self, object: ObjectRef) -> Option<&str> {
+        match self.heap.get(object) {
+            Some(Object::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Runs the entry point and returns the process exit code, `Main`'s return value or 0 when it returns void.
+    ///
+    /// `Main(string[])` gets an empty array.
+    pub fn run_entry_point(&mut self) -> Result<i32, Error> {
+        let assembly = self.types.image(0);
+        let method = assembly.entry_point()?.ok_or(Error::NoEntryPoint)?;
+        let mut args = Vec::new();
+        if !method.signature()?.params.is_empty() {
+            let element_type = self.types.load_canonical(&Type::String)?;
+            let object = self.heap.allocate(Object::Array {
+                element_type: element_type.ok_or_else(|| Error::NotSupported {
+                    method: method.token(),
+                    offset: 0,
+                    reason: "Strings".to_string(),
+                })?,
+                element: Kind::Object,
+                element_size: Kind::Object.primitive_size().unwrap_or_default(),
+                element_references: vec![0],
+                length: 0,
+                data: Vec::new(),
+            });
+            args.push(Value::Object(Some(object)));
+        }
+        match self.call(method, args)? {
+            Some(Value::Int32(code)) => Ok(code),
+            None => Ok(0),
+            Some(value) => Err(Error::InvalidProgram {
+                method: method.token(),
+                offset: 0,
+                reason: format!("Entry point returns {}", value.stack_type()),
+            }),
+        }
+    }
+
+    /// Calls `method`, a method of the image being run, with `args`, `this` first for instance methods,
+    /// and returns what it returns.
+    ///
+    /// The collector only sees the objects the interpreter itself holds on to: those `args` and the
+    /// returned value reference may be collected once the call is over.
+    pub fn call(&mut self, method: MethodDefinition<'_>, args: Vec<Value>) -> Result<Option<Value>, Error> {
+        self.call_instantiation(RowId::new(0, method.rid), Rc::default(), args)
+    }
+
+    fn call_instantiation(
+        &mut self,
+        method: RowId,
+        generics: Rc<Generics>,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, Error> {
+        let depth = self.frames.len();
+        let result = self.enter(method, generics, args).and_then(|_| self.execute(depth));
+        self.frames.truncate(depth);
+        result
+    }
+
+    /// Pushes the frame of `method`, and that of its type initializer if it is a static method running first.
+    fn enter(&mut self, method: RowId, generics: Rc<Generics>, args: Vec<Value>) -> Result<(), Error> {
+        let at = |x: Fault| x.at(method, 0);
+        self.push_frame(method, generics.clone(), args, None).map_err(at)?;
+        if self.types.method(method)?.row.flags & METHOD_ATTRIBUTES_STATIC != 0 {
+            if let Some(owner) = self.declaring_type(method) {
+                if let Some((initializer, generics)) = self.initializer(owner, &generics.types).map_err(at)? {
+                    self.push_frame(initializer, generics, Vec::new(), None).map_err(at)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the heap, then runs the finalizers it queued unless they are already running.
+    pub fn collect(&mut self) -> Result<(), Error> {
+        let roots = self.roots();
+        self.heap.collect(roots);
+        self.run_finalizers()
+    }
+
+    fn run_finalizers(&mut self) -> Result<(), Error> {
+        if self.finalizing {
+            return Ok(());
+        }
+        self.finalizing = true;
+        let result = self.finalize_pending();
+        self.finalizing = false;
+        result
+    }
+
+    fn finalize_pending(&mut self) -> Result<(), Error> {
+        while let Some(object) = self.heap.next_pending() {
+            let finalizer = match self.heap.get(object) {
+                Some(Object::Instance { ty, .. }) => ty.finalizer.map(|x| self.dispatched(ty, x, Vec::new())),
+                _ => None,
+            };
+            if let Some((method, generics)) = finalizer {
+                self.call_instantiation(method, generics, vec![Value::Object(Some(object))])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Objects the program can still reach without going through another object.
+    fn roots(&self) -> Vec<ObjectRef> {
+        let mut roots = self.interned.values().cloned().collect::<Vec<_>>();
+        for frame in &self.frames {
+            for value in frame.args.iter().chain(&frame.locals).chain(&frame.stack) {
+                references(value, &mut roots);
+            }
+            match frame.constructing {
+                Some(Construction::Object(object)) | Some(Construction::ValueType(object)) => roots.push(object),
+                None => {}
+            }
+        }
+        for (_, value) in &self.statics {
+            references(value, &mut roots);
+        }
+        roots
+    }
+
+    /// How values of `ty`, read in `image` with `generics`, are stored.
+    fn kind(&mut self, image: usize, ty: &Type, generics: &Generics) -> Result<Kind, Fault> {
+        let ty = self.types.canonical(image, ty, generics)?;
+        self.types.canonical_kind(&ty)?.ok_or_else(|| {
+            Fault::NotSupported(format!(
+                "Values of type {}",
+                self.types.type_name(&ty).unwrap_or_default()
+            ))
+        })
+    }
+
+    fn param_kind(&mut self, image: usize, param: &Param, generics: &Generics) -> Result<Kind, Fault> {
+        if param.by_ref {
+            Ok(Kind::Pointer)
+        } else {
+            self.kind(image, &param.ty, generics)
+        }
+    }
+
+    fn zero(&mut self, kind: Kind) -> Result<Value, Fault> {
+        match kind {
+            Kind::Pointer => Ok(Value::NativeInt(0)),
+            kind => {
+                let (size, _) = self.types.size(kind)?;
+                self.decode(kind, &vec![0; size as usize])
+            }
+        }
+    }
+
+    /// Reads a value of `kind` from object data.
+    fn decode(&mut self, kind: Kind, bytes: &[u8]) -> Result<Value, Fault> {
+        Ok(match kind {
+            Kind::I1 => Value::Int32(i32::from(bytes.pread::<i8>(0)?)),
+            Kind::U1 => Value::Int32(i32::from(bytes.pread::<u8>(0)?)),
+            Kind::I2 => Value::Int32(i32::from(bytes.pread_with::<i16>(0, scroll::LE)?)),
+            Kind::U2 => Value::Int32(i32::from(bytes.pread_with::<u16>(0, scroll::LE)?)),
+            Kind::I4 => Value::Int32(bytes.pread_with(0, scroll::LE)?),
+            Kind::I8 => Value::Int64(bytes.pread_with(0, scroll::LE)?),
+            Kind::I => Value::NativeInt(bytes.pread_with(0, scroll::LE)?),
+            Kind::R4 => Value::Float(f64::from(bytes.pread_with::<f32>(0, scroll::LE)?)),
+            Kind::R8 => Value::Float(bytes.pread_with(0, scroll::LE)?),
+            Kind::Object => Value::Object(decode_reference(bytes.pread_with(0, scroll::LE)?)),
+            Kind::Pointer => return Err(Fault::Invalid("Managed pointer stored in an object".to_string())),
+            Kind::ValueType(id) => {
+                let ty = self.types.value_type(id)?;
+                let data = bytes
+                    .get(..ty.size as usize)
+                    .ok_or_else(|| Fault::Invalid("Value type doesn't fit its field".to_string()))?
+                    .to_vec();
+                Value::ValueType(ty, data)
+            }
+        })
+    }
+
+    fn code(&mut self, row: RowId, generics: Rc<Generics>) -> Result<Rc<Code<'a>>, Fault> {
+        if let Some(code) = self.code.get(&(row, generics.clone())) {
+            return Ok(code.clone());
+        }
+        let image = row.image;
+        let assembly = self.types.image(image);
+        let method = assembly.method(row.rid).ok_or(Error::TokenOutOfRange {
+            token: Token::new(TokenKind::Table(TableId::MethodDef), row.rid),
+            rows: assembly.tables().methods.len(),
+        })?;
+        let body = method
+            .body()?
+            .ok_or_else(|| Fault::NotSupported(format!("{} has no IL body", method.name().unwrap_or_default())))?;
+        let signature = method.signature()?;
+        if signature.generic_param_count as usize != generics.methods.len() {
+            return Err(Fault::Invalid(format!(
+                "{} takes {} type arguments, got {}",
+                method.name()?,
+                signature.generic_param_count,
+                generics.methods.len()
+            )));
+        }
+        let mut args = Vec::with_capacity(signature.params.len() + 1);
+        if signature.has_this {
+            // Methods of value types get a pointer to the value as `this`
+            let owner = match method.declaring_type() {
+                Some(owner) => Some(
+                    self.types
+                        .instantiate(RowId::new(image, owner.rid), generics.types.clone())?,
+                ),
+                None => None,
+            };
+            args.push(match owner {
+                Some(ref owner) if owner.is_value_type => Kind::Pointer,
+                _ => Kind::Object,
+            });
+        }
+        for param in &signature.params {
+            args.push(self.param_kind(image, param, &generics)?);
+        }
+        let returns = match signature.return_type {
+            Param {
+                by_ref: false,
+                ty: Type::Void,
+                ..
+            } => None,
+            ref param => Some(self.param_kind(image, param, &generics)?),
+        };
+        let mut locals = Vec::new();
+        for local in method.locals()?.map(|x| x.locals).unwrap_or_default() {
+            locals.push(if local.by_ref {
+                Kind::Pointer
+            } else {
+                self.kind(image, &local.ty, &generics)?
+            });
+        }
+        let code = Rc::new(Code {
+            instructions: decode_instructions(body.code, None)?,
+            clauses: body.exception_clauses,
+            max_stack: body.max_stack as usize,
+            args,
+            locals,
+            returns,
+            generics: generics.clone(),
+        });
+        self.code.insert((row, generics), code.clone());
+        Ok(code)
+    }
+
+    fn push_frame(
+        &mut self,
+        method: RowId,
+        generics: Rc<Generics>,
+        args: Vec<Value>,
+        constructing: Option<Construction>,
+    ) -> Result<(), Fault> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(Fault::Exception("System.StackOverflowException"));
+        }
+        let code = self.code(method, generics)?;
+        if args.len() != code.args.len() {
+            return Err(Fault::Invalid(format!(
+                "Method takes {} arguments, got {}",
+                code.args.len(),
+                args.len()
+            )));
+        }
+        let args = code
+            .args
+            .iter()
+            .zip(args)
+            .map(|(&kind, value)| store(kind, value))
+            .collect::<Result<Vec<_>, Fault>>()?;
+        let mut locals = Vec::with_capacity(code.locals.len());
+        for &kind in &code.locals {
+            locals.push(self.zero(kind)?);
+        }
+        self.frames.push(Frame {
+            method,
+            code,
+            pc: 0,
+            args,
+            locals,
+            stack: Vec::new(),
+            unwinds: Vec::new(),
+            constructing,
+        });
+        Ok(())
+    }
+
+    /// Runs until the frame at `depth` returns.
+    fn execute(&mut self, depth: usize) -> Result<Option<Value>, Error> {
+        loop {
+            // Between instructions every live value is in a frame, where the collector finds it
+            if self.heap.needs_collection() {
+                self.collect()?;
+            }
+            let frame = self.frame();
+            let (method, code, pc) = (frame.method, frame.code.clone(), frame.pc);
+            let instruction = match code.instructions.get(pc) {
+                Some(instruction) => instruction,
+                None => {
+                    let offset = code.instructions.last().map_or(0, |x| x.next_offset());
+                    return Err(
+                        Fault::Invalid("Execution runs past the end of the method".to_string()).at(method, offset)
+                    );
+                }
+            };
+            let at = |fault: Fault| fault.at(method, instruction.offset);
+            match self.step(&code, instruction).map_err(at)? {
+                Flow::Next => self.frame().pc += 1,
+                Flow::Jump(offset) => self.frame().pc = code.index(offset).map_err(at)?,
+                Flow::Call(rid, generics, args, constructing) => {
+                    self.frame().pc += 1;
+                    self.push_frame(rid, generics, args, constructing).map_err(at)?;
+                }
+                Flow::Initialize(rid, generics) => self.push_frame(rid, generics, Vec::new(), None).map_err(at)?,
+                Flow::Return(value) => {
+                    let constructing = self.frame().constructing;
+                    self.frames.pop();
+                    if self.frames.len() == depth {
+                        return Ok(value);
+                    }
+                    // Nothing to push, e.g. after a type initializer whose caller hasn't started its instruction yet
+                    if value.is_none() && constructing.is_none() {
+                        continue;
+                    }
+                    // The caller is still on its call instruction as far as errors go
+                    let frame = self.frame();
+                    let method = frame.method;
+                    let offset = match frame.pc.checked_sub(1).and_then(|x| frame.code.instructions.get(x)) {
+                        Some(instruction) => instruction.offset,
+                        None => {
+                            let reason = "Return value without a call to receive it".to_string();
+                            return Err(Fault::Invalid(reason).at(method, 0));
+                        }
+                    };
+                    let value = match constructing {
+                        Some(construction) => Some(self.constructed(construction).map_err(|x| x.at(method, offset))?),
+                        None => value,
+                    };
+                    if let Some(value) = value {
+                        self.push(value).map_err(|x| x.at(method, offset))?;
+                    }
+                }
+            }
+        }
+    }
+
+    fn constructed(&mut self, construction: Construction) -> Result<Value, Fault> {
+        match construction {
+            Construction::Object(object) => Ok(Value::Object(Some(object))),
+            Construction::ValueType(object) => {
+                let (ty, data) = match self.heap.get(object) {
+                    Some(Object::Instance { ty, data }) => (ty.clone(), data.clone()),
+                    _ => return Err(Fault::Invalid("Constructed value type is not boxed".to_string())),
+                };
+                let kind = self.types.kind_of(&ty).unwrap_or(Kind::Object);
+                self.decode(kind, &data)
+            }
+        }
+    }
+
+    fn frame(&mut self) -> &mut Frame<'a> {
+        self.frames.last_mut().expect("the interpreter runs with a frame")
+    }
+
+    fn pop(&mut self) -> Result<Value, Fault> {
+        self.frame().stack.pop().ok_or_else(underflow)
+    }
+
+    /// The last `count` values of the stack, the deepest first.
+    fn pop_many(&mut self, count: usize) -> Result<Vec<Value>, Fault> {
+        let stack = &mut self.frame().stack;
+        if stack.len() < count {
+            return Err(underflow());
+        }
+        Ok(stack.split_off(stack.len() - count))
+    }
+
+    fn push(&mut self, value: Value) -> Result<Flow, Fault> {
+        let frame = self.frame();
+        if frame.stack.len() >= frame.code.max_stack {
+            return Err(Fault::Invalid(format!(
+                "Stack overflows its maximum of {}",
+                frame.code.max_stack
+            )));
+        }
+        frame.stack.push(value);
+        Ok(Flow::Next)
+    }
+
+    /// The argument or local a managed pointer points to, with its kind.
+    fn slot(&mut self, pointer: &Value) -> Result<(&mut Value, Kind), Fault> {
+        let (frame, index, argument) = match *pointer {
+            Value::Pointer(Pointer::Argument { frame, index }) => (frame, index, true),
+            Value::Pointer(Pointer::Local { frame, index }) => (frame, index, false),
+            Value::Pointer(Pointer::Static { slot }) => {
+                return match self.statics.get_mut(slot) {
+                    Some((kind, value)) => Ok((value, *kind)),
+                    None => Err(Fault::Invalid(
+                        "Pointer to a static field that doesn't exist".to_string(),
+                    )),
+                }
+            }
+            Value::NativeInt(_) => return Err(Fault::NotSupported("Unmanaged pointers".to_string())),
+            _ => return Err(Fault::Invalid(format!("Dereferencing {}", pointer.stack_type()))),
+        };
+        let frame = self
+            .frames
+            .get_mut(frame)
+            .ok_or_else(|| Fault::Invalid("Pointer outlived its frame".to_string()))?;
+        let (slots, kinds) = if argument {
+            (&mut frame.args, &frame.code.args)
+        } else {
+            (&mut frame.locals, &frame.code.locals)
+        };
+        let index = index as usize;
+        match (slots.get_mut(index), kinds.get(index)) {
+            (Some(slot), Some(&kind)) => Ok((slot, kind)),
+            _ => Err(Fault::Invalid("Pointer outlived its frame".to_string())),
+        }
+    }
+
+    fn object_data(&mut self, object: ObjectRef) -> Result<&mut Vec<u8>, Fault> {
+        match self.heap.get_mut(object) {
+            Some(Object::Instance { data, .. }) | Some(Object::Array { data, .. }) => Ok(data),
+            Some(object) => Err(Fault::Invalid(format!("{} has no fields", object.type_name()))),
+            None => Err(Fault::Invalid("Reference to an object that doesn't exist".to_string())),
+        }
+    }
+
+    /// Reads a `kind` at `offset` into what `pointer` points to, 0 being the whole argument or local.
+    fn load(&mut self, pointer: &Value, offset: u32, kind: Kind) -> Result<Value, Fault> {
+        let bytes = match *pointer {
+            Value::Pointer(Pointer::Heap { object, offset: start }) => self
+                .object_data(object)?
+                .get((start + offset) as usize..)
+                .map(<[u8]>::to_vec),
+            _ => {
+                let (slot, slot_kind) = self.slot(pointer)?;
+                match slot {
+                    Value::ValueType(_, data) if offset != 0 || slot_kind != kind => {
+                        data.get(offset as usize..).map(<[u8]>::to_vec)
+                    }
+                    slot if offset == 0 => return store(kind, slot.clone()),
+                    _ => None,
+                }
+            }
+        };
+        let bytes = bytes.ok_or_else(|| Fault::Invalid("Field outside of the value pointed to".to_string()))?;
+        self.decode(kind, &bytes)
+    }
+
+    /// Writes `value` as a `kind` at `offset` into what `pointer` points to.
+    fn store_at(&mut self, pointer: &Value, offset: u32, kind: Kind, value: Value) -> Result<(), Fault> {
+        let outside = || Fault::Invalid("Field outside of the value pointed to".to_string());
+        let value = store(kind, value)?;
+        match *pointer {
+            Value::Pointer(Pointer::Heap { object, offset: start }) => {
+                let bytes = self
+                    .object_data(object)?
+                    .get_mut((start + offset) as usize..)
+                    .ok_or_else(outside)?;
+                encode(bytes, kind, &value)
+            }
+            _ => {
+                let (slot, slot_kind) = self.slot(pointer)?;
+                match slot {
+                    Value::ValueType(_, data) if offset != 0 || slot_kind != kind => {
+                        encode(data.get_mut(offset as usize..).ok_or_else(outside)?, kind, &value)
+                    }
+                    slot if offset == 0 => {
+                        *slot = store(slot_kind, value)?;
+                        Ok(())
+                    }
+                    _ => Err(outside()),
+                }
+            }
+        }
+    }
+
+    fn argument(&mut self, index: u16) -> Result<(usize, Kind), Fault> {
+        let frame = self.frame();
+        let kind = frame.code.args.get(index as usize).cloned();
+        kind.map(|x| (index as usize, x))
+            .ok_or_else(|| Fault::Invalid(format!("No argument {}", index)))
+    }
+
+    fn local(&mut self, index: u16) -> Result<(usize, Kind), Fault> {
+        let frame = self.frame();
+        let kind = frame.code.locals.get(index as usize).cloned();
+        kind.map(|x| (index as usize, x))
+            .ok_or_else(|| Fault::Invalid(format!("No local {}", index)))
+    }
+
+    /// Index of the image the running method belongs to, whose tokens its instructions take.
+    fn image(&self) -> usize {
+        self.frames.last().map_or(0, |x| x.method.image)
+    }
+
+    fn assembly(&self) -> Image<'a> {
+        self.types.image(self.image())
+    }
+
+    fn ldstr(&mut self, rid: u32) -> Result<Value, Fault> {
+        let key = RowId::new(self.image(), rid);
+        if let Some(&object) = self.interned.get(&key) {
+            return Ok(Value::Object(Some(object)));
+        }
+        let object = self
+            .heap
+            .allocate(Object::String(self.assembly().user_strings().get(rid)?));
+        self.interned.insert(key, object);
+        Ok(Value::Object(Some(object)))
+    }
+
+    /// A member outside the images, named the way the disassembler does.
+    fn outside(&self, what: &str, token: Token) -> Fault {
+        let assembly = self.assembly();
+        let name = Disassembler::new(&assembly)
+            .token(token)
+            .unwrap_or_else(|_| format!("0x{:08x}", token.raw()));
+        Fault::NotSupported(format!("{} {} outside the image", what, name))
+    }
+
+    fn member_signature(&self, rid: u32) -> Result<MethodSignature, Fault> {
+        let assembly = self.assembly();
+        let row = assembly
+            .tables()
+            .member_refs
+            .get((rid as usize).wrapping_sub(1))
+            .ok_or_else(|| Fault::Invalid("MemberRef out of range".to_string()))?;
+        Ok(assembly.blobs().get(row.signature)?.pread_with(0, scroll::LE)?)
+    }
+
+    /// The built-in method a MemberRef calls, if any.
+    fn intrinsic(&self, rid: u32) -> Result<Option<(Intrinsic, MethodSignature)>, Fault> {
+        let assembly = self.assembly();
+        let row = assembly
+            .tables()
+            .member_refs
+            .get((rid as usize).wrapping_sub(1))
+            .ok_or_else(|| Fault::Invalid("MemberRef out of range".to_string()))?;
+        let parent = match row.class {
+            MemberRefParent::TypeRef(parent) => assembly.type_reference(parent),
+            _ => None,
+        };
+        let parent = match parent {
+            Some(parent) => parent.full_name()?,
+            None => return Ok(None),
+        };
+        match Intrinsic::of(&parent, assembly.strings().get(row.name)?) {
+            Some(intrinsic) => Ok(Some((
+                intrinsic,
+                assembly.blobs().get(row.signature)?.pread_with(0, scroll::LE)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn call_intrinsic(&mut self, intrinsic: Intrinsic, signature: &MethodSignature) -> Result<Flow, Fault> {
+        let args = self.pop_many(signature.params.len() + signature.has_this as usize)?;
+        let object = |index: usize| match args.get(index) {
+            Some(&Value::Object(Some(object))) => Ok(object),
+            Some(&Value::Object(None)) => Err(Fault::Exception("System.ArgumentNullException")),
+            _ => Err(Fault::Invalid(format!("{:?} takes an object", intrinsic))),
+        };
+        let result = match intrinsic {
+            Intrinsic::ObjectConstructor | Intrinsic::KeepAlive => None,
+            Intrinsic::Collect => {
+                self.collect()?;
+                None
+            }
+            Intrinsic::WaitForPendingFinalizers => {
+                self.run_finalizers()?;
+                None
+            }
+            Intrinsic::SuppressFinalize => {
+                self.heap.suppress_finalize(object(0)?);
+                None
+            }
+            Intrinsic::ReRegisterForFinalize => {
+                self.heap.register_for_finalize(object(0)?);
+                None
+            }
+            Intrinsic::GetTotalMemory => {
+                if truthy(OpCode::Call, args.first().ok_or_else(underflow)?)? {
+                    self.collect()?;
+                }
+                Some(Value::Int64(self.heap.size() as i64))
+            }
+            // Every collection is a full one
+            Intrinsic::CollectionCount => Some(Value::Int32(self.heap.collections() as i32)),
+            Intrinsic::AllocHandle => {
+                let target = match args.first() {
+                    Some(&Value::Object(target)) => target,
+                    _ => return Err(Fault::Invalid("GCHandle.Alloc takes an object".to_string())),
+                };
+                let kind = match args.get(1) {
+                    None => HandleKind::Normal,
+                    Some(&Value::Int32(kind)) => HandleKind::from_u32(kind as u32)
+                        .ok_or(Fault::Exception("System.ArgumentOutOfRangeException"))?,
+                    Some(_) => return Err(Fault::Invalid("GCHandle.Alloc takes a GCHandleType".to_string())),
+                };
+                let handle = self.heap.allocate_handle(Handle { kind, target });
+                Some(Value::NativeInt(i64::from(handle)))
+            }
+            Intrinsic::HandleTarget
+            | Intrinsic::SetHandleTarget
+            | Intrinsic::FreeHandle
+            | Intrinsic::IsHandleAllocated => {
+                let this = args.first().ok_or_else(underflow)?;
+                let handle = match self.load(this, 0, Kind::I)? {
+                    Value::NativeInt(handle) => handle as u32,
+                    value => return Err(mismatch(OpCode::Call, &[&value])),
+                };
+                if intrinsic == Intrinsic::IsHandleAllocated {
+                    return self.push(Value::Int32(self.heap.handle(handle).is_some() as i32));
+                }
+                let unallocated = || Fault::Exception("System.InvalidOperationException");
+                match intrinsic {
+                    Intrinsic::HandleTarget => {
+                        let handle = self.heap.handle(handle).ok_or_else(unallocated)?;
+                        Some(Value::Object(handle.target))
+                    }
+                    Intrinsic::SetHandleTarget => {
+                        let target = match args.get(1) {
+                            Some(&Value::Object(target)) => target,
+                            _ => return Err(Fault::Invalid("GCHandle.Target takes an object".to_string())),
+                        };
+                        self.heap.handle_mut(handle).ok_or_else(unallocated)?.target = target;
+                        None
+                    }
+                    _ => {
+                        self.heap.free_handle(handle).ok_or_else(unallocated)?;
+                        self.store_at(this, 0, Kind::I, Value::NativeInt(0))?;
+                        None
+                    }
+                }
+            }
+        };
+        match result {
+            Some(value) => self.push(value),
+            None => Ok(Flow::Next),
+        }
+    }
+
+    /// The type initializer of an instantiation of a TypeDef if it still has to run, with the type
+    /// arguments it runs with. The type counts as initialized from then on.
+    ///
+    /// Only a static `void .cctor()` is a type initializer, other methods of that name are ordinary methods.
+    fn initializer(&mut self, owner: RowId, types: &[Type]) -> Result<Option<(RowId, Rc<Generics>)>, Fault> {
+        if !self.initialized.insert((owner, types.to_vec())) {
+            return Ok(None);
+        }
+        let assembly = self.types.image(owner.image);
+        let ty = match assembly.type_definition(owner.rid) {
+            Some(ty) => ty,
+            None => return Ok(None),
+        };
+        for method in ty.methods() {
+            if method.row.flags & METHOD_ATTRIBUTES_STATIC == 0 || method.name()? != ".cctor" {
+                continue;
+            }
+            let signature = method.signature()?;
+            if !signature.has_this
+                && signature.generic_param_count == 0
+                && signature.params.is_empty()
+                && signature.return_type.ty == Type::Void
+            {
+                let initializer = RowId::new(owner.image, method.rid);
+                return Ok(Some((initializer, Rc::new(Generics::new(types.to_vec(), Vec::new())))));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Type arguments of the running method.
+    fn generics(&self) -> Rc<Generics> {
+        self.frames.last().map(|x| x.code.generics.clone()).unwrap_or_default()
+    }
+
+    /// Type arguments of the instantiation of a TypeDef the running method may refer to by its rows:
+    /// those of the running method when it belongs to that TypeDef.
+    fn own_instantiation(&self, owner: RowId) -> Vec<Type> {
+        let frame = match self.frames.last() {
+            Some(frame) => frame,
+            None => return Vec::new(),
+        };
+        let ty = match self.types.method(frame.method) {
+            Ok(method) => method.declaring_type().map(|x| RowId::new(frame.method.image, x.rid)),
+            Err(_) => None,
+        };
+        match ty {
+            Some(ty) if ty == owner => frame.code.generics.types.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The method a MethodDef, MemberRef or MethodSpec refers to, `None` for methods outside the images.
+    fn callee(&mut self, token: Token) -> Result<Option<Callee>, Fault> {
+        let generics = self.generics();
+        let image = self.image();
+        let assembly = self.assembly();
+        let (rid, owner, methods) = match token.kind {
+            TokenKind::Table(TableId::MethodDef) => {
+                let owner = assembly
+                    .method(token.rid)
+                    .and_then(|x| x.declaring_type())
+                    .ok_or_else(|| Fault::Invalid("Method without a declaring type".to_string()))?;
+                let owner = RowId::new(image, owner.rid);
+                let types = self.own_instantiation(owner);
+                (
+                    RowId::new(image, token.rid),
+                    self.types.instantiate(owner, types)?,
+                    Vec::new(),
+                )
+            }
+            TokenKind::Table(TableId::MemberRef) => match self.types.member_method(image, token.rid, &generics)? {
+                Some((owner, rid)) => (rid, owner, Vec::new()),
+                None => return Ok(None),
+            },
+            TokenKind::Table(TableId::MethodSpec) => {
+                let row = assembly
+                    .tables()
+                    .method_specs
+                    .get((token.rid as usize).wrapping_sub(1))
+                    .ok_or_else(|| Fault::Invalid("MethodSpec out of range".to_string()))?;
+                let instantiation: MethodSpecSignature =
+                    assembly.blobs().get(row.instantiation)?.pread_with(0, scroll::LE)?;
+                let method = match row.method {
+                    MethodDefOrRef::MethodDef(rid) => Token::new(TokenKind::Table(TableId::MethodDef), rid),
+                    MethodDefOrRef::MemberRef(rid) => Token::new(TokenKind::Table(TableId::MemberRef), rid),
+                };
+                let callee = match self.callee(method)? {
+                    Some(callee) => callee,
+                    None => return Ok(None),
+                };
+                let mut methods = Vec::with_capacity(instantiation.args.len());
+                for arg in &instantiation.args {
+                    methods.push(self.types.canonical(image, arg, &generics)?);
+                }
+                let constraints = Generics::new(callee.owner.instantiation.clone(), methods.clone());
+                let owner = TypeOrMethodDef::MethodDef(callee.rid.rid);
+                self.types
+                    .check_constraints(callee.rid.image, owner, &methods, &constraints)?;
+                (callee.rid, callee.owner, methods)
+            }
+            _ => return Ok(None),
+        };
+        let generics = Rc::new(Generics::new(owner.instantiation.clone(), methods));
+        Ok(Some(Callee { rid, owner, generics }))
+    }
+
+    fn call_target(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let token = token(instruction)?;
+        if token.kind == TokenKind::Table(TableId::MemberRef) && token.rid != 0 {
+            if let Some((intrinsic, signature)) = self.intrinsic(token.rid)? {
+                return self.call_intrinsic(intrinsic, &signature);
+            }
+        }
+        match self.callee(token)? {
+            Some(callee) => self.call_method(callee.rid, callee.generics),
+            None => Err(self.outside("Call to", token)),
+        }
+    }
+
+    /// Calls a MethodDef without dispatch, running the type initializer first for static methods.
+    fn call_method(&mut self, method: RowId, generics: Rc<Generics>) -> Result<Flow, Fault> {
+        let owner = self
+            .types
+            .method(method)
+            .ok()
+            .filter(|x| x.row.flags & METHOD_ATTRIBUTES_STATIC != 0)
+            .and_then(|x| x.declaring_type())
+            .map(|x| RowId::new(method.image, x.rid));
+        if let Some(owner) = owner {
+            if let Some((initializer, generics)) = self.initializer(owner, &generics.types)? {
+                return Ok(Flow::Initialize(initializer, generics));
+            }
+        }
+        let count = self.code(method, generics.clone())?.args.len();
+        let args = self.pop_many(count)?;
+        Ok(Flow::Call(method, generics, args, None))
+    }
+
+    /// The TypeDef declaring a MethodDef.
+    fn declaring_type(&self, method: RowId) -> Option<RowId> {
+        let owner = self.types.method(method).ok()?.declaring_type()?;
+        Some(RowId::new(method.image, owner.rid))
+    }
+
+    /// `target`, a method of the vtable of `ty`, with the type arguments `ty` gives the type declaring it.
+    fn dispatched(&self, ty: &MethodTable, target: RowId, methods: Vec<Type>) -> (RowId, Rc<Generics>) {
+        let types = self
+            .declaring_type(target)
+            .and_then(|x| ty.instantiation_of(x))
+            .map(<[Type]>::to_vec)
+            .unwrap_or_default();
+        (target, Rc::new(Generics::new(types, methods)))
+    }
+
+    /// The method a virtual call to `token` runs on an object of type `ty`, `None` if the type
+    /// doesn't implement it. Calls to non-virtual methods run the method itself.
+    fn resolve(
+        &mut self,
+        ty: Option<&MethodTable>,
+        token: Token,
+        callee: Option<&Callee>,
+    ) -> Result<Option<(RowId, Rc<Generics>)>, Fault> {
+        let callee = match callee {
+            Some(callee) => callee,
+            None if token.kind == TokenKind::Table(TableId::MemberRef) => {
+                // Overrides of methods outside the images, which the type loader maps by MemberRef
+                let member = RowId::new(self.image(), token.rid);
+                return Ok(ty.and_then(|ty| {
+                    let slot = ty.external_slots.get(&member)?;
+                    ty.vtable.get(*slot).map(|&x| self.dispatched(ty, x, Vec::new()))
+                }));
+            }
+            None => return Ok(None),
+        };
+        let is_virtual = self.types.method(callee.rid)?.row.flags & METHOD_ATTRIBUTES_VIRTUAL != 0;
+        let ty = match ty {
+            Some(ty) if is_virtual => ty,
+            None if is_virtual => return Ok(None),
+            _ => return Ok(Some((callee.rid, callee.generics.clone()))),
+        };
+        let owner = &callee.owner;
+        let slot = if owner.is_interface {
+            let map = ty.interfaces.iter().find(|x| x.interface.id == owner.id);
+            let index = owner.vtable.iter().position(|&x| x == callee.rid);
+            match (map, index) {
+                (Some(map), Some(index)) => map.slots.get(index).cloned().flatten(),
+                _ => None,
+            }
+        } else {
+            ty.slots.get(&callee.rid).cloned()
+        };
+        let methods = &callee.generics.methods;
+        Ok(slot
+            .and_then(|x| ty.vtable.get(x))
+            .map(|&x| self.dispatched(ty, x, methods.clone())))
+    }
+
+    /// Method table of the object a virtual call is made on, `None` for strings and arrays.
+    fn receiver(&self, opcode: OpCode, this: &Value) -> Result<Option<Rc<MethodTable>>, Fault> {
+        match *this {
+            Value::Object(Some(object)) => match self.heap.get(object) {
+                Some(Object::Instance { ty, .. }) => Ok(Some(ty.clone())),
+                Some(_) => Ok(None),
+                None => Err(Fault::Invalid("Reference to an object that doesn't exist".to_string())),
+            },
+            Value::Object(None) => Err(null_reference()),
+            _ => Err(mismatch(opcode, &[this])),
+        }
+    }
+
+    fn callvirt(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let token = token(instruction)?;
+        if token.kind == TokenKind::Table(TableId::MemberRef) && token.rid != 0 {
+            if let Some((intrinsic, signature)) = self.intrinsic(token.rid)? {
+                return self.call_intrinsic(intrinsic, &signature);
+            }
+        }
+        let callee = self.callee(token)?;
+        let signature = match callee {
+            Some(ref callee) => self.types.method(callee.rid)?.signature()?,
+            None if token.kind == TokenKind::Table(TableId::MemberRef) && token.rid != 0 => {
+                self.member_signature(token.rid)?
+            }
+            None => return Err(self.outside("Call to", token)),
+        };
+        if !signature.has_this {
+            return Err(Fault::Invalid("callvirt on a static method".to_string()));
+        }
+        let mut args = self.pop_many(signature.params.len() + 1)?;
+        let ty = match instruction.constrained() {
+            Some(constraint) => {
+                let (this, ty) = self.constrain(constraint, token, callee.as_ref(), &args[0])?;
+                args[0] = this;
+                ty
+            }
+            None => self.receiver(instruction.opcode, &args[0])?,
+        };
+        let (target, generics) = match self.resolve(ty.as_deref(), token, callee.as_ref())? {
+            Some(target) => target,
+            None if callee.is_none() => return Err(self.outside("Call to", token)),
+            None => {
+                return Err(Fault::Invalid(format!(
+                    "{} doesn't implement {}",
+                    ty.as_ref().map_or("The object", |x| x.name.as_str()),
+                    callee
+                        .and_then(|x| self.types.method(x.rid).ok())
+                        .and_then(|x| x.name().ok())
+                        .unwrap_or_default()
+                )))
+            }
+        };
+        let this = self.this(target, ty.as_deref(), args.remove(0))?;
+        args.insert(0, this);
+        Ok(Flow::Call(target, generics, args, None))
+    }
+
+    /// `this` as `target` takes it: methods of value types called on a boxed value, of type `ty`, get
+    /// a pointer into the box.
+    fn this(&mut self, target: RowId, ty: Option<&MethodTable>, this: Value) -> Result<Value, Fault> {
+        let method = self.types.method(target)?;
+        if method.row.flags & METHOD_ATTRIBUTES_ABSTRACT != 0 {
+            return Err(Fault::Invalid(format!("Call to abstract method {}", method.name()?)));
+        }
+        let owner = self.declaring_type(target);
+        let is_value_type = match ty {
+            Some(ty) => ty.is_value_type && ty.definition() == owner,
+            None => false,
+        };
+        Ok(match this {
+            Value::Object(Some(object)) if is_value_type => Value::Pointer(Pointer::Heap { object, offset: 0 }),
+            this => this,
+        })
+    }
+
+    /// `this` of a `constrained.` call and the type to dispatch on (III.2.1): references are
+    /// dereferenced, value types are called directly when they implement the method and boxed otherwise.
+    fn constrain(
+        &mut self,
+        constraint: Token,
+        method: Token,
+        callee: Option<&Callee>,
+        this: &Value,
+    ) -> Result<(Value, Option<Rc<MethodTable>>), Fault> {
+        let ty = self.load_type(constraint)?;
+        if !ty.is_value_type {
+            let this = self.load(this, 0, Kind::Object)?;
+            let receiver = self.receiver(OpCode::Callvirt, &this)?;
+            return Ok((this, receiver));
+        }
+        let target = self.resolve(Some(&ty), method, callee)?;
+        let owner = target.and_then(|x| self.declaring_type(x.0));
+        if owner.is_some() && owner == ty.definition() {
+            return Ok((this.clone(), Some(ty)));
+        }
+        let kind = self
+            .types
+            .kind_of(&ty)
+            .ok_or_else(|| Fault::NotSupported(format!("Values of type {}", ty.name)))?;
+        let value = self.load(this, 0, kind)?;
+        let mut data = vec![0; ty.size as usize];
+        encode(&mut data, kind, &value)?;
+        let object = self.heap.allocate(Object::Instance { ty: ty.clone(), data });
+        Ok((Value::Object(Some(object)), Some(ty)))
+    }
+
+    /// `ldftn` and `ldvirtftn`, a method pointer is the index of the method in `functions`.
+    fn load_function(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let token = token(instruction)?;
+        let callee = match self.callee(token)? {
+            Some(callee) => callee,
+            None => return Err(self.outside("Method", token)),
+        };
+        let target = if instruction.opcode == OpCode::Ldvirtftn {
+            let this = self.pop()?;
+            let ty = self.receiver(instruction.opcode, &this)?;
+            self.resolve(ty.as_deref(), token, Some(&callee))?
+                .ok_or_else(|| Fault::Invalid("Object doesn't implement the method".to_string()))?
+        } else {
+            (callee.rid, callee.generics)
+        };
+        let index = match self.functions.iter().position(|x| *x == target) {
+            Some(index) => index,
+            None => {
+                self.functions.push(target);
+                self.functions.len() - 1
+            }
+        };
+        self.push(Value::NativeInt(index as i64))
+    }
+
+    fn calli(&mut self) -> Result<Flow, Fault> {
+        let (method, generics) = match self.pop()? {
+            Value::NativeInt(x) => self
+                .functions
+                .get(x as usize)
+                .cloned()
+                .ok_or_else(|| Fault::Invalid("calli on a pointer that isn't a method".to_string()))?,
+            value => return Err(mismatch(OpCode::Calli, &[&value])),
+        };
+        self.call_method(method, generics)
+    }
+
+    /// `castclass` and `isinst`.
+    fn cast(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let ty = self.type_operand(instruction)?;
+        let object = match self.pop()? {
+            Value::Object(object) => object,
+            value => return Err(mismatch(instruction.opcode, &[&value])),
+        };
+        match object {
+            Some(x) if !self.is_instance(x, &ty)? => {
+                if instruction.opcode == OpCode::Castclass {
+                    Err(Fault::Exception("System.InvalidCastException"))
+                } else {
+                    self.push(Value::Object(None))
+                }
+            }
+            object => self.push(Value::Object(object)),
+        }
+    }
+
+    fn newobj(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let token = token(instruction)?;
+        let Callee {
+            rid,
+            owner: ty,
+            generics,
+        } = match self.callee(token)? {
+            Some(callee) => callee,
+            None => return Err(self.outside("Constructor", token)),
+        };
+        if let Some(definition) = ty.definition() {
+            if let Some((initializer, generics)) = self.initializer(definition, &ty.instantiation)? {
+                return Ok(Flow::Initialize(initializer, generics));
+            }
+        }
+        if ty.is_abstract || ty.is_interface {
+            return Err(Fault::Invalid(format!(
+                "Cannot create an instance of abstract type {}",
+                ty.name
+            )));
+        }
+        let count = self.code(rid, generics.clone())?.args.len();
+        if count == 0 {
+            return Err(Fault::Invalid("Constructor is static".to_string()));
+        }
+        let mut args = self.pop_many(count - 1)?;
+        let object = self.heap.allocate(Object::Instance {
+            ty: ty.clone(),
+            data: vec![0; ty.size as usize],
+        });
+        let (this, construction) = if ty.is_value_type {
+            let this = Value::Pointer(Pointer::Heap { object, offset: 0 });
+            (this, Construction::ValueType(object))
+        } else {
+            (Value::Object(Some(object)), Construction::Object(object))
+        };
+        args.insert(0, this);
+        Ok(Flow::Call(rid, generics, args, Some(construction)))
+    }
+
+    /// The Field a Field or MemberRef token refers to and the instantiation of the type declaring it.
+    fn field_owner(&mut self, token: Token) -> Result<(Rc<MethodTable>, RowId), Fault> {
+        let image = self.image();
+        match token.kind {
+            TokenKind::Table(TableId::Field) => {
+                let assembly = self.assembly();
+                let owner = assembly
+                    .field(token.rid)
+                    .ok_or_else(|| Fault::Invalid("Field out of range".to_string()))?
+                    .declaring_type()
+                    .ok_or_else(|| Fault::Invalid("Field without a declaring type".to_string()))?;
+                let owner = RowId::new(image, owner.rid);
+                let types = self.own_instantiation(owner);
+                Ok((self.types.instantiate(owner, types)?, RowId::new(image, token.rid)))
+            }
+            TokenKind::Table(TableId::MemberRef) => {
+                let generics = self.generics();
+                self.types
+                    .member_field(image, token.rid, &generics)?
+                    .ok_or_else(|| self.outside("Field", token))
+            }
+            _ => Err(self.outside("Field", token)),
+        }
+    }
+
+    /// The layout slot of the instance field an `ldfld` or `stfld` accesses.
+    fn field(&mut self, instruction: &Instruction<'a>) -> Result<FieldSlot, Fault> {
+        let (ty, field) = self.field_owner(token(instruction)?)?;
+        // Only instance fields are part of the layout
+        ty.field(field)
+            .cloned()
+            .ok_or_else(|| Fault::NotSupported("Static fields".to_string()))
+    }
+
+    fn ldfld(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let slot = self.field(instruction)?;
+        let object = self.pop()?;
+        let bytes = match object {
+            Value::Object(Some(object)) => {
+                self.check_field(object, &slot)?;
+                self.object_data(object)?
+                    .get(slot.offset as usize..)
+                    .map(<[u8]>::to_vec)
+            }
+            Value::Object(None) => return Err(null_reference()),
+            Value::ValueType(ty, data) if ty.field(slot.field).is_some() => {
+                data.get(slot.offset as usize..).map(<[u8]>::to_vec)
+            }
+            pointer @ Value::Pointer(_) | pointer @ Value::NativeInt(_) => {
+                let value = self.load(&pointer, slot.offset, slot.kind)?;
+                return self.push(value);
+            }
+            object => return Err(mismatch(instruction.opcode, &[&object])),
+        };
+        let bytes = bytes.ok_or_else(|| Fault::Invalid("Field outside of the object".to_string()))?;
+        let value = self.decode(slot.kind, &bytes)?;
+        self.push(value)
+    }
+
+    fn stfld(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let slot = self.field(instruction)?;
+        let value = self.pop()?;
+        match self.pop()? {
+            Value::Object(Some(object)) => {
+                self.check_field(object, &slot)?;
+                let value = store(slot.kind, value)?;
+                let bytes = self
+                    .object_data(object)?
+                    .get_mut(slot.offset as usize..)
+                    .ok_or_else(|| Fault::Invalid("Field outside of the object".to_string()))?;
+                encode(bytes, slot.kind, &value)?;
+            }
+            Value::Object(None) => return Err(null_reference()),
+            pointer @ Value::Pointer(_) | pointer @ Value::NativeInt(_) => {
+                self.store_at(&pointer, slot.offset, slot.kind, value)?
+            }
+            object => return Err(mismatch(instruction.opcode, &[&object, &value])),
+        }
+        Ok(Flow::Next)
+    }
+
+    /// `ldsfld`, `stsfld` and `ldsflda`, running the type initializer first if it hasn't run yet.
+    fn static_field(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let (owner, row) = self.field_owner(token(instruction)?)?;
+        let assembly = self.types.image(row.image);
+        let field = assembly
+            .field(row.rid)
+            .ok_or_else(|| Fault::Invalid("Field out of range".to_string()))?;
+        if field.row.flags & FIELD_ATTRIBUTES_STATIC == 0 {
+            return Err(Fault::Invalid(format!(
+                "{} on instance field {}",
+                instruction.opcode.mnemonic(),
+                field.name()?
+            )));
+        }
+        if let Some(definition) = owner.definition() {
+            if let Some((initializer, generics)) = self.initializer(definition, &owner.instantiation)? {
+                return Ok(Flow::Initialize(initializer, generics));
+            }
+        }
+        // Every instantiation of a generic type has static fields of its own
+        let key = (row, owner.instantiation.clone());
+        let slot = match self.static_slots.get(&key) {
+            Some(&slot) => slot,
+            None => {
+                let generics = Generics::new(owner.instantiation.clone(), Vec::new());
+                let kind = self.kind(row.image, &field.signature()?.ty, &generics)?;
+                let value = self.zero(kind)?;
+                self.statics.push((kind, value));
+                self.static_slots.insert(key, self.statics.len() - 1);
+                self.statics.len() - 1
+            }
+        };
+        let kind = self.statics[slot].0;
+        let pointer = Value::Pointer(Pointer::Static { slot });
+        match instruction.opcode {
+            OpCode::Ldsfld => {
+                let value = self.load(&pointer, 0, kind)?;
+                self.push(value)
+            }
+            OpCode::Stsfld => {
+                let value = self.pop()?;
+                self.store_at(&pointer, 0, kind, value)?;
+                Ok(Flow::Next)
+            }
+            _ => self.push(pointer),
+        }
+    }
+
+    /// Fields may only be accessed on objects of the declaring type or types derived from it.
+    fn check_field(&self, object: ObjectRef, slot: &FieldSlot) -> Result<(), Fault> {
+        match self.heap.get(object) {
+            Some(Object::Instance { ty, .. }) if ty.field(slot.field) == Some(slot) => Ok(()),
+            Some(object) => Err(Fault::Invalid(format!(
+                "{} has no field 0x{:08x}",
+                object.type_name(),
+                Token::new(TokenKind::Table(TableId::Field), slot.field.rid).raw()
+            ))),
+            None => Err(Fault::Invalid("Reference to an object that doesn't exist".to_string())),
+        }
+    }
+
+    /// The method table of the type an instruction takes.
+    fn type_operand(&mut self, instruction: &Instruction<'a>) -> Result<Rc<MethodTable>, Fault> {
+        self.load_type(token(instruction)?)
+    }
+
+    /// The method table of a TypeDef, TypeRef or TypeSpec, the latter read with the type arguments of the running method.
+    fn load_type(&mut self, token: Token) -> Result<Rc<MethodTable>, Fault> {
+        let image = self.image();
+        let index = match token.kind {
+            TokenKind::Table(TableId::TypeDef) => TypeDefOrRef::TypeDef(token.rid),
+            TokenKind::Table(TableId::TypeRef) => TypeDefOrRef::TypeRef(token.rid),
+            TokenKind::Table(TableId::TypeSpec) => {
+                let ty = self.type_specification(token.rid)?;
+                let ty = self.types.canonical(image, &ty, &self.generics())?;
+                return self.types.load_canonical(&ty)?.ok_or_else(|| {
+                    let name = self.types.type_name(&ty).unwrap_or_default();
+                    Fault::NotSupported(format!("Type operands of type {}", name))
+                });
+            }
+            _ => return Err(Fault::Invalid(format!("Token 0x{:08x} is not a type", token.raw()))),
+        };
+        self.types
+            .load(image, index, &Generics::default())?
+            .ok_or_else(|| Fault::Invalid("Type token is null".to_string()))
+    }
+
+    fn type_specification(&self, rid: u32) -> Result<Type, Fault> {
+        Ok(self
+            .assembly()
+            .type_specification(rid)
+            .ok_or_else(|| Fault::Invalid("TypeSpec out of range".to_string()))?
+            .signature()?)
+    }
+
+    /// How values of the type an instruction takes are stored, TypeSpecs included.
+    fn kind_operand(&mut self, instruction: &Instruction<'a>) -> Result<Kind, Fault> {
+        let token = token(instruction)?;
+        if token.kind == TokenKind::Table(TableId::TypeSpec) {
+            let ty = self.type_specification(token.rid)?;
+            let generics = self.generics();
+            return self.kind(self.image(), &ty, &generics);
+        }
+        let ty = self.type_operand(instruction)?;
+        self.types
+            .kind_of(&ty)
+            .ok_or_else(|| Fault::NotSupported(format!("Values of type {}", ty.name)))
+    }
+
+    /// The method table of the type of an object.
+    fn type_of(&mut self, object: ObjectRef) -> Result<Rc<MethodTable>, Fault> {
+        match self.heap.get(object) {
+            Some(Object::Instance { ty, .. }) => Ok(ty.clone()),
+            Some(Object::String(_)) => self
+                .types
+                .load_canonical(&Type::String)?
+                .ok_or_else(|| Fault::NotSupported("Strings".to_string())),
+            Some(Object::Array { element_type, .. }) => {
+                let element_type = element_type.clone();
+                Ok(self.types.array_of(element_type))
+            }
+            None => Err(Fault::Invalid("Reference to an object that doesn't exist".to_string())),
+        }
+    }
+
+    fn is_instance(&mut self, object: ObjectRef, ty: &MethodTable) -> Result<bool, Fault> {
+        Ok(self.type_of(object)?.is_subclass_of(ty))
+    }
+
+    fn box_value(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let ty = self.type_operand(instruction)?;
+        // Boxing a reference type leaves the reference as it is
+        if !ty.is_value_type {
+            return Ok(Flow::Next);
+        }
+        let kind = self
+            .types
+            .kind_of(&ty)
+            .ok_or_else(|| Fault::NotSupported(format!("Values of type {}", ty.name)))?;
+        let value = store(kind, self.pop()?)?;
+        let mut data = vec![0; ty.size as usize];
+        encode(&mut data, kind, &value)?;
+        let object = self.heap.allocate(Object::Instance { ty, data });
+        self.push(Value::Object(Some(object)))
+    }
+
+    fn unbox_any(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let ty = self.type_operand(instruction)?;
+        let object = match self.pop()? {
+            Value::Object(object) => object,
+            value => return Err(mismatch(instruction.opcode, &[&value])),
+        };
+        if !ty.is_value_type {
+            // Same as castclass
+            return match object {
+                Some(x) if !self.is_instance(x, &ty)? => Err(Fault::Exception("System.InvalidCastException")),
+                object => self.push(Value::Object(object)),
+            };
+        }
+        let object = object.ok_or_else(null_reference)?;
+        let is_enum = |x: &MethodTable| x.parent.iter().any(|x| x.name == "System.Enum");
+        let data = match self.heap.get(object) {
+            // Enums unbox to their underlying type and back
+            Some(Object::Instance { ty: boxed, data })
+                if boxed.id == ty.id
+                    || (is_enum(boxed) || is_enum(&ty))
+                        && boxed.primitive.is_some()
+                        && boxed.primitive == ty.primitive =>
+            {
+                data.clone()
+            }
+            _ => return Err(Fault::Exception("System.InvalidCastException")),
+        };
+        let kind = self
+            .types
+            .kind_of(&ty)
+            .ok_or_else(|| Fault::NotSupported(format!("Values of type {}", ty.name)))?;
+        let value = self.decode(kind, &data)?;
+        self.push(value)
+    }
+
+    fn newarr(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let element_type = self.type_operand(instruction)?;
+        let element = self.kind_operand(instruction)?;
+        let length = match self.pop()? {
+            Value::Int32(x) => i64::from(x),
+            Value::NativeInt(x) => x,
+            value => return Err(mismatch(instruction.opcode, &[&value])),
+        };
+        if length < 0 {
+            return Err(Fault::Exception("System.OverflowException"));
+        }
+        let (element_size, _) = self.types.size(element)?;
+        match (length as u64).checked_mul(u64::from(element_size)) {
+            Some(size) if size <= MAX_ARRAY_SIZE => {}
+            _ => return Err(Fault::Exception("System.OutOfMemoryException")),
+        }
+        let element_references = match element {
+            Kind::Object => vec![0],
+            Kind::ValueType(id) => self.types.value_type(id)?.references.clone(),
+            _ => Vec::new(),
+        };
+        let object = self.heap.allocate(Object::Array {
+            element_type,
+            element,
+            element_size,
+            element_references,
+            length: length as u32,
+            data: vec![0; length as usize * element_size as usize],
+        });
+        self.push(Value::Object(Some(object)))
+    }
+
+    /// The array, its element kind and the offset of the element an `ldelem` or `stelem` accesses.
+    fn element(&self, opcode: OpCode, array: &Value, index: &Value) -> Result<(ObjectRef, Kind, u32), Fault> {
+        let object = match *array {
+            Value::Object(Some(object)) => object,
+            Value::Object(None) => return Err(null_reference()),
+            _ => return Err(mismatch(opcode, &[array, index])),
+        };
+        let index = match *index {
+            Value::Int32(x) => i64::from(x),
+            Value::NativeInt(x) => x,
+            _ => return Err(mismatch(opcode, &[array, index])),
+        };
+        match self.heap.get(object) {
+            Some(&Object::Array {
+                element,
+                element_size,
+                length,
+                ..
+            }) => {
+                if index < 0 || index >= i64::from(length) {
+                    return Err(Fault::Exception("System.IndexOutOfRangeException"));
+                }
+                Ok((object, element, index as u32 * element_size))
+            }
+            Some(object) => Err(Fault::Invalid(format!(
+                "{} on {}",
+                opcode.mnemonic(),
+                object.type_name()
+            ))),
+            None => Err(Fault::Invalid("Reference to an object that doesn't exist".to_string())),
+        }
+    }
+
+    fn ldelem(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let opcode = instruction.opcode;
+        let kind = match opcode_kind(opcode) {
+            Some(kind) => kind,
+            None => self.kind_operand(instruction)?,
+        };
+        let index = self.pop()?;
+        let array = self.pop()?;
+        let (object, element, offset) = self.element(opcode, &array, &index)?;
+        if !is_compatible(kind, element) {
+            return Err(Fault::Invalid(format!(
+                "{} on an array of {:?}",
+                opcode.mnemonic(),
+                element
+            )));
+        }
+        let bytes = self.object_data(object)?[offset as usize..].to_vec();
+        let value = self.decode(element, &bytes)?;
+        let value = store(kind, value)?;
+        self.push(value)
+    }
+
+    fn stelem(&mut self, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        let opcode = instruction.opcode;
+        let kind = match opcode_kind(opcode) {
+            Some(kind) => kind,
+            None => self.kind_operand(instruction)?,
+        };
+        let value = self.pop()?;
+        let index = self.pop()?;
+        let array = self.pop()?;
+        let (object, element, offset) = self.element(opcode, &array, &index)?;
+        if !is_compatible(kind, element) {
+            return Err(Fault::Invalid(format!(
+                "{} on an array of {:?}",
+                opcode.mnemonic(),
+                element
+            )));
+        }
+        // Arrays are covariant, so an object[] may be a string[] that can't hold other objects
+        if let Value::Object(Some(x)) = value {
+            let element_type = match self.heap.get(object) {
+                Some(Object::Array { element_type, .. }) => element_type.clone(),
+                _ => return Err(Fault::Invalid("Reference to an object that doesn't exist".to_string())),
+            };
+            if !self.is_instance(x, &element_type)? {
+                return Err(Fault::Exception("System.ArrayTypeMismatchException"));
+            }
+        }
+        let value = store(element, store(kind, value)?)?;
+        encode(&mut self.object_data(object)?[offset as usize..], element, &value)?;
+        Ok(Flow::Next)
+    }
+
+    fn step(&mut self, code: &Code<'a>, instruction: &Instruction<'a>) -> Result<Flow, Fault> {
+        use crate::instruction::OpCode::*;
+        let opcode = instruction.opcode;
+        let depth = self.frames.len() - 1;
+        match opcode {
+            Nop | Break => Ok(Flow::Next),
+            Ldarg0 | Ldarg1 | Ldarg2 | Ldarg3 | LdargS | Ldarg => {
+                let (index, _) = self.argument(var(instruction, Ldarg0))?;
+                let value = self.frame().args[index].clone();
+                self.push(value)
+            }
+            StargS | Starg => {
+                let (index, kind) = self.argument(var(instruction, Ldarg0))?;
+                let value = store(kind, self.pop()?)?;
+                self.frame().args[index] = value;
+                Ok(Flow::Next)
+            }
+            LdargaS | Ldarga => {
+                let (index, _) = self.argument(var(instruction, Ldarg0))?;
+                self.push(Value::Pointer(Pointer::Argument {
+                    frame: depth,
+                    index: index as u16,
+                }))
+            }
+            Ldloc0 | Ldloc1 | Ldloc2 | Ldloc3 | LdlocS | Ldloc => {
+                let (index, _) = self.local(var(instruction, Ldloc0))?;
+                let value = self.frame().locals[index].clone();
+                self.push(value)
+            }
+            Stloc0 | Stloc1 | Stloc2 | Stloc3 | StlocS | Stloc => {
+                let (index, kind) = self.local(var(instruction, Stloc0))?;
+                let value = store(kind, self.pop()?)?;
+                self.frame().locals[index] = value;
+                Ok(Flow::Next)
+            }
+            LdlocaS | Ldloca => {
+                let (index, _) = self.local(var(instruction, Ldloc0))?;
+                self.push(Value::Pointer(Pointer::Local {
+                    frame: depth,
+                    index: index as u16,
+                }))
+            }
+            Ldnull => self.push(Value::Object(None)),
+            LdcI4M1 | LdcI40 | LdcI41 | LdcI42 | LdcI43 | LdcI44 | LdcI45 | LdcI46 | LdcI47 | LdcI48 => self.push(
+                Value::Int32(i32::from(opcode.value() as u8) - i32::from(LdcI40.value() as u8)),
+            ),
+            LdcI4S | LdcI4 | LdcI8 | LdcR4 | LdcR8 => self.push(match instruction.operand {
+                Operand::Int8(x) => Value::Int32(i32::from(x)),
+                Operand::Int32(x) => Value::Int32(x),
+                Operand::Int64(x) => Value::Int64(x),
+                Operand::Float32(x) => Value::Float(f64::from(x)),
+                Operand::Float64(x) => Value::Float(x),
+                _ => return Err(Fault::Invalid("Constant without a value".to_string())),
+            }),
+            Ldstr => {
+                let token = token(instruction)?;
+                let value = self.ldstr(token.rid)?;
+                self.push(value)
+            }
+            Dup => {
+                let value = self.frame().stack.last().ok_or_else(underflow)?.clone();
+                self.push(value)
+            }
+            Pop => self.pop().map(|_| Flow::Next),
+            Call => self.call_target(instruction),
+            Callvirt => self.callvirt(instruction),
+            Calli => self.calli(),
+            Ldftn | Ldvirtftn => self.load_function(instruction),
+            Castclass | Isinst => self.cast(instruction),
+            Newobj => self.newobj(instruction),
+            Ret => {
+                let value = match code.returns {
+                    Some(kind) => Some(store(kind, self.pop()?)?),
+                    None => None,
+                };
+                if !self.frame().stack.is_empty() {
+                    return Err(Fault::Invalid("Stack is not empty on return".to_string()));
+                }
+                Ok(Flow::Return(value))
+            }
+            Br | BrS => Ok(Flow::Jump(target(instruction)?)),
+            Brfalse | BrfalseS | Brtrue | BrtrueS => {
+                let value = truthy(opcode, &self.pop()?)?;
+                if value == (opcode == Brtrue || opcode == BrtrueS) {
+                    Ok(Flow::Jump(target(instruction)?))
+                } else {
+                    Ok(Flow::Next)
+                }
+            }
+            Beq | BeqS | Bge | BgeS | Bgt | BgtS | Ble | BleS | Blt | BltS | BneUn | BneUnS | BgeUn | BgeUnS
+            | BgtUn | BgtUnS | BleUn | BleUnS | BltUn | BltUnS => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                if holds(opcode, compare(opcode, &a, &b, is_unsigned_comparison(opcode))?) {
+                    Ok(Flow::Jump(target(instruction)?))
+                } else {
+                    Ok(Flow::Next)
+                }
+            }
+            Ceq | Cgt | CgtUn | Clt | CltUn => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let result = holds(opcode, compare(opcode, &a, &b, is_unsigned_comparison(opcode))?);
+                self.push(Value::Int32(result as i32))
+            }
+            Switch => {
+                let targets = match instruction.operand {
+                    Operand::Switch(ref targets) => targets,
+                    _ => return Err(Fault::Invalid("Switch without targets".to_string())),
+                };
+                let index = match self.pop()? {
+                    Value::Int32(x) => x as u32 as usize,
+                    Value::NativeInt(x) => x as u64 as usize,
+                    value => return Err(mismatch(opcode, &[&value])),
+                };
+                Ok(targets.get(index).map_or(Flow::Next, |&x| Flow::Jump(x)))
+            }
+            Add | Sub | Mul | Div | DivUn | Rem | RemUn | And | Or | Xor | AddOvf | AddOvfUn | SubOvf | SubOvfUn
+            | MulOvf | MulOvfUn => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let value = binary(opcode, &a, &b)?;
+                self.push(value)
+            }
+            Shl | Shr | ShrUn => {
+                let amount = self.pop()?;
+                let value = self.pop()?;
+                let value = shift(opcode, &value, &amount)?;
+                self.push(value)
+            }
+            Neg | Not => {
+                let value = unary(opcode, &self.pop()?)?;
+                self.push(value)
+            }
+            ConvI1 | ConvI2 | ConvI4 | ConvI8 | ConvR4 | ConvR8 | ConvU4 | ConvU8 | ConvRUn | ConvOvfI1Un
+            | ConvOvfI2Un | ConvOvfI4Un | ConvOvfI8Un | ConvOvfU1Un | ConvOvfU2Un | ConvOvfU4Un | ConvOvfU8Un
+            | ConvOvfIUn | ConvOvfUUn | ConvOvfI1 | ConvOvfU1 | ConvOvfI2 | ConvOvfU2 | ConvOvfI4 | ConvOvfU4
+            | ConvOvfI8 | ConvOvfU8 | ConvU2 | ConvU1 | ConvI | ConvOvfI | ConvOvfU | ConvU => {
+                let value = convert(opcode, &self.pop()?)?;
+                self.push(value)
+            }
+            Ckfinite => match self.pop()? {
+                Value::Float(x) if x.is_finite() => self.push(Value::Float(x)),
+                Value::Float(_) => Err(Fault::Exception("System.ArithmeticException")),
+                value => Err(mismatch(opcode, &[&value])),
+            },
+            LdindI1 | LdindU1 | LdindI2 | LdindU2 | LdindI4 | LdindU4 | LdindI8 | LdindI | LdindR4 | LdindR8
+            | LdindRef => {
+                let pointer = self.pop()?;
+                let kind = opcode_kind(opcode).unwrap_or(Kind::Object);
+                let value = self.load(&pointer, 0, kind)?;
+                self.push(value)
+            }
+            StindRef | StindI1 | StindI2 | StindI4 | StindI8 | StindR4 | StindR8 | StindI => {
+                let value = self.pop()?;
+                let pointer = self.pop()?;
+                let kind = opcode_kind(opcode).unwrap_or(Kind::Object);
+                self.store_at(&pointer, 0, kind, value)?;
+                Ok(Flow::Next)
+            }
+            Ldfld => self.ldfld(instruction),
+            Ldsfld | Stsfld | Ldsflda => self.static_field(instruction),
+            Stfld => self.stfld(instruction),
+            Box => self.box_value(instruction),
+            UnboxAny => self.unbox_any(instruction),
+            Newarr => self.newarr(instruction),
+            Ldlen => match self.pop()? {
+                Value::Object(Some(object)) => match self.heap.get(object) {
+                    Some(&Object::Array { length, .. }) => self.push(Value::NativeInt(i64::from(length))),
+                    Some(object) => Err(Fault::Invalid(format!("ldlen on {}", object.type_name()))),
+                    None => Err(Fault::Invalid("Reference to an object that doesn't exist".to_string())),
+                },
+                Value::Object(None) => Err(null_reference()),
+                value => Err(mismatch(opcode, &[&value])),
+            },
+            LdelemI1 | LdelemU1 | LdelemI2 | LdelemU2 | LdelemI4 | LdelemU4 | LdelemI8 | LdelemI | LdelemR4
+            | LdelemR8 | LdelemRef | Ldelem => self.ldelem(instruction),
+            StelemI | StelemI1 | StelemI2 | StelemI4 | StelemI8 | StelemR4 | StelemR8 | StelemRef | Stelem => {
+                self.stelem(instruction)
+            }
+            Leave | LeaveS => {
+                let target = target(instruction)?;
+                let offset = instruction.offset;
+                let frame = self.frame();
+                frame.stack.clear();
+                // Clauses are ordered innermost first
+                let mut handlers = code
+                    .clauses
+                    .iter()
+                    .filter(|x| x.kind == ExceptionClauseKind::Finally)
+                    .filter(|x| x.try_range().contains(&offset) && !x.try_range().contains(&target))
+                    .map(|x| x.handler_offset)
+                    .collect::<Vec<_>>();
+                handlers.reverse();
+                match handlers.pop() {
+                    Some(handler) => {
+                        frame.unwinds.push(Unwind { handlers, target });
+                        Ok(Flow::Jump(handler))
+                    }
+                    None => Ok(Flow::Jump(target)),
+                }
+            }
+            Endfinally => {
+                let frame = self.frame();
+                frame.stack.clear();
+                let unwind = frame
+                    .unwinds
+                    .last_mut()
+                    .ok_or_else(|| Fault::Invalid("endfinally outside of a finally handler".to_string()))?;
+                match unwind.handlers.pop() {
+                    Some(handler) => Ok(Flow::Jump(handler)),
+                    None => {
+                        let target = unwind.target;
+                        frame.unwinds.pop();
+                        Ok(Flow::Jump(target))
+                    }
+                }
+            }
+            _ => Err(Fault::NotSupported(format!("{} is not supported", opcode.mnemonic()))),
+        }
+    }
+}
+
+/// Adds the objects `value` references to `roots`.
+fn references(value: &Value, roots: &mut Vec<ObjectRef>) {
+    match *value {
+        Value::Object(Some(object)) | Value::Pointer(Pointer::Heap { object, .. }) => roots.push(object),
+        Value::ValueType(ref ty, ref data) => {
+            for &offset in &ty.references {
+                if let Ok(value) = data.pread_with(offset as usize, scroll::LE) {
+                    roots.extend(decode_reference(value));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Argument or local index of an instruction, `first` is the opcode of the form with index 0 built in.
+fn var(instruction: &Instruction<'_>, first: OpCode) -> u16 {
+    match instruction.operand {
+        Operand::Var(index) => index,
+        _ => instruction.opcode.value() - first.value(),
+    }
+}
+
+fn target(instruction: &Instruction<'_>) -> Result<u32, Fault> {
+    match instruction.operand {
+        Operand::Target(target) => Ok(target),
+        _ => Err(Fault::Invalid("Branch without a target".to_string())),
+    }
+}
+
+fn token(instruction: &Instruction<'_>) -> Result<Token, Fault> {
+    instruction
+        .token()
+        .ok_or_else(|| Fault::Invalid(format!("{} without a token", instruction.opcode.mnemonic())))
+}
+
+/// Runs the entry point of `assembly` and returns the process exit code.
+pub fn run(assembly: &Assembly<'_>) -> Result<i32, Error> {
+    Interpreter::new(assembly).run_entry_point()
+}
+
+/// Runs the entry point of `assembly`, with the assemblies `resolver` finds for its references, and
+/// returns the process exit code.
+pub fn run_with_resolver<R: AssemblyResolver>(assembly: Rc<Assembly<'static>>, resolver: R) -> Result<i32, Error> {
+    Interpreter::with_resolver(assembly, resolver).run_entry_point()
+}
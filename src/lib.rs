@@ -0,0 +1,25 @@
+//! Reader for .Net assemblies: PE/CLI headers, ECMA-335 metadata tables and heaps.
+
+mod assembly;
+pub mod cli_header;
+pub mod coded_index;
+pub mod disasm;
+pub mod error;
+pub mod export;
+pub mod heaps;
+pub mod instruction;
+pub mod interpreter;
+pub mod metadata;
+pub mod method_body;
+pub mod platform;
+pub mod resolver;
+pub mod runtime;
+pub mod signature;
+pub mod tables;
+pub mod token;
+
+pub use crate::assembly::{
+    Assembly, Attribute, FieldDefinition, GenericParameter, MethodDefinition, TypeDefinition, TypeHandle,
+    TypeReference, TypeSpecification,
+};
+pub use crate::error::Error;
@@ -1,245 +1,396 @@
-use failure::{bail, err_msg, Error};
-use goblin::container::Endian;
-use goblin::pe::data_directories::DataDirectory;
-use goblin::pe::utils::{find_offset, get_data};
-use goblin::pe::PE;
-use scroll::ctx::TryFromCtx;
-use scroll::{self, Pread};
+use dotnet_rs::cli_header;
+use dotnet_rs::disasm::{disassemble, Disassembler};
+use dotnet_rs::export::export;
+use dotnet_rs::interpreter;
+use dotnet_rs::metadata::ValidationMode;
+use dotnet_rs::resolver::{AssemblyResolver, DirectoryResolver};
+use dotnet_rs::tables::TableId;
+use dotnet_rs::token::{Token, TokenKind};
+use dotnet_rs::{Assembly, Error};
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+use std::rc::Rc;
+use structopt::StructOpt;
 
-#[repr(C)]
-#[derive(Debug, Pread)]
-pub struct CliHeader {
-    pub cb: u32,
-    pub major_version: u16,
-    pub minor_version: u16,
-    pub metadata: DataDirectory,
-    pub flags: u32,
-    pub entry_point_token: u32,
+#[derive(StructOpt)]
+#[structopt(name = "dotnet-rs", about = "Inspects .Net assemblies")]
+struct Options {
+    /// Print JSON instead of tables
+    #[structopt(long, global = true)]
+    json: bool,
+    /// Refuse images whose metadata root breaks ECMA-335
+    #[structopt(long, global = true)]
+    strict: bool,
+    /// Directory to look for referenced assemblies in after the application folder, may be repeated
+    #[structopt(long = "reference", global = true, number_of_values = 1)]
+    references: Vec<PathBuf>,
+    #[structopt(subcommand)]
+    command: Command,
 }
 
-#[repr(C)]
-#[derive(Debug)]
-struct MetadataRoot<'a> {
-    pub signature: u32,
-    pub major_version: u16,
-    pub minor_version: u16,
-    _reserved: u32,
-    pub length: u32,
-    pub version: &'a str,
-    pub flags: u16,
-    pub streams: u16,
-    pub stream_headers: Vec<StreamHeader<'a>>,
+#[derive(StructOpt)]
+enum Command {
+    /// PE and CLI headers
+    Headers { path: PathBuf },
+    /// Metadata stream headers
+    Streams { path: PathBuf },
+    /// Row counts of the metadata tables, or the rows of a single table
+    Tables {
+        /// Table to dump, e.g. TypeDef
+        #[structopt(long)]
+        table: Option<String>,
+        path: PathBuf,
+    },
+    /// Contents of the #Strings heap
+    Strings { path: PathBuf },
+    /// Contents of the #US heap
+    Userstrings { path: PathBuf },
+    /// Contents of the #Blob heap
+    Blobs { path: PathBuf },
+    /// The entry point method
+    Entrypoint { path: PathBuf },
+    /// Type definitions with their base types and interfaces
+    Types { path: PathBuf },
+    /// Method definitions with their signatures
+    Methods { path: PathBuf },
+    /// TypeRefs and MemberRefs with the definitions they resolve to
+    Resolve { path: PathBuf },
+    /// ildasm-style IL listing
+    Disasm { path: PathBuf },
+    /// Interprets the entry point and exits with its return value
+    Run { path: PathBuf },
+    /// ECMA-335 violations found in the metadata root
+    Validate { path: PathBuf },
+    /// The whole metadata as JSON, laid out as described by schema/metadata.schema.json
+    Export { path: PathBuf },
 }
 
-#[repr(C)]
-#[derive(Debug)]
-struct StreamHeader<'a> {
-    pub offset: u32,
-    pub size: u32,
-    pub name: &'a str,
+fn hex(value: u32) -> String {
+    format!("0x{:08x}", value)
 }
 
-impl<'a> TryFromCtx<'a, Endian> for MetadataRoot<'a> {
-    type Error = scroll::Error;
-    type Size = usize;
-    // and the lifetime annotation on `&'a [u8]` here
-    fn try_from_ctx(src: &'a [u8], endian: Endian) -> Result<(Self, Self::Size), Self::Error> {
-        let offset = &mut 0;
-        let signature = src.gread_with(offset, endian)?;
-        let major_version = src.gread_with(offset, endian)?;
-        let minor_version = src.gread_with(offset, endian)?;
-        let reserved = src.gread_with(offset, endian)?;
-        let length = src.gread_with(offset, endian)?;
-        let version = src.gread(offset)?;
-        let padding = 4 - *offset % 4;
-        if padding < 4 {
-            *offset += padding;
-        }
-        let flags = src.gread_with(offset, endian)?;
-        let streams: u16 = src.gread_with(offset, endian)?;
-        let mut stream_headers = Vec::with_capacity(streams as usize);
-        for _ in 0..streams {
-            stream_headers.push(src.gread(offset)?);
-            let padding = 4 - *offset % 4;
-            if padding < 4 {
-                *offset += padding;
-            }
-        }
+fn headers(assembly: &Assembly) -> Result<Value, Box<dyn std::error::Error>> {
+    let header = assembly.cli_header();
+    let directory = |x| cli_header::serialize_directory(x, serde_json::value::Serializer);
+    Ok(json!({
+        "machine": format!("0x{:04x}", assembly.machine()),
+        "platform": format!("{:?}", assembly.platform()),
+        "pe32_plus": assembly.is_pe32_plus(),
+        "runtime_version": format!("{}.{}", header.major_version, header.minor_version),
+        "metadata_version": assembly.metadata_root()?.version,
+        "flags": hex(header.flags),
+        "il_only": header.is_il_only(),
+        "32bit_required": header.is_32bit_required(),
+        "32bit_preferred": header.is_32bit_preferred(),
+        "strong_name_signed": header.is_strong_name_signed(),
+        "entry_point_token": header.entry_point_token().map(hex),
+        "entry_point_rva": header.entry_point_rva().map(hex),
+        "metadata": directory(&header.metadata)?,
+        "resources": directory(&header.resources)?,
+        "strong_name_signature": directory(&header.strong_name_signature)?,
+        "code_manager_table": directory(&header.code_manager_table)?,
+        "vtable_fixups": directory(&header.vtable_fixups)?,
+        "export_address_table_jumps": directory(&header.export_address_table_jumps)?,
+        "managed_native_header": directory(&header.managed_native_header)?,
+    }))
+}
 
-        Ok((
-            Self {
-                signature,
-                major_version,
-                minor_version,
-                _reserved: reserved,
-                length,
-                version,
-                flags,
-                streams,
-                stream_headers,
-            },
-            *offset,
-        ))
-    }
+fn streams(assembly: &Assembly) -> Result<Value, Box<dyn std::error::Error>> {
+    Ok(assembly
+        .metadata_root()?
+        .stream_headers
+        .iter()
+        .map(|x| {
+            json!({
+                "name": x.name,
+                "offset": x.offset,
+                "size": x.size,
+            })
+        })
+        .collect())
 }
 
-impl<'a> TryFromCtx<'a, Endian> for StreamHeader<'a> {
-    type Error = scroll::Error;
-    type Size = usize;
-    // and the lifetime annotation on `&'a [u8]` here
-    fn try_from_ctx(src: &'a [u8], endian: Endian) -> Result<(Self, Self::Size), Self::Error> {
-        let offset = &mut 0;
-        let offset_field = src.gread_with(offset, endian)?;
-        let size = src.gread_with(offset, endian)?;
-        let name = src.gread(offset)?;
-        Ok((
-            Self {
-                offset: offset_field,
-                size,
-                name,
-            },
-            *offset,
-        ))
+fn tables(assembly: &Assembly, table: Option<String>) -> Result<Value, Box<dyn std::error::Error>> {
+    let tables = assembly.tables();
+    let table = match table {
+        Some(name) => TableId::from_name(&name).ok_or_else(|| format!("Unknown table {}", name))?,
+        None => {
+            return Ok((0..=0x2c)
+                .filter_map(TableId::from_u8)
+                .filter(|&x| tables.row_count(x) != 0)
+                .map(|x| {
+                    json!({
+                        "id": format!("0x{:02x}", x as u8),
+                        "name": x.name(),
+                        "rows": tables.row_count(x),
+                        "sorted": tables.sorted >> (x as u8) & 1 != 0,
+                    })
+                })
+                .collect());
+        }
+    };
+    let mut rows = Vec::new();
+    for rid in 1..=tables.row_count(table) as u32 {
+        let mut row = Map::new();
+        row.insert("rid".to_string(), json!(rid));
+        if let Value::Object(columns) =
+            serde_json::to_value(assembly.resolve(Token::new(TokenKind::Table(table), rid))?)?
+        {
+            row.extend(columns);
+        }
+        rows.push(Value::Object(row));
     }
+    Ok(Value::Array(rows))
 }
 
-#[repr(C)]
-#[derive(Debug)]
-pub struct TildaStream {
-    _reserved: u32,
-    pub major_version: u8,
-    pub minor_version: u8,
-    pub heap_sizes: u8,
-    _reserved2: u8,
-    pub valid: u64,
-    pub sorted: u64,
-    pub rows: Vec<(u32, u32)>,
-    pub methods: Vec<MethodDef>,
+fn entry_point(assembly: &Assembly) -> Result<Value, Box<dyn std::error::Error>> {
+    let method = match assembly.entry_point()? {
+        Some(method) => method,
+        None => return Ok(Value::Null),
+    };
+    Ok(json!({
+        "token": hex(method.token().raw()),
+        "name": method.name()?,
+        "type": method.declaring_type().map(|x| x.full_name()).transpose()?,
+        "signature": Disassembler::new(assembly).token(method.token())?,
+        "rva": hex(method.row.rva),
+    }))
 }
 
-impl<'a> TryFromCtx<'a, Endian> for TildaStream {
-    type Error = scroll::Error;
-    type Size = usize;
-    // and the lifetime annotation on `&'a [u8]` here
-    fn try_from_ctx(src: &'a [u8], endian: Endian) -> Result<(Self, Self::Size), Self::Error> {
-        let offset = &mut 0;
-        let _reserved = src.gread_with(offset, endian)?;
-        let major_version = src.gread_with(offset, endian)?;
-        let minor_version = src.gread_with(offset, endian)?;
-        let heap_sizes = src.gread_with(offset, endian)?;
-        let _reserved2 = src.gread_with(offset, endian)?;
-        let valid = src.gread_with(offset, endian)?;
-        let sorted = src.gread_with(offset, endian)?;
-        let mut rows = Vec::new();
-
-        let mut j = 1;
-        for i in 0..64_u32 {
-            if valid & j == j {
-                let count = src.gread_with(offset, endian)?;
-                rows.push((i, count));
-            }
-            j <<= 1;
-        }
-
-        let table_sizes = [
-            10_u32, 6, 14, 0, 6, 0, 14, 0, // 0x00 - 0x07
-            6, 0, 6, 0, 6, 0, 0, 0,    // 0x08 - 0x0f
-            0, 2, 0, 0, 0, 0, 0, 0,    // 0x10 - 0x17
-            0, 0, 0, 0, 0, 0, 0, 0,    // 0x18 - 0x1f
-            22, 0, 0, 20               // 0x20 - 0x23
-        ];
+fn types(assembly: &Assembly) -> Result<Value, Box<dyn std::error::Error>> {
+    let disassembler = Disassembler::new(assembly);
+    assembly
+        .types()
+        .map(|x| {
+            let base_type = match x.base_type()? {
+                Some(base_type) => Some(disassembler.token(base_type.token())?),
+                None => None,
+            };
+            let interfaces = x
+                .interfaces()
+                .map(|x| disassembler.token(x?.token()))
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(json!({
+                "token": hex(x.token().raw()),
+                "name": x.full_name()?,
+                "extends": base_type,
+                "implements": interfaces.join(", "),
+                "methods": x.methods().count(),
+                "fields": x.fields().count(),
+            }))
+        })
+        .collect()
+}
 
-        let mut methods = Vec::new();
+fn methods(assembly: &Assembly) -> Result<Value, Box<dyn std::error::Error>> {
+    let disassembler = Disassembler::new(assembly);
+    assembly
+        .methods()
+        .map(|x| {
+            Ok(json!({
+                "token": hex(x.token().raw()),
+                "name": x.name()?,
+                "signature": disassembler.token(x.token())?,
+                "rva": hex(x.row.rva),
+                "flags": format!("0x{:04x}", x.row.flags),
+                "impl_flags": format!("0x{:04x}", x.row.impl_flags),
+            }))
+        })
+        .collect()
+}
 
-        for (i, count) in rows.iter().cloned() {
-            if i == 6 {
-                for _ in 0..count {
-                    methods.push(src.gread(offset)?);
-                }
-            } else if i >= table_sizes.len() as u32 {
-                break;
-            } else {
-                *offset += (table_sizes[i as usize] * count) as usize;
-            }
-        }
+fn resolve(
+    assembly: &Rc<Assembly<'static>>,
+    resolver: &DirectoryResolver,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let disassembler = Disassembler::new(assembly);
+    let type_refs = (1..=assembly.tables().type_refs.len() as u32).map(|rid| {
+        (
+            Token::new(TokenKind::Table(TableId::TypeRef), rid),
+            resolver.resolve_type_ref(assembly, rid),
+        )
+    });
+    let member_refs = (1..=assembly.tables().member_refs.len() as u32).map(|rid| {
+        (
+            Token::new(TokenKind::Table(TableId::MemberRef), rid),
+            resolver.resolve_member_ref(assembly, rid),
+        )
+    });
+    type_refs
+        .chain(member_refs)
+        .map(|(token, definition)| {
+            let (assembly, definition) = match definition {
+                Ok(x) => (x.assembly.name().map(str::to_string), hex(x.token.raw())),
+                Err(error) => (None, format!("error: {}", error)),
+            };
+            Ok(json!({
+                "token": hex(token.raw()),
+                "reference": disassembler.token(token)?,
+                "assembly": assembly,
+                "definition": definition,
+            }))
+        })
+        .collect()
+}
 
-        Ok((
-            Self {
-                _reserved,
-                major_version,
-                minor_version,
-                heap_sizes,
-                _reserved2,
-                valid,
-                sorted,
-                rows,
-                methods,
-            },
-            *offset,
-        ))
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
     }
 }
 
-#[repr(C, packed)]
-#[derive(Debug, Copy, Clone, Pread)]
-pub struct MethodDef {
-    pub rva: u32,
-    pub impl_flags: u16,
-    pub flags: u16,
-    pub name: u16,
-    pub signature: u16,
-    pub param_list: u16,
+fn print_table(rows: &[Value]) {
+    let headers = match rows.first() {
+        Some(Value::Object(columns)) => columns.keys().cloned().collect::<Vec<_>>(),
+        _ => return,
+    };
+    let cells = rows
+        .iter()
+        .map(|row| {
+            headers
+                .iter()
+                .map(|x| row.get(x).map(cell).unwrap_or_default())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let widths = headers
+        .iter()
+        .enumerate()
+        .map(|(i, x)| cells.iter().map(|row| row[i].chars().count()).fold(x.len(), usize::max))
+        .collect::<Vec<_>>();
+    let print_row = |row: &[String]| {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(x, &width)| format!("{:width$}", x, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+    print_row(&headers);
+    print_row(&widths.iter().map(|&x| "-".repeat(x)).collect::<Vec<_>>());
+    for row in &cells {
+        print_row(row);
+    }
 }
 
-fn main() -> Result<(), Error> {
-    let path = std::env::args()
-        .skip(1)
-        .next()
-        .ok_or_else(|| err_msg("Path to executable was not specified"))?;
-    let file = std::fs::read(path)?;
-    let pe = PE::parse(&file)?;
-    if pe.header.coff_header.machine != 0x14c {
-        bail!("Is not a .Net executable");
+fn print(value: &Value, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value)?);
+        return Ok(());
     }
-    let optional_header = pe.header.optional_header.ok_or_else(|| err_msg("No optional header"))?;
-    let file_alignment = optional_header.windows_fields.file_alignment;
-    let cli_header = optional_header
-        .data_directories
-        .get_clr_runtime_header()
-        .ok_or_else(|| err_msg("No CLI header"))?;
-    let sections = &pe.sections;
-
-    let cli_header_value: CliHeader = get_data(&file, sections, &cli_header, file_alignment)?;
-
-    println!("{:#?}", cli_header_value);
-    let rva = cli_header_value.metadata.virtual_address as usize;
-    let metadata_root_offset =
-        find_offset(rva, sections, file_alignment).ok_or(err_msg("Cannot map rva into offset"))?;
-    let root: MetadataRoot = file.pread_with(metadata_root_offset, scroll::LE)?;
-    println!("{:#?}", root);
+    match value {
+        Value::Array(rows) => print_table(rows),
+        Value::Object(fields) => print_table(
+            &fields
+                .iter()
+                .map(|(name, value)| json!({ "field": name, "value": cell(value) }))
+                .collect::<Vec<_>>(),
+        ),
+        value => println!("{}", cell(value)),
+    }
+    Ok(())
+}
 
-    let offset = metadata_root_offset + root.stream_headers.iter().find(|x| x.name == "#~").unwrap().offset as usize;
-    let tilda_stream: TildaStream = file.pread_with(offset, scroll::LE)?;
-    println!("{:#?}", tilda_stream);
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_args();
+    let mode = if options.strict {
+        ValidationMode::Strict
+    } else {
+        ValidationMode::Lenient
+    };
+    let load = |path: PathBuf| Assembly::from_path_with(path, mode);
+    let references = options.references;
+    let resolver = |path: &PathBuf| {
+        let mut resolver = DirectoryResolver::for_application(path);
+        resolver.set_mode(mode);
+        for directory in &references {
+            resolver.add_directory(directory.clone());
+        }
+        resolver
+    };
+    let value = match options.command {
+        Command::Headers { path } => headers(&load(path)?)?,
+        Command::Streams { path } => streams(&load(path)?)?,
+        Command::Tables { table, path } => tables(&load(path)?, table)?,
+        Command::Strings { path } => {
+            let assembly = load(path)?;
+            let strings = assembly.strings();
+            // Empty entries are mostly padding
+            strings
+                .iter()
+                .filter(|x| !x.1.is_empty())
+                .map(|(offset, value)| json!({ "offset": offset, "value": value }))
+                .collect()
+        }
+        Command::Userstrings { path } => {
+            let assembly = load(path)?;
+            let user_strings = assembly.user_strings();
+            user_strings
+                .iter()
+                .filter(|x| !x.1.is_empty())
+                .map(|(offset, value)| json!({ "offset": offset, "value": value }))
+                .collect()
+        }
+        Command::Blobs { path } => {
+            let assembly = load(path)?;
+            let blobs = assembly.blobs();
+            blobs
+                .iter()
+                .filter(|x| !x.1.is_empty())
+                .map(|(offset, value)| {
+                    json!({
+                        "offset": offset,
+                        "length": value.len(),
+                        "data": value.iter().map(|x| format!("{:02x}", x)).collect::<Vec<_>>().join(" "),
+                    })
+                })
+                .collect()
+        }
+        Command::Entrypoint { path } => entry_point(&load(path)?)?,
+        Command::Types { path } => types(&load(path)?)?,
+        Command::Methods { path } => methods(&load(path)?)?,
+        Command::Resolve { path } => {
+            let resolver = resolver(&path);
+            resolve(&Rc::new(load(path)?), &resolver)?
+        }
+        Command::Disasm { path } => {
+            let assembly = load(path)?;
+            let stdout = std::io::stdout();
+            disassemble(&assembly, &mut stdout.lock())?;
+            return Ok(());
+        }
+        Command::Run { path } => {
+            let resolver = resolver(&path);
+            let code = interpreter::run_with_resolver(Rc::new(load(path)?), resolver)?;
+            std::process::exit(code);
+        }
+        Command::Validate { path } => {
+            // Strict loading stops right after validation, so images too broken to load still get their report
+            let violations = match Assembly::from_path_with(path, ValidationMode::Strict) {
+                Ok(_) => Vec::new(),
+                Err(Error::MetadataViolations { violations, .. }) => violations,
+                Err(error) => return Err(error.into()),
+            };
+            violations
+                .iter()
+                .map(|x| json!({ "violation": x.to_string() }))
+                .collect()
+        }
+        Command::Export { path } => {
+            let assembly = load(path)?;
+            println!("{}", serde_json::to_string_pretty(&export(&assembly)?)?);
+            return Ok(());
+        }
+    };
+    print(&value, options.json)
+}
 
-    let offset = metadata_root_offset
-        + root
-            .stream_headers
-            .iter()
-            .find(|x| x.name == "#Strings")
-            .unwrap()
-            .offset as usize;
-    let name: &str = file.pread(
-        offset + tilda_stream.methods[(cli_header_value.entry_point_token & 0x00ffffff - 1) as usize].name as usize,
-    )?;
-    println!("Entry point name: {}", name);
-    println!("Table id: {}", cli_header_value.entry_point_token >> 24);
-    println!("{}", cli_header_value.entry_point_token & 0xFFFFFF);
-    let names = tilda_stream
-        .methods
-        .iter()
-        .map(|x| file.pread(offset + x.name as usize).unwrap())
-        .collect::<Vec<&str>>();
-    println!("{:?}", names.iter().position(|x| x == &"Main"));
-    Ok(())
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,337 @@
+use crate::error::Error;
+use goblin::container::Endian;
+use scroll::ctx::TryFromCtx;
+use scroll::{self, Pread};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+
+/// "BSJB"
+pub const METADATA_SIGNATURE: u32 = 0x424A_5342;
+/// ECMA-335 caps the padded version string at 255 bytes.
+pub const MAX_VERSION_LENGTH: u32 = 255;
+/// Stream names are at most 32 bytes including the terminator.
+pub const MAX_STREAM_NAME_LENGTH: usize = 31;
+
+/// How to treat a metadata root that doesn't follow ECMA-335 II.24.2.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Refuse to load the image if there is any violation.
+    Strict,
+    /// Load what can be read and report the violations as diagnostics, the way the runtime tolerates them.
+    Lenient,
+}
+
+/// A single departure of the metadata root from ECMA-335.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Violation {
+    /// The root doesn't start with `BSJB`
+    Signature(u32),
+    /// The version length is over 255 bytes or not a multiple of 4
+    VersionLength(u32),
+    /// The version string isn't terminated within `length` bytes
+    VersionNotTerminated {
+        length: u32,
+        version_length: usize,
+    },
+    /// A stream reaches past the end of the metadata directory
+    StreamOutOfBounds {
+        name: String,
+        offset: u32,
+        size: u32,
+        metadata_size: usize,
+    },
+    /// Stream offset or size is not a multiple of 4
+    StreamMisaligned {
+        name: String,
+        offset: u32,
+        size: u32,
+    },
+    StreamNameTooLong(String),
+    DuplicateStream(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::Signature(signature) => write!(
+                f,
+                "Signature is 0x{:08x}, expected 0x{:08x} (BSJB)",
+                signature, METADATA_SIGNATURE
+            ),
+            Violation::VersionLength(length) => write!(
+                f,
+                "Version length {} is over {} or not a multiple of 4",
+                length, MAX_VERSION_LENGTH
+            ),
+            Violation::VersionNotTerminated { length, version_length } => write!(
+                f,
+                "Version string of {} bytes doesn't fit into the {} bytes of its length",
+                version_length, length
+            ),
+            Violation::StreamOutOfBounds {
+                name,
+                offset,
+                size,
+                metadata_size,
+            } => write!(
+                f,
+                "Stream {} at 0x{:x} of {} bytes is out of the {} bytes of metadata",
+                name, offset, size, metadata_size
+            ),
+            Violation::StreamMisaligned { name, offset, size } => write!(
+                f,
+                "Stream {} at 0x{:x} of {} bytes is not 4-byte aligned",
+                name, offset, size
+            ),
+            Violation::StreamNameTooLong(name) => write!(
+                f,
+                "Stream name {} is longer than {} bytes",
+                name, MAX_STREAM_NAME_LENGTH
+            ),
+            Violation::DuplicateStream(name) => write!(f, "Stream {} is defined more than once", name),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Serialize)]
+pub struct MetadataRoot<'a> {
+    pub signature: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    #[serde(skip)]
+    _reserved: u32,
+    pub length: u32,
+    pub version: &'a str,
+    pub flags: u16,
+    pub streams: u16,
+    pub stream_headers: Vec<StreamHeader<'a>>,
+}
+
+#[repr(C)]
+#[derive(Debug, Serialize)]
+pub struct StreamHeader<'a> {
+    pub offset: u32,
+    pub size: u32,
+    pub name: &'a str,
+}
+
+impl<'a> TryFromCtx<'a, Endian> for MetadataRoot<'a> {
+    type Error = Error;
+    // and the lifetime annotation on `&'a [u8]` here
+    fn try_from_ctx(src: &'a [u8], endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let signature = src.gread_with(offset, endian)?;
+        let major_version = src.gread_with(offset, endian)?;
+        let minor_version = src.gread_with(offset, endian)?;
+        let reserved = src.gread_with(offset, endian)?;
+        let length: u32 = src.gread_with(offset, endian)?;
+        let start = *offset;
+        let version: &str = src.gread(offset)?;
+        if length & 3 == 0 && version.len() < length as usize && start + length as usize <= src.len() {
+            *offset = start + length as usize;
+        } else {
+            // A bogus length is reported by `validate`, the string is read up to its terminator then
+            let padding = 4 - *offset % 4;
+            if padding < 4 {
+                *offset += padding;
+            }
+        }
+        let flags = src.gread_with(offset, endian)?;
+        let streams: u16 = src.gread_with(offset, endian)?;
+        let mut stream_headers = Vec::with_capacity(streams as usize);
+        for _ in 0..streams {
+            stream_headers.push(src.gread(offset)?);
+            let padding = 4 - *offset % 4;
+            if padding < 4 {
+                *offset += padding;
+            }
+        }
+
+        Ok((
+            Self {
+                signature,
+                major_version,
+                minor_version,
+                _reserved: reserved,
+                length,
+                version,
+                flags,
+                streams,
+                stream_headers,
+            },
+            *offset,
+        ))
+    }
+}
+
+impl<'a> MetadataRoot<'a> {
+    /// Checks the root read from a metadata directory of `metadata_size` bytes, returns every violation found.
+    pub fn validate(&self, metadata_size: usize) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        if self.signature != METADATA_SIGNATURE {
+            violations.push(Violation::Signature(self.signature));
+        }
+        if self.length > MAX_VERSION_LENGTH || self.length & 3 != 0 {
+            violations.push(Violation::VersionLength(self.length));
+        }
+        if self.version.len() >= self.length as usize {
+            violations.push(Violation::VersionNotTerminated {
+                length: self.length,
+                version_length: self.version.len(),
+            });
+        }
+        let mut names = HashSet::new();
+        for header in &self.stream_headers {
+            let name = header.name.to_string();
+            if u64::from(header.offset) + u64::from(header.size) > metadata_size as u64 {
+                violations.push(Violation::StreamOutOfBounds {
+                    name: name.clone(),
+                    offset: header.offset,
+                    size: header.size,
+                    metadata_size,
+                });
+            }
+            if header.offset & 3 != 0 || header.size & 3 != 0 {
+                violations.push(Violation::StreamMisaligned {
+                    name: name.clone(),
+                    offset: header.offset,
+                    size: header.size,
+                });
+            }
+            if header.name.len() > MAX_STREAM_NAME_LENGTH {
+                violations.push(Violation::StreamNameTooLong(name.clone()));
+            }
+            if !names.insert(header.name) {
+                violations.push(Violation::DuplicateStream(name));
+            }
+        }
+        violations
+    }
+
+    /// Returns the contents of the named stream, `metadata` being the bytes the root was read from.
+    pub fn stream(&self, metadata: &'a [u8], name: &str) -> Option<&'a [u8]> {
+        metadata.get(self.stream_range(name)?)
+    }
+
+    /// Byte range of the named stream relative to the metadata root.
+    pub fn stream_range(&self, name: &str) -> Option<Range<usize>> {
+        let header = self.stream_headers.iter().find(|x| x.name == name)?;
+        let start = header.offset as usize;
+        Some(start..start.checked_add(header.size as usize)?)
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for StreamHeader<'a> {
+    type Error = Error;
+    // and the lifetime annotation on `&'a [u8]` here
+    fn try_from_ctx(src: &'a [u8], endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let offset_field = src.gread_with(offset, endian)?;
+        let size = src.gread_with(offset, endian)?;
+        let name = src.gread(offset)?;
+        Ok((
+            Self {
+                offset: offset_field,
+                size,
+                name,
+            },
+            *offset,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Assembly;
+
+    /// A metadata root with this version `length` field, the version string being padded to 4 bytes whatever it says.
+    fn root(signature: u32, length: u32, streams: &[(u32, u32, &str)]) -> Vec<u8> {
+        let mut src = signature.to_le_bytes().to_vec();
+        src.extend(&[1, 0, 1, 0, 0, 0, 0, 0]);
+        src.extend(&length.to_le_bytes());
+        src.extend(b"v4.0.30319\0\0");
+        src.extend(&[0, 0]);
+        src.extend(&(streams.len() as u16).to_le_bytes());
+        for &(offset, size, name) in streams {
+            src.extend(&offset.to_le_bytes());
+            src.extend(&size.to_le_bytes());
+            src.extend(name.as_bytes());
+            src.extend(&[0; 4][..4 - name.len() % 4]);
+        }
+        src
+    }
+
+    #[test]
+    fn valid_root() {
+        let src = root(METADATA_SIGNATURE, 12, &[(0x6c, 0x10, "#~"), (0x7c, 0x8, "#Strings")]);
+        let root: MetadataRoot = src.pread_with(0, scroll::LE).unwrap();
+        assert_eq!(root.version, "v4.0.30319");
+        assert_eq!(root.stream_range("#Strings"), Some(0x7c..0x84));
+        assert_eq!(root.validate(0x84), Vec::new());
+    }
+
+    #[test]
+    fn violations() {
+        let long = "#0123456789012345678901234567890";
+        let src = root(
+            0x1234_5678,
+            6,
+            &[
+                (0x6c, 0x10, "#~"),
+                (0x7e, 0x8, "#Strings"),
+                (0x100, 0x10, "#~"),
+                (0x80, 0, long),
+            ],
+        );
+        let root: MetadataRoot = src.pread_with(0, scroll::LE).unwrap();
+        assert_eq!(root.version, "v4.0.30319");
+        assert_eq!(
+            root.validate(0x104),
+            vec![
+                Violation::Signature(0x1234_5678),
+                Violation::VersionLength(6),
+                Violation::VersionNotTerminated {
+                    length: 6,
+                    version_length: 10,
+                },
+                Violation::StreamMisaligned {
+                    name: "#Strings".to_string(),
+                    offset: 0x7e,
+                    size: 0x8,
+                },
+                Violation::StreamOutOfBounds {
+                    name: "#~".to_string(),
+                    offset: 0x100,
+                    size: 0x10,
+                    metadata_size: 0x104,
+                },
+                Violation::DuplicateStream("#~".to_string()),
+                Violation::StreamNameTooLong(long.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_mode_refuses_violations() {
+        let mut data = include_bytes!("../tests/fixtures/fib.exe").to_vec();
+        let root = data.windows(4).position(|x| x == b"BSJB").unwrap();
+        assert!(Assembly::from_bytes_with(&data, ValidationMode::Strict).is_ok());
+
+        // A version length that isn't a multiple of 4, the string is still read up to its terminator
+        data[root + 12] = 13;
+        match Assembly::from_bytes_with(&data, ValidationMode::Strict) {
+            Err(Error::MetadataViolations { offset, violations }) => {
+                assert_eq!(offset, root);
+                assert_eq!(violations, vec![Violation::VersionLength(13)]);
+            }
+            other => panic!("Expected metadata violations, got {:?}", other.map(|_| ())),
+        }
+        let assembly = Assembly::from_bytes_with(&data, ValidationMode::Lenient).unwrap();
+        assert_eq!(assembly.diagnostics(), &[Violation::VersionLength(13)]);
+        assert!(assembly.entry_point().unwrap().is_some());
+    }
+}
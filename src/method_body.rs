@@ -0,0 +1,314 @@
+//! CIL method bodies (ECMA-335 II.25.4): header, code and exception handling sections.
+
+use crate::error::Error;
+use crate::token::Token;
+use goblin::container::Endian;
+use scroll::ctx::TryFromCtx;
+use scroll::{self, Pread};
+
+pub const COR_ILMETHOD_TINY_FORMAT: u8 = 0x2;
+pub const COR_ILMETHOD_FAT_FORMAT: u8 = 0x3;
+pub const COR_ILMETHOD_FORMAT_MASK: u8 = 0x3;
+pub const COR_ILMETHOD_MORE_SECTS: u16 = 0x8;
+pub const COR_ILMETHOD_INIT_LOCALS: u16 = 0x10;
+
+pub const COR_ILMETHOD_SECT_EH_TABLE: u8 = 0x1;
+pub const COR_ILMETHOD_SECT_OPT_IL_TABLE: u8 = 0x2;
+pub const COR_ILMETHOD_SECT_FAT_FORMAT: u8 = 0x40;
+pub const COR_ILMETHOD_SECT_MORE_SECTS: u8 = 0x80;
+
+pub const COR_ILEXCEPTION_CLAUSE_EXCEPTION: u32 = 0x0;
+pub const COR_ILEXCEPTION_CLAUSE_FILTER: u32 = 0x1;
+pub const COR_ILEXCEPTION_CLAUSE_FINALLY: u32 = 0x2;
+pub const COR_ILEXCEPTION_CLAUSE_FAULT: u32 = 0x4;
+
+/// Tiny headers don't store it, the runtime assumes 8.
+const TINY_MAX_STACK: u16 = 8;
+
+#[derive(Debug)]
+pub struct MethodBody<'a> {
+    pub max_stack: u16,
+    pub init_locals: bool,
+    /// StandAloneSig token of the locals signature, 0 when the method has no locals
+    pub local_var_sig_token: u32,
+    pub code: &'a [u8],
+    pub exception_clauses: Vec<ExceptionClause>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExceptionClauseKind {
+    /// Typed handler catching exceptions of the given class
+    Catch(Token),
+    /// Handler guarded by the filter block starting at the given code offset
+    Filter(u32),
+    Finally,
+    Fault,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExceptionClause {
+    pub kind: ExceptionClauseKind,
+    pub try_offset: u32,
+    pub try_length: u32,
+    pub handler_offset: u32,
+    pub handler_length: u32,
+}
+
+impl ExceptionClause {
+    /// `offset` is where the clause starts in the method body, for errors.
+    fn new(
+        offset: usize,
+        flags: u32,
+        try_offset: u32,
+        try_length: u32,
+        handler_offset: u32,
+        handler_length: u32,
+        class_token_or_filter_offset: u32,
+    ) -> Result<Self, Error> {
+        let kind = match flags & 0x7 {
+            COR_ILEXCEPTION_CLAUSE_EXCEPTION => {
+                ExceptionClauseKind::Catch(Token::decode(class_token_or_filter_offset)?)
+            }
+            COR_ILEXCEPTION_CLAUSE_FILTER => ExceptionClauseKind::Filter(class_token_or_filter_offset),
+            COR_ILEXCEPTION_CLAUSE_FINALLY => ExceptionClauseKind::Finally,
+            COR_ILEXCEPTION_CLAUSE_FAULT => ExceptionClauseKind::Fault,
+            _ => {
+                return Err(Error::BadMethodBody {
+                    offset,
+                    reason: format!("Invalid exception clause flags 0x{:x}", flags),
+                })
+            }
+        };
+        Ok(Self {
+            kind,
+            try_offset,
+            try_length,
+            handler_offset,
+            handler_length,
+        })
+    }
+
+    pub fn try_range(&self) -> std::ops::Range<u32> {
+        self.try_offset..self.try_offset.saturating_add(self.try_length)
+    }
+
+    pub fn handler_range(&self) -> std::ops::Range<u32> {
+        self.handler_offset..self.handler_offset.saturating_add(self.handler_length)
+    }
+}
+
+fn read_exception_clauses(src: &[u8], offset: &mut usize, clauses: &mut Vec<ExceptionClause>) -> Result<(), Error> {
+    loop {
+        // Sections start on a 4-byte boundary
+        *offset = (*offset + 3) & !3;
+        let kind: u8 = src.gread(offset)?;
+        let fat = kind & COR_ILMETHOD_SECT_FAT_FORMAT != 0;
+        let data_size = if fat {
+            let size: [u8; 3] = [src.gread(offset)?, src.gread(offset)?, src.gread(offset)?];
+            u32::from(size[0]) | u32::from(size[1]) << 8 | u32::from(size[2]) << 16
+        } else {
+            let size: u8 = src.gread(offset)?;
+            let _reserved: u16 = src.gread_with(offset, scroll::LE)?;
+            u32::from(size)
+        } as usize;
+        let data_end = (*offset - 4)
+            .checked_add(data_size)
+            .filter(|&x| x <= src.len())
+            .ok_or_else(|| Error::BadMethodBody {
+                offset: *offset - 4,
+                reason: format!("Method data section of {} bytes is truncated", data_size),
+            })?;
+
+        if kind & COR_ILMETHOD_SECT_EH_TABLE != 0 {
+            let clause_size = if fat { 24 } else { 12 };
+            for _ in 0..data_size.saturating_sub(4) / clause_size {
+                let start = *offset;
+                let clause = if fat {
+                    ExceptionClause::new(
+                        start,
+                        src.gread_with(offset, scroll::LE)?,
+                        src.gread_with(offset, scroll::LE)?,
+                        src.gread_with(offset, scroll::LE)?,
+                        src.gread_with(offset, scroll::LE)?,
+                        src.gread_with(offset, scroll::LE)?,
+                        src.gread_with(offset, scroll::LE)?,
+                    )?
+                } else {
+                    ExceptionClause::new(
+                        start,
+                        u32::from(src.gread_with::<u16>(offset, scroll::LE)?),
+                        u32::from(src.gread_with::<u16>(offset, scroll::LE)?),
+                        u32::from(src.gread::<u8>(offset)?),
+                        u32::from(src.gread_with::<u16>(offset, scroll::LE)?),
+                        u32::from(src.gread::<u8>(offset)?),
+                        src.gread_with(offset, scroll::LE)?,
+                    )?
+                };
+                clauses.push(clause);
+            }
+        }
+        // Other kinds, like OptILTable, are skipped
+        *offset = data_end.max(*offset);
+
+        if kind & COR_ILMETHOD_SECT_MORE_SECTS == 0 {
+            return Ok(());
+        }
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for MethodBody<'a> {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let first: u8 = src.pread(0)?;
+        match first & COR_ILMETHOD_FORMAT_MASK {
+            COR_ILMETHOD_TINY_FORMAT => {
+                *offset += 1;
+                let code_size = (first >> 2) as usize;
+                let code = src.get(1..1 + code_size).ok_or_else(|| Error::BadMethodBody {
+                    offset: 1,
+                    reason: format!("Tiny method body of {} bytes is truncated", code_size),
+                })?;
+                *offset += code_size;
+                Ok((
+                    Self {
+                        max_stack: TINY_MAX_STACK,
+                        init_locals: false,
+                        local_var_sig_token: 0,
+                        code,
+                        exception_clauses: Vec::new(),
+                    },
+                    *offset,
+                ))
+            }
+            COR_ILMETHOD_FAT_FORMAT => {
+                let flags_and_size: u16 = src.gread_with(offset, endian)?;
+                let flags = flags_and_size & 0x0fff;
+                let header_size = (flags_and_size >> 12) as usize * 4;
+                let max_stack = src.gread_with(offset, endian)?;
+                let code_size: u32 = src.gread_with(offset, endian)?;
+                let local_var_sig_token = src.gread_with(offset, endian)?;
+                *offset = header_size.max(*offset);
+                let code = src
+                    .get(*offset..*offset + code_size as usize)
+                    .ok_or_else(|| Error::BadMethodBody {
+                        offset: *offset,
+                        reason: format!("Fat method body of {} bytes is truncated", code_size),
+                    })?;
+                *offset += code_size as usize;
+
+                let mut exception_clauses = Vec::new();
+                if flags & COR_ILMETHOD_MORE_SECTS != 0 {
+                    read_exception_clauses(src, offset, &mut exception_clauses)?;
+                }
+                Ok((
+                    Self {
+                        max_stack,
+                        init_locals: flags & COR_ILMETHOD_INIT_LOCALS != 0,
+                        local_var_sig_token,
+                        code,
+                        exception_clauses,
+                    },
+                    *offset,
+                ))
+            }
+            _ => Err(Error::BadMethodBody {
+                offset: 0,
+                reason: format!("Invalid method body header 0x{:02x}", first),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tables::TableId;
+    use crate::token::TokenKind;
+
+    fn body(src: &[u8]) -> MethodBody<'_> {
+        src.pread_with(0, scroll::LE).unwrap()
+    }
+
+    /// A fat header with init locals and more sections, a 2-byte code block padded to 4 bytes, then `sections`.
+    fn fat(sections: &[u8]) -> Vec<u8> {
+        let mut src = vec![0x1b, 0x30, 0x03, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x11];
+        src.extend(&[0x00, 0x2a, 0x00, 0x00]);
+        src.extend(sections);
+        src
+    }
+
+    #[test]
+    fn tiny_header() {
+        let body = body(&[0x0a, 0x16, 0x2a, 0xff]);
+        assert_eq!(body.code, &[0x16, 0x2a]);
+        assert_eq!(
+            (body.max_stack, body.init_locals, body.local_var_sig_token),
+            (8, false, 0)
+        );
+        assert!(body.exception_clauses.is_empty());
+
+        assert!(MethodBody::try_from_ctx(&[0x0e, 0x16, 0x2a], scroll::LE).is_err());
+        assert!(MethodBody::try_from_ctx(&[0x00], scroll::LE).is_err());
+    }
+
+    #[test]
+    fn fat_header_with_small_section() {
+        // finally clause: try 0..1, handler 1..3
+        let src = fat(&[
+            0x01, 0x10, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x02, 0, 0, 0, 0,
+        ]);
+        let body = body(&src);
+        assert_eq!(
+            (body.max_stack, body.init_locals, body.local_var_sig_token),
+            (3, true, 0x1100_0001)
+        );
+        assert_eq!(body.code, &[0x00, 0x2a]);
+        assert_eq!(
+            body.exception_clauses,
+            vec![ExceptionClause {
+                kind: ExceptionClauseKind::Finally,
+                try_offset: 0,
+                try_length: 1,
+                handler_offset: 1,
+                handler_length: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn fat_header_with_fat_sections() {
+        let mut sections = vec![
+            // Skipped OptILTable section followed by another one
+            COR_ILMETHOD_SECT_OPT_IL_TABLE | COR_ILMETHOD_SECT_MORE_SECTS,
+            0x08,
+            0x00,
+            0x00,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            COR_ILMETHOD_SECT_EH_TABLE | COR_ILMETHOD_SECT_FAT_FORMAT,
+            28,
+            0,
+            0,
+        ];
+        // catch [5]TypeRef: try 0..2, handler 2..4
+        for &x in &[0_u32, 0, 2, 2, 2, 0x0100_0005] {
+            sections.extend(&x.to_le_bytes());
+        }
+        let src = fat(&sections);
+        let body = body(&src);
+        assert_eq!(body.exception_clauses.len(), 1);
+        let clause = body.exception_clauses[0];
+        assert_eq!(
+            clause.kind,
+            ExceptionClauseKind::Catch(Token::new(TokenKind::Table(TableId::TypeRef), 5))
+        );
+        assert_eq!((clause.try_range(), clause.handler_range()), (0..2, 2..4));
+
+        // A section claiming more data than the body has
+        let src = fat(&[0x41, 0xff, 0x00, 0x00]);
+        assert!(MethodBody::try_from_ctx(&src, scroll::LE).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::cli_header::{COMIMAGE_FLAGS_32BITPREFERRED, COMIMAGE_FLAGS_32BITREQUIRED, COMIMAGE_FLAGS_ILONLY};
+
+pub const IMAGE_FILE_MACHINE_I386: u16 = 0x014c;
+pub const IMAGE_FILE_MACHINE_ARMNT: u16 = 0x01c4;
+pub const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+pub const IMAGE_FILE_MACHINE_ARM64: u16 = 0xaa64;
+
+/// ReadyToRun images built for other operating systems XOR the machine with one of these.
+const READY_TO_RUN_OS_MACHINE_XOR: [u16; 4] = [
+    0x4644, // Apple
+    0x7b79, // Linux
+    0xadc4, // FreeBSD
+    0x1993, // NetBSD
+];
+
+/// The platform a managed image can run on, as decided by the PE machine and the CLI header flags.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Platform {
+    AnyCpu,
+    AnyCpu32BitPreferred,
+    X86,
+    X64,
+    Arm,
+    Arm64,
+    Unknown(u16),
+}
+
+impl Platform {
+    pub fn new(machine: u16, cli_flags: u32) -> Self {
+        match normalize_machine(machine) {
+            IMAGE_FILE_MACHINE_I386 => {
+                // Prefer 32-bit sets 32BITREQUIRED as well, for runtimes that don't know 32BITPREFERRED
+                let required = cli_flags & COMIMAGE_FLAGS_32BITREQUIRED != 0;
+                let preferred = cli_flags & COMIMAGE_FLAGS_32BITPREFERRED != 0;
+                if cli_flags & COMIMAGE_FLAGS_ILONLY == 0 {
+                    Platform::X86
+                } else if required && preferred {
+                    Platform::AnyCpu32BitPreferred
+                } else if required {
+                    Platform::X86
+                } else {
+                    Platform::AnyCpu
+                }
+            }
+            IMAGE_FILE_MACHINE_AMD64 => Platform::X64,
+            IMAGE_FILE_MACHINE_ARMNT => Platform::Arm,
+            IMAGE_FILE_MACHINE_ARM64 => Platform::Arm64,
+            machine => Platform::Unknown(machine),
+        }
+    }
+}
+
+/// Strips the ReadyToRun target OS from `machine`.
+pub fn normalize_machine(machine: u16) -> u16 {
+    for &os in &READY_TO_RUN_OS_MACHINE_XOR {
+        match machine ^ os {
+            x @ IMAGE_FILE_MACHINE_I386
+            | x @ IMAGE_FILE_MACHINE_ARMNT
+            | x @ IMAGE_FILE_MACHINE_AMD64
+            | x @ IMAGE_FILE_MACHINE_ARM64 => return x,
+            _ => {}
+        }
+    }
+    machine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembly::Assembly;
+    use std::path::Path;
+
+    #[test]
+    fn i386_flags() {
+        let i386 = |flags| Platform::new(IMAGE_FILE_MACHINE_I386, flags);
+        assert_eq!(i386(COMIMAGE_FLAGS_ILONLY), Platform::AnyCpu);
+        assert_eq!(
+            i386(COMIMAGE_FLAGS_ILONLY | COMIMAGE_FLAGS_32BITREQUIRED),
+            Platform::X86
+        );
+        assert_eq!(
+            i386(COMIMAGE_FLAGS_ILONLY | COMIMAGE_FLAGS_32BITREQUIRED | COMIMAGE_FLAGS_32BITPREFERRED),
+            Platform::AnyCpu32BitPreferred
+        );
+        // 32BITPREFERRED means nothing without 32BITREQUIRED
+        assert_eq!(
+            i386(COMIMAGE_FLAGS_ILONLY | COMIMAGE_FLAGS_32BITPREFERRED),
+            Platform::AnyCpu
+        );
+        // Mixed-mode images carry x86 code
+        assert_eq!(i386(0), Platform::X86);
+        assert_eq!(i386(COMIMAGE_FLAGS_32BITPREFERRED), Platform::X86);
+    }
+
+    #[test]
+    fn other_machines() {
+        assert_eq!(
+            Platform::new(IMAGE_FILE_MACHINE_AMD64, COMIMAGE_FLAGS_ILONLY),
+            Platform::X64
+        );
+        assert_eq!(
+            Platform::new(IMAGE_FILE_MACHINE_ARM64, COMIMAGE_FLAGS_ILONLY),
+            Platform::Arm64
+        );
+        assert_eq!(Platform::new(IMAGE_FILE_MACHINE_ARMNT, 0), Platform::Arm);
+        assert_eq!(Platform::new(0x0200, COMIMAGE_FLAGS_ILONLY), Platform::Unknown(0x0200));
+    }
+
+    #[test]
+    fn ready_to_run_machines() {
+        let machines = [
+            IMAGE_FILE_MACHINE_I386,
+            IMAGE_FILE_MACHINE_ARMNT,
+            IMAGE_FILE_MACHINE_AMD64,
+            IMAGE_FILE_MACHINE_ARM64,
+        ];
+        for &os in &READY_TO_RUN_OS_MACHINE_XOR {
+            for &machine in &machines {
+                assert_eq!(normalize_machine(machine ^ os), machine, "{:04x} ^ {:04x}", machine, os);
+            }
+        }
+        assert_eq!(normalize_machine(IMAGE_FILE_MACHINE_AMD64), IMAGE_FILE_MACHINE_AMD64);
+        assert_eq!(normalize_machine(0x0200), 0x0200);
+        assert_eq!(
+            Platform::new(IMAGE_FILE_MACHINE_ARM64 ^ 0x7b79, COMIMAGE_FLAGS_ILONLY),
+            Platform::Arm64
+        );
+        assert_eq!(
+            Platform::new(IMAGE_FILE_MACHINE_AMD64 ^ 0x4644, COMIMAGE_FLAGS_ILONLY),
+            Platform::X64
+        );
+    }
+
+    #[test]
+    fn corpus_images() {
+        let load = |name: &str| {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("fuzz/corpus/assembly")
+                .join(name);
+            Assembly::from_path(path).unwrap()
+        };
+        let hello64 = load("seed-hello64");
+        assert_eq!(hello64.platform(), Platform::X64);
+        assert!(hello64.is_pe32_plus());
+        let arm64_linux = load("seed-hello_arm64_linux");
+        assert_eq!(arm64_linux.machine(), IMAGE_FILE_MACHINE_ARM64 ^ 0x7b79);
+        assert_eq!(arm64_linux.platform(), Platform::Arm64);
+        assert!(arm64_linux.is_pe32_plus());
+        let hello = load("seed-hello");
+        assert_eq!(hello.platform(), Platform::AnyCpu);
+        assert!(!hello.is_pe32_plus());
+    }
+}
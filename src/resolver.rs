@@ -0,0 +1,520 @@
+//! Loading referenced assemblies and resolving TypeRefs and MemberRefs to the TypeDefs, MethodDefs
+//! and Fields that define them.
+//!
+//! References name types and members rather than point at them: a TypeRef is a namespace and a name
+//! in a resolution scope, a MemberRef a name and a signature in a parent type. Resolution looks the
+//! names up in the referenced assembly, following type forwarders, and compares signatures by the
+//! names of the types they mention since tokens are only meaningful inside their own image.
+
+use crate::assembly::{qualified_name, Assembly, FieldDefinition, MethodDefinition, TypeDefinition, MAX_DEPTH};
+use crate::coded_index::{Implementation, MemberRefParent, ResolutionScope, TypeDefOrRef};
+use crate::error::Error;
+use crate::metadata::ValidationMode;
+use crate::signature::{CustomMod, FieldSignature, MethodSignature, Param, Type, SIG_FIELD, SIG_KIND_MASK};
+use crate::tables::TableId;
+use crate::token::{get_row, Token, TokenKind};
+use scroll::{self, Pread};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Identity of an assembly as written in its AssemblyRef rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyName {
+    pub name: String,
+    pub version: [u16; 4],
+    pub culture: String,
+}
+
+impl AssemblyName {
+    /// Name of the assembly referenced by AssemblyRef `rid` of `assembly`.
+    pub fn from_ref(assembly: &Assembly<'_>, rid: u32) -> Result<Self, Error> {
+        let row = get_row(
+            &assembly.tables().assembly_refs,
+            Token::new(TokenKind::Table(TableId::AssemblyRef), rid),
+        )?;
+        let strings = assembly.strings();
+        Ok(Self {
+            name: strings.get(row.name)?.to_string(),
+            version: [
+                row.major_version,
+                row.minor_version,
+                row.build_number,
+                row.revision_number,
+            ],
+            culture: strings.get(row.culture)?.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for AssemblyName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [major, minor, build, revision] = self.version;
+        write!(
+            f,
+            "{}, Version={}.{}.{}.{}, Culture={}",
+            self.name,
+            major,
+            minor,
+            build,
+            revision,
+            if self.culture.is_empty() {
+                "neutral"
+            } else {
+                &self.culture
+            }
+        )
+    }
+}
+
+/// A definition and the assembly it lives in.
+#[derive(Clone)]
+pub struct Definition {
+    pub assembly: Rc<Assembly<'static>>,
+    /// A TypeDef, MethodDef or Field token
+    pub token: Token,
+}
+
+impl Definition {
+    fn new(assembly: &Rc<Assembly<'static>>, table: TableId, rid: u32) -> Self {
+        Self {
+            assembly: assembly.clone(),
+            token: Token::new(TokenKind::Table(table), rid),
+        }
+    }
+
+    pub fn type_definition(&self) -> Option<TypeDefinition<'_>> {
+        match self.token.kind {
+            TokenKind::Table(TableId::TypeDef) => self.assembly.type_definition(self.token.rid),
+            _ => None,
+        }
+    }
+
+    pub fn method(&self) -> Option<MethodDefinition<'_>> {
+        match self.token.kind {
+            TokenKind::Table(TableId::MethodDef) => self.assembly.method(self.token.rid),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self) -> Option<FieldDefinition<'_>> {
+        match self.token.kind {
+            TokenKind::Table(TableId::Field) => self.assembly.field(self.token.rid),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the assemblies an image refers to.
+///
+/// Only `resolve` has to be implemented, resolving references on top of it is shared by every resolver.
+pub trait AssemblyResolver {
+    /// The assembly named `name`, `None` when it can't be found.
+    fn resolve(&self, name: &AssemblyName) -> Result<Option<Rc<Assembly<'static>>>, Error>;
+
+    /// The TypeDef that TypeRef `rid` of `assembly` refers to.
+    fn resolve_type_ref(&self, assembly: &Rc<Assembly<'static>>, rid: u32) -> Result<Definition, Error> {
+        resolve_type_ref(self, assembly, rid, 0)
+    }
+
+    /// The TypeDef behind a TypeDef, TypeRef or the generic type of a TypeSpec instantiation.
+    fn resolve_type(&self, assembly: &Rc<Assembly<'static>>, index: TypeDefOrRef) -> Result<Definition, Error> {
+        resolve_type(self, assembly, index, 0)
+    }
+
+    /// The MethodDef or Field that MemberRef `rid` of `assembly` refers to.
+    fn resolve_member_ref(&self, assembly: &Rc<Assembly<'static>>, rid: u32) -> Result<Definition, Error> {
+        let row = get_row(
+            &assembly.tables().member_refs,
+            Token::new(TokenKind::Table(TableId::MemberRef), rid),
+        )?;
+        let parent = match row.class {
+            MemberRefParent::TypeDef(rid) => Definition::new(assembly, TableId::TypeDef, rid),
+            MemberRefParent::TypeRef(rid) => self.resolve_type_ref(assembly, rid)?,
+            MemberRefParent::TypeSpec(rid) => self.resolve_type(assembly, TypeDefOrRef::TypeSpec(rid))?,
+            // Call site signature of a vararg method defined in this module
+            MemberRefParent::MethodDef(rid) => return Ok(Definition::new(assembly, TableId::MethodDef, rid)),
+            MemberRefParent::ModuleRef(rid) => {
+                let module_ref = get_row(
+                    &assembly.tables().module_refs,
+                    Token::new(TokenKind::Table(TableId::ModuleRef), rid),
+                )?;
+                return Err(Error::UnresolvedMember {
+                    parent: format!("module {}", assembly.strings().get(module_ref.name)?),
+                    name: assembly.strings().get(row.name)?.to_string(),
+                });
+            }
+        };
+        let name = assembly.strings().get(row.name)?;
+        let blob = assembly.blobs().get(row.signature)?;
+        let target = &parent.assembly;
+        let ty = parent.type_definition().ok_or(Error::TokenOutOfRange {
+            token: parent.token,
+            rows: target.tables().type_defs.len(),
+        })?;
+        if blob.first().map(|x| x & SIG_KIND_MASK) == Some(SIG_FIELD) {
+            let signature: FieldSignature = blob.pread_with(0, scroll::LE)?;
+            for field in ty.fields() {
+                if field.name()? == name && same_field(assembly, &signature, target, &field.signature()?)? {
+                    return Ok(Definition::new(target, TableId::Field, field.rid));
+                }
+            }
+        } else {
+            let signature: MethodSignature = blob.pread_with(0, scroll::LE)?;
+            for method in ty.methods() {
+                if method.name()? == name && same_method(assembly, &signature, target, &method.signature()?, 0)? {
+                    return Ok(Definition::new(target, TableId::MethodDef, method.rid));
+                }
+            }
+        }
+        Err(Error::UnresolvedMember {
+            parent: ty.full_name()?,
+            name: name.to_string(),
+        })
+    }
+}
+
+fn resolve_type<R: AssemblyResolver + ?Sized>(
+    resolver: &R,
+    assembly: &Rc<Assembly<'static>>,
+    index: TypeDefOrRef,
+    depth: u32,
+) -> Result<Definition, Error> {
+    match index {
+        TypeDefOrRef::TypeDef(rid) => Ok(Definition::new(assembly, TableId::TypeDef, rid)),
+        TypeDefOrRef::TypeRef(rid) => resolve_type_ref(resolver, assembly, rid, depth),
+        TypeDefOrRef::TypeSpec(rid) => {
+            let token = Token::new(TokenKind::Table(TableId::TypeSpec), rid);
+            let spec = assembly.type_specification(rid).ok_or(Error::TokenOutOfRange {
+                token,
+                rows: assembly.tables().type_specs.len(),
+            })?;
+            match spec.signature()? {
+                Type::GenericInst { generic_type, .. } if depth <= MAX_DEPTH => {
+                    resolve_type(resolver, assembly, generic_type, depth + 1)
+                }
+                Type::GenericInst { .. } => Err(Error::NestedTooDeeply(token)),
+                // Arrays, pointers and generic parameters have no TypeDef of their own
+                _ => Err(Error::UnexpectedToken {
+                    token,
+                    expected: TableId::TypeDef,
+                }),
+            }
+        }
+    }
+}
+
+fn resolve_type_ref<R: AssemblyResolver + ?Sized>(
+    resolver: &R,
+    assembly: &Rc<Assembly<'static>>,
+    rid: u32,
+    depth: u32,
+) -> Result<Definition, Error> {
+    let token = Token::new(TokenKind::Table(TableId::TypeRef), rid);
+    if depth > MAX_DEPTH {
+        return Err(Error::NestedTooDeeply(token));
+    }
+    let reference = assembly.type_reference(rid).ok_or(Error::TokenOutOfRange {
+        token,
+        rows: assembly.tables().type_refs.len(),
+    })?;
+    let namespace = reference.namespace()?;
+    let name = reference.name()?;
+    let unresolved = |scope: String| Error::UnresolvedType {
+        name: qualified_name(namespace, name),
+        scope,
+    };
+    match reference.resolution_scope() {
+        ResolutionScope::TypeRef(outer) => {
+            let outer = resolve_type_ref(resolver, assembly, outer, depth + 1)?;
+            let nested = match outer.type_definition() {
+                Some(ty) => find_nested(&ty, namespace, name)?,
+                None => None,
+            };
+            nested
+                .map(|rid| Definition::new(&outer.assembly, TableId::TypeDef, rid))
+                .ok_or_else(|| unresolved(outer.assembly.name().unwrap_or_default().to_string()))
+        }
+        ResolutionScope::AssemblyRef(scope) => {
+            let assembly_name = AssemblyName::from_ref(assembly, scope)?;
+            let target = resolver
+                .resolve(&assembly_name)?
+                .ok_or_else(|| Error::UnresolvedAssembly(assembly_name.to_string()))?;
+            find_top_level(resolver, &target, namespace, name, depth + 1)?
+                .ok_or_else(|| unresolved(assembly_name.to_string()))
+        }
+        // A null scope means the type is exported from this assembly, `find_top_level` covers both
+        ResolutionScope::Module(_) => find_top_level(resolver, assembly, namespace, name, depth + 1)?
+            .ok_or_else(|| unresolved(assembly.name().unwrap_or_default().to_string())),
+        ResolutionScope::ModuleRef(scope) => {
+            let module_ref = get_row(
+                &assembly.tables().module_refs,
+                Token::new(TokenKind::Table(TableId::ModuleRef), scope),
+            )?;
+            Err(unresolved(format!(
+                "module {}",
+                assembly.strings().get(module_ref.name)?
+            )))
+        }
+    }
+}
+
+fn find_nested(outer: &TypeDefinition<'_>, namespace: &str, name: &str) -> Result<Option<u32>, Error> {
+    for ty in outer.nested_types() {
+        if ty.name()? == name && ty.namespace()? == namespace {
+            return Ok(Some(ty.rid));
+        }
+    }
+    Ok(None)
+}
+
+/// A top-level type defined in `assembly` or forwarded by it to another assembly.
+fn find_top_level<R: AssemblyResolver + ?Sized>(
+    resolver: &R,
+    assembly: &Rc<Assembly<'static>>,
+    namespace: &str,
+    name: &str,
+    depth: u32,
+) -> Result<Option<Definition>, Error> {
+    for ty in assembly.types() {
+        if ty.name()? == name && ty.namespace()? == namespace && ty.enclosing_type().is_none() {
+            return Ok(Some(Definition::new(assembly, TableId::TypeDef, ty.rid)));
+        }
+    }
+    if depth > MAX_DEPTH {
+        return Err(Error::NestedTooDeeply(Token::new(
+            TokenKind::Table(TableId::ExportedType),
+            0,
+        )));
+    }
+    let strings = assembly.strings();
+    for exported in &assembly.tables().exported_types {
+        if strings.get(exported.type_name)? != name || strings.get(exported.type_namespace)? != namespace {
+            continue;
+        }
+        // Types exported from other modules of a multi-module assembly aren't supported
+        if let Implementation::AssemblyRef(scope) = exported.implementation {
+            let assembly_name = AssemblyName::from_ref(assembly, scope)?;
+            let target = resolver
+                .resolve(&assembly_name)?
+                .ok_or_else(|| Error::UnresolvedAssembly(assembly_name.to_string()))?;
+            return find_top_level(resolver, &target, namespace, name, depth + 1);
+        }
+    }
+    Ok(None)
+}
+
+/// Identity of a type named by a TypeDefOrRef, TypeSpecs are compared structurally by the caller.
+fn type_name(assembly: &Assembly<'_>, index: TypeDefOrRef) -> Result<Option<String>, Error> {
+    match assembly.type_handle(index)? {
+        Some(handle) => handle.full_name(),
+        None => Ok(None),
+    }
+}
+
+fn same_type_index(
+    a: &Assembly<'_>,
+    x: TypeDefOrRef,
+    b: &Assembly<'_>,
+    y: TypeDefOrRef,
+    depth: u32,
+) -> Result<bool, Error> {
+    match (x, y) {
+        (TypeDefOrRef::TypeSpec(x), TypeDefOrRef::TypeSpec(y)) => {
+            let (x, y) = match (a.type_specification(x), b.type_specification(y)) {
+                (Some(x), Some(y)) => (x.signature()?, y.signature()?),
+                _ => return Ok(false),
+            };
+            same_type(a, &x, b, &y, depth + 1)
+        }
+        (TypeDefOrRef::TypeSpec(_), _) | (_, TypeDefOrRef::TypeSpec(_)) => Ok(false),
+        _ => Ok(type_name(a, x)? == type_name(b, y)?),
+    }
+}
+
+fn same_custom_mods(
+    a: &Assembly<'_>,
+    x: &[CustomMod],
+    b: &Assembly<'_>,
+    y: &[CustomMod],
+    depth: u32,
+) -> Result<bool, Error> {
+    if x.len() != y.len() {
+        return Ok(false);
+    }
+    for (x, y) in x.iter().zip(y) {
+        if x.required != y.required || !same_type_index(a, x.modifier, b, y.modifier, depth)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn same_types(a: &Assembly<'_>, x: &[Type], b: &Assembly<'_>, y: &[Type], depth: u32) -> Result<bool, Error> {
+    if x.len() != y.len() {
+        return Ok(false);
+    }
+    for (x, y) in x.iter().zip(y) {
+        if !same_type(a, x, b, y, depth)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Whether `x` in `a` and `y` in `b` denote the same type.
+fn same_type(a: &Assembly<'_>, x: &Type, b: &Assembly<'_>, y: &Type, depth: u32) -> Result<bool, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::BadSignature {
+            offset: 0,
+            reason: "Type nested too deeply".to_string(),
+        });
+    }
+    Ok(match (x, y) {
+        (Type::ValueType(x), Type::ValueType(y)) | (Type::Class(x), Type::Class(y)) => {
+            same_type_index(a, *x, b, *y, depth)?
+        }
+        (Type::Ptr(x_mods, x), Type::Ptr(y_mods, y)) | (Type::SzArray(x_mods, x), Type::SzArray(y_mods, y)) => {
+            same_custom_mods(a, x_mods, b, y_mods, depth)? && same_type(a, x, b, y, depth + 1)?
+        }
+        (Type::ByRef(x), Type::ByRef(y)) => same_type(a, x, b, y, depth + 1)?,
+        (Type::FnPtr(x), Type::FnPtr(y)) => same_method(a, x, b, y, depth + 1)?,
+        (Type::Array(x, x_shape), Type::Array(y, y_shape)) => x_shape == y_shape && same_type(a, x, b, y, depth + 1)?,
+        (
+            Type::GenericInst {
+                is_value_type: x_value_type,
+                generic_type: x_type,
+                args: x_args,
+            },
+            Type::GenericInst {
+                is_value_type: y_value_type,
+                generic_type: y_type,
+                args: y_args,
+            },
+        ) => {
+            x_value_type == y_value_type
+                && same_type_index(a, *x_type, b, *y_type, depth)?
+                && same_types(a, x_args, b, y_args, depth + 1)?
+        }
+        // Primitives and generic parameters don't refer to the image
+        _ => x == y,
+    })
+}
+
+fn same_param(a: &Assembly<'_>, x: &Param, b: &Assembly<'_>, y: &Param, depth: u32) -> Result<bool, Error> {
+    Ok(x.by_ref == y.by_ref
+        && same_custom_mods(a, &x.custom_mods, b, &y.custom_mods, depth)?
+        && same_type(a, &x.ty, b, &y.ty, depth)?)
+}
+
+/// Whether a MemberRef signature `x` in `a` matches a MethodDef signature `y` in `b`, vararg arguments aside.
+fn same_method(
+    a: &Assembly<'_>,
+    x: &MethodSignature,
+    b: &Assembly<'_>,
+    y: &MethodSignature,
+    depth: u32,
+) -> Result<bool, Error> {
+    if x.has_this != y.has_this
+        || x.explicit_this != y.explicit_this
+        || x.calling_convention != y.calling_convention
+        || x.generic_param_count != y.generic_param_count
+        || x.params.len() != y.params.len()
+        || !same_param(a, &x.return_type, b, &y.return_type, depth)?
+    {
+        return Ok(false);
+    }
+    for (x, y) in x.params.iter().zip(&y.params) {
+        if !same_param(a, x, b, y, depth)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn same_field(a: &Assembly<'_>, x: &FieldSignature, b: &Assembly<'_>, y: &FieldSignature) -> Result<bool, Error> {
+    Ok(same_custom_mods(a, &x.custom_mods, b, &y.custom_mods, 0)? && same_type(a, &x.ty, b, &y.ty, 0)?)
+}
+
+/// Probes directories for `<name>.dll` and `<name>.exe` and keeps every assembly it loaded.
+///
+/// Assemblies are matched by simple name only, the first directory that has one wins whatever its version.
+pub struct DirectoryResolver {
+    directories: Vec<PathBuf>,
+    mode: ValidationMode,
+    /// Keyed by lowercase simple name, `None` for names that were probed and not found
+    cache: RefCell<HashMap<String, Option<Rc<Assembly<'static>>>>>,
+}
+
+impl DirectoryResolver {
+    pub fn new() -> Self {
+        Self {
+            directories: Vec::new(),
+            mode: ValidationMode::Lenient,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Resolver probing the folder of the application at `path` first.
+    pub fn for_application<P: AsRef<Path>>(path: P) -> Self {
+        let mut resolver = Self::new();
+        resolver.add_directory(
+            path.as_ref()
+                .parent()
+                .filter(|x| !x.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new(".")),
+        );
+        resolver
+    }
+
+    /// Adds a directory to probe after the ones added before, e.g. a reference assembly pack.
+    pub fn add_directory<P: Into<PathBuf>>(&mut self, directory: P) {
+        self.directories.push(directory.into());
+    }
+
+    /// Validation mode referenced assemblies are loaded with, lenient by default.
+    pub fn set_mode(&mut self, mode: ValidationMode) {
+        self.mode = mode;
+    }
+
+    fn probe(&self, name: &str) -> Result<Option<Rc<Assembly<'static>>>, Error> {
+        if !is_file_name(name) {
+            return Ok(None);
+        }
+        for directory in &self.directories {
+            for extension in &["dll", "exe"] {
+                let path = directory.join(format!("{}.{}", name, extension));
+                if path.is_file() {
+                    return Ok(Some(Rc::new(Assembly::from_path_with(path, self.mode)?)));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Whether an assembly name is a plain file name, so the path probed for it stays in the directory.
+/// Names come from the image and may well be `../../secret`, `/etc/passwd` or `C:x`.
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains("..") && !name.contains(&['/', '\\', ':', '\0'][..])
+}
+
+impl Default for DirectoryResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AssemblyResolver for DirectoryResolver {
+    fn resolve(&self, name: &AssemblyName) -> Result<Option<Rc<Assembly<'static>>>, Error> {
+        let key = name.name.to_lowercase();
+        if let Some(assembly) = self.cache.borrow().get(&key) {
+            return Ok(assembly.clone());
+        }
+        let assembly = self.probe(&name.name)?;
+        self.cache.borrow_mut().insert(key, assembly.clone());
+        Ok(assembly)
+    }
+}
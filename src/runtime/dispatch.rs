@@ -0,0 +1,398 @@
+//! Virtual method slots (II.10.3) and interface maps (II.12.2) of the types being loaded.
+
+use super::{Generics, InterfaceMap, MethodTable, RowId, TypeLoader};
+use crate::assembly::TypeDefinition;
+use crate::coded_index::{MemberRefParent, MethodDefOrRef, TypeDefOrRef};
+use crate::error::Error;
+use crate::signature::{MethodSignature, Type, SIG_FIELD, SIG_KIND_MASK};
+use crate::tables::TableId;
+use crate::token::{Token, TokenKind};
+use scroll::Pread;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const TYPE_ATTRIBUTES_INTERFACE: u32 = 0x20;
+const METHOD_ATTRIBUTES_FINAL: u16 = 0x20;
+const METHOD_ATTRIBUTES_VIRTUAL: u16 = 0x40;
+const METHOD_ATTRIBUTES_NEW_SLOT: u16 = 0x100;
+const METHOD_ATTRIBUTES_ABSTRACT: u16 = 0x400;
+
+impl<'a> TypeLoader<'a> {
+    /// Assigns the virtual methods of a type to slots (II.10.3) and maps the interfaces it implements (II.12.2).
+    ///
+    /// Abstract types, interfaces included, may leave methods without an implementation. Signatures
+    /// are compared once the type arguments of the types declaring the methods are substituted in them,
+    /// as canonical signatures for methods of different images to compare equal.
+    pub(super) fn dispatch(
+        &mut self,
+        image: usize,
+        ty: &TypeDefinition<'_>,
+        generics: &Generics,
+        parent: Option<&MethodTable>,
+        is_value_type: bool,
+        is_abstract: bool,
+    ) -> Result<Dispatch, Error> {
+        let assembly = self.image(image);
+        let rows = self.rows(image)?;
+        let token = Token::new(TokenKind::Table(TableId::TypeDef), ty.rid);
+        let type_load = |reason: String| Error::TypeLoad { token, reason };
+        let mut dispatch = match parent {
+            Some(parent) => Dispatch {
+                vtable: parent.vtable.clone(),
+                slots: parent.slots.clone(),
+                interfaces: parent.interfaces.clone(),
+                external_slots: parent.external_slots.clone(),
+                finalizer: parent.finalizer,
+            },
+            None => Dispatch::default(),
+        };
+
+        let mut declared: Vec<Rc<MethodTable>> = Vec::new();
+        for &index in rows.interface_impls.get(&ty.rid).into_iter().flatten() {
+            let row = &assembly.tables().interface_impls[index];
+            let interface = self
+                .load(image, row.interface, generics)?
+                .ok_or_else(|| type_load("Null interface".to_string()))?;
+            let inherited = interface.interfaces.iter().map(|x| x.interface.clone());
+            for interface in std::iter::once(interface.clone()).chain(inherited) {
+                if !declared.iter().any(|x| x.id == interface.id) {
+                    declared.push(interface);
+                }
+            }
+        }
+
+        // Type arguments of the types the methods of the vtable may come from
+        let mut owners = vec![(RowId::new(image, ty.rid), generics.types.clone())];
+        let mut ancestor = parent;
+        while let Some(x) = ancestor {
+            owners.extend(x.definition().map(|owner| (owner, x.instantiation.clone())));
+            ancestor = x.parent.as_deref();
+        }
+        let interfaces = dispatch.interfaces.iter().map(|x| &x.interface).chain(&declared);
+        owners.extend(interfaces.filter_map(|x| Some((x.definition()?, x.instantiation.clone()))));
+
+        // Methods that may implement virtual methods outside the images
+        let mut candidates = Vec::new();
+        for method in ty.methods().filter(|x| x.row.flags & METHOD_ATTRIBUTES_VIRTUAL != 0) {
+            let row = RowId::new(image, method.rid);
+            let name = method.name()?;
+            let signature = self.canonical_signature(image, &method.signature()?, generics)?;
+            let reuses_slot = method.row.flags & METHOD_ATTRIBUTES_NEW_SLOT == 0;
+            let overridden = if reuses_slot {
+                self.find_slot(&dispatch.vtable, name, &signature, &owners)?
+            } else {
+                None
+            };
+            let slot = match overridden {
+                Some(slot) if self.is_final(dispatch.vtable[slot])? => {
+                    return Err(type_load(format!("Method {} overrides a final method", name)))
+                }
+                Some(slot) => {
+                    dispatch.vtable[slot] = row;
+                    slot
+                }
+                None => {
+                    dispatch.vtable.push(row);
+                    dispatch.vtable.len() - 1
+                }
+            };
+            dispatch.slots.insert(row, slot);
+            // Overriding the Finalize of a `System.Object` outside the images doesn't find a slot
+            let overrides_finalize = method.row.flags & METHOD_ATTRIBUTES_NEW_SLOT == 0 && name == "Finalize";
+            if overrides_finalize
+                && !is_value_type
+                && signature.params.is_empty()
+                && signature.return_type.ty == Type::Void
+            {
+                dispatch.finalizer = Some(row);
+            }
+            candidates.push(Candidate {
+                name,
+                signature,
+                slot,
+                overrides: reuses_slot && overridden.is_none(),
+            });
+        }
+
+        self.external_overrides(&rows, parent, &declared, &candidates, &mut dispatch.external_slots)?;
+
+        // Explicit overrides, by the declaration and the method table of the instantiation declaring it if
+        // it is generic, as a type may implement several instantiations of an interface
+        let mut explicit = HashMap::new();
+        for &index in rows.method_impls.get(&ty.rid).into_iter().flatten() {
+            let row = &assembly.tables().method_impls[index];
+            let body = match row.method_body {
+                MethodDefOrRef::MethodDef(body) => RowId::new(image, body),
+                MethodDefOrRef::MemberRef(_) => return Err(type_load("MethodImpl body outside the image".to_string())),
+            };
+            let slot = match dispatch.slots.get(&body) {
+                Some(&slot) => slot,
+                None => {
+                    return Err(type_load(format!(
+                        "MethodImpl body {} is not virtual",
+                        self.method(body)?.name()?
+                    )))
+                }
+            };
+            let (declaration, owner) = match row.method_declaration {
+                MethodDefOrRef::MethodDef(declaration) => (RowId::new(image, declaration), None),
+                MethodDefOrRef::MemberRef(declaration) => match self.member_method(image, declaration, generics)? {
+                    Some((owner, declaration)) => (declaration, Some(owner.id)),
+                    None => {
+                        let row = assembly
+                            .tables()
+                            .member_refs
+                            .get((declaration as usize).wrapping_sub(1))
+                            .ok_or_else(|| type_load("MethodImpl declaration out of range".to_string()))?;
+                        if assembly.strings().get(row.name)? == "Finalize" && !is_value_type {
+                            dispatch.finalizer = Some(body);
+                        }
+                        dispatch.external_slots.insert(RowId::new(image, declaration), slot);
+                        continue;
+                    }
+                },
+            };
+            if let Some(&overridden) = dispatch.slots.get(&declaration) {
+                if self.is_final(dispatch.vtable[overridden])? {
+                    return Err(type_load(format!(
+                        "Method {} overrides a final method",
+                        self.method(body)?.name()?
+                    )));
+                }
+                dispatch.vtable[overridden] = body;
+            }
+            explicit.insert((declaration, owner), slot);
+        }
+
+        for interface in declared {
+            let inherited = dispatch.interfaces.iter().position(|x| x.interface.id == interface.id);
+            let instantiation = Generics::new(interface.instantiation.clone(), Vec::new());
+            let mut slots = Vec::with_capacity(interface.vtable.len());
+            for (index, &declaration) in interface.vtable.iter().enumerate() {
+                let implementation = explicit
+                    .get(&(declaration, Some(interface.id)))
+                    .or_else(|| explicit.get(&(declaration, None)));
+                let slot = match implementation {
+                    // Interfaces only list the interfaces they derive from
+                    _ if ty.row.flags & TYPE_ATTRIBUTES_INTERFACE != 0 => None,
+                    Some(&slot) => Some(slot),
+                    None => {
+                        let method = self.method(declaration)?;
+                        let (name, flags, signature) =
+                            (method.name()?.to_string(), method.row.flags, method.signature()?);
+                        let signature = self.canonical_signature(declaration.image, &signature, &instantiation)?;
+                        match self.find_slot(&dispatch.vtable, &name, &signature, &owners)? {
+                            Some(slot) => Some(slot),
+                            None => match inherited.and_then(|x| dispatch.interfaces[x].slots[index]) {
+                                Some(slot) => Some(slot),
+                                // A default implementation gets a slot of its own
+                                None if flags & METHOD_ATTRIBUTES_ABSTRACT == 0 => {
+                                    dispatch.vtable.push(declaration);
+                                    Some(dispatch.vtable.len() - 1)
+                                }
+                                None => None,
+                            },
+                        }
+                    }
+                };
+                slots.push(slot);
+            }
+            let map = InterfaceMap { interface, slots };
+            match inherited {
+                Some(index) => dispatch.interfaces[index] = map,
+                None => dispatch.interfaces.push(map),
+            }
+        }
+
+        if !is_abstract {
+            for &row in &dispatch.vtable {
+                let method = self.method(row)?;
+                if method.row.flags & METHOD_ATTRIBUTES_ABSTRACT != 0 {
+                    return Err(type_load(format!(
+                        "Abstract method {} has no implementation",
+                        method.name()?
+                    )));
+                }
+            }
+            for map in &dispatch.interfaces {
+                if let Some(index) = map.slots.iter().position(Option::is_none) {
+                    return Err(type_load(format!(
+                        "Method {}::{} has no implementation",
+                        map.interface.name,
+                        self.method(map.interface.vtable[index])?.name()?
+                    )));
+                }
+            }
+        }
+        Ok(dispatch)
+    }
+
+    /// The InterfaceImpl and MethodImpl rows of `image` by class, and its MemberRefs to methods of types
+    /// outside the images by method table, with their canonical signatures read with the type arguments of
+    /// the type. Built once per image by `TypeLoader::rows`, as every type loaded from it looks them up.
+    pub(super) fn index_rows(&mut self, image: usize) -> Result<RowIndex, Error> {
+        let assembly = self.image(image);
+        let mut rows = RowIndex::default();
+        for (index, row) in assembly.tables().interface_impls.iter().enumerate() {
+            rows.interface_impls.entry(row.class).or_default().push(index);
+        }
+        for (index, row) in assembly.tables().method_impls.iter().enumerate() {
+            rows.method_impls.entry(row.class).or_default().push(index);
+        }
+        let mut owners = HashMap::new();
+        for (index, row) in assembly.tables().member_refs.iter().enumerate() {
+            let owner = match row.class {
+                MemberRefParent::TypeRef(rid) => TypeDefOrRef::TypeRef(rid),
+                MemberRefParent::TypeSpec(rid) => TypeDefOrRef::TypeSpec(rid),
+                _ => continue,
+            };
+            let signature = match assembly.blobs().get(row.signature) {
+                Ok(signature) if signature.first().map(|x| x & SIG_KIND_MASK) != Some(SIG_FIELD) => signature,
+                _ => continue,
+            };
+            let owner = match owners.get(&owner) {
+                Some(owner) => Option::clone(owner),
+                None => {
+                    // Open instantiations and types that fail to load can't be implemented by a type
+                    let ty = self.canonical(image, &Type::Class(owner), &Generics::default());
+                    let loaded = match ty.map(|ty| self.load_external(&ty)) {
+                        Ok(Ok(loaded)) => loaded,
+                        _ => None,
+                    };
+                    owners.insert(owner, loaded.clone());
+                    loaded
+                }
+            };
+            let owner = match owner {
+                Some(owner) => owner,
+                None => continue,
+            };
+            // MemberRefs that don't decode are reported when they're called, not by every type of the image
+            let (name, signature) = match (assembly.strings().get(row.name), signature.pread_with(0, scroll::LE)) {
+                (Ok(name), Ok(signature)) => (name.to_string(), signature),
+                _ => continue,
+            };
+            let instantiation = Generics::new(owner.instantiation.clone(), Vec::new());
+            let signature = match self.canonical_signature(image, &signature, &instantiation) {
+                Ok(signature) => signature,
+                Err(_) => continue,
+            };
+            rows.external_methods.entry(owner.id).or_default().push(ExternalMethod {
+                member_ref: RowId::new(image, index as u32 + 1),
+                name,
+                signature,
+            });
+        }
+        Ok(rows)
+    }
+
+    /// Maps the MemberRefs of the image to virtual methods outside the images to the slots of the `candidates`
+    /// implementing them: methods that don't override one in the images for those of the base types and of
+    /// `System.Object`, any of them for those of the interfaces in `declared`. The MemberRefs are found by
+    /// the method table of their parent, the methods themselves by name and signature as with overrides in
+    /// the images.
+    fn external_overrides(
+        &mut self,
+        rows: &RowIndex,
+        parent: Option<&MethodTable>,
+        declared: &[Rc<MethodTable>],
+        candidates: &[Candidate<'_>],
+        external_slots: &mut HashMap<RowId, usize>,
+    ) -> Result<(), Error> {
+        if candidates.is_empty() {
+            return Ok(());
+        }
+        let mut bases = Vec::new();
+        let mut ancestor = parent;
+        while let Some(x) = ancestor {
+            if x.definition().is_none() {
+                bases.push(x.id);
+            }
+            ancestor = x.parent.as_deref();
+        }
+        bases.extend(self.load_canonical(&Type::Object)?.map(|x| x.id));
+        let interfaces = declared.iter().filter(|x| x.definition().is_none()).map(|x| x.id);
+        let owners = bases
+            .into_iter()
+            .map(|x| (x, false))
+            .chain(interfaces.map(|x| (x, true)));
+        for (owner, is_interface) in owners {
+            for method in rows.external_methods.get(&owner).into_iter().flatten() {
+                let implementation = candidates.iter().rev().find(|x| {
+                    (is_interface || x.overrides) && x.name == method.name && x.signature == method.signature
+                });
+                if let Some(implementation) = implementation {
+                    external_slots.insert(method.member_ref, implementation.slot);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The slot of the last virtual method of `vtable` with this name and canonical signature, the
+    /// signatures of the methods of `vtable` being read with the type arguments `owners` gives the types
+    /// declaring them.
+    fn find_slot(
+        &mut self,
+        vtable: &[RowId],
+        name: &str,
+        signature: &MethodSignature,
+        owners: &[(RowId, Vec<Type>)],
+    ) -> Result<Option<usize>, Error> {
+        for (slot, &row) in vtable.iter().enumerate().rev() {
+            let method = self.method(row)?;
+            if method.name()? != name {
+                continue;
+            }
+            let owner = method.declaring_type().map(|x| RowId::new(row.image, x.rid));
+            let declared = method.signature()?;
+            let instantiation = owners.iter().find(|x| Some(x.0) == owner).map(|x| x.1.clone());
+            let generics = Generics::new(instantiation.unwrap_or_default(), Vec::new());
+            if self.canonical_signature(row.image, &declared, &generics)? == *signature {
+                return Ok(Some(slot));
+            }
+        }
+        Ok(None)
+    }
+
+    fn is_final(&self, method: RowId) -> Result<bool, Error> {
+        Ok(self.method(method)?.row.flags & METHOD_ATTRIBUTES_FINAL != 0)
+    }
+}
+
+/// A virtual method of the type being loaded, with its canonical signature read with the type arguments of the type.
+struct Candidate<'a> {
+    name: &'a str,
+    signature: MethodSignature,
+    slot: usize,
+    /// Whether it reuses a slot without overriding a method in the images
+    overrides: bool,
+}
+
+/// Rows of an image by the type they belong to, see `TypeLoader::index_rows`.
+#[derive(Default)]
+pub(super) struct RowIndex {
+    /// Indexes in the InterfaceImpl table by TypeDef
+    interface_impls: HashMap<u32, Vec<usize>>,
+    /// Indexes in the MethodImpl table by TypeDef
+    method_impls: HashMap<u32, Vec<usize>>,
+    /// By the id of the method table of their parent
+    external_methods: HashMap<u32, Vec<ExternalMethod>>,
+}
+
+/// A MemberRef to a method of a type outside the images.
+struct ExternalMethod {
+    member_ref: RowId,
+    name: String,
+    signature: MethodSignature,
+}
+
+/// Virtual methods of a type being loaded.
+#[derive(Default)]
+pub(super) struct Dispatch {
+    pub vtable: Vec<RowId>,
+    pub slots: HashMap<RowId, usize>,
+    pub interfaces: Vec<InterfaceMap>,
+    pub external_slots: HashMap<RowId, usize>,
+    pub finalizer: Option<RowId>,
+}
@@ -0,0 +1,471 @@
+//! The managed heap and its precise, non-moving mark and sweep collector (I.12.6.1).
+//!
+//! The caller supplies the roots, the references inside objects come from the layouts of their
+//! method tables.
+
+use super::{Object, ObjectRef};
+use std::collections::VecDeque;
+
+/// Bytes allocated before the first collection, later ones wait until the heap has grown by as much as survived.
+const MIN_BUDGET: u64 = 1 << 20;
+
+/// How strongly a GC handle holds its target (`System.Runtime.InteropServices.GCHandleType`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HandleKind {
+    /// Cleared once the target is only reachable through finalization
+    Weak,
+    /// Cleared once the target is collected, finalizer or not
+    WeakTrackResurrection,
+    Normal,
+    /// Same as `Normal`, objects never move on this heap
+    Pinned,
+}
+
+impl HandleKind {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Some(match value {
+            0 => HandleKind::Weak,
+            1 => HandleKind::WeakTrackResurrection,
+            2 => HandleKind::Normal,
+            3 => HandleKind::Pinned,
+            _ => return None,
+        })
+    }
+
+    fn is_strong(self) -> bool {
+        self == HandleKind::Normal || self == HandleKind::Pinned
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Handle {
+    pub kind: HandleKind,
+    pub target: Option<ObjectRef>,
+}
+
+/// What a collection did.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Collection {
+    pub freed: usize,
+    /// Unreachable objects queued for their finalizer instead of being freed
+    pub finalizable: usize,
+}
+
+/// The managed heap: objects, GC handles and the finalization queues.
+#[derive(Debug, Default)]
+pub struct Heap {
+    objects: Vec<Option<Object>>,
+    /// Free slots of `objects`, reused by the next allocations
+    free: Vec<u32>,
+    handles: Vec<Option<Handle>>,
+    /// Live objects whose finalizer still has to run once they become unreachable
+    finalizable: Vec<ObjectRef>,
+    /// Unreachable objects whose finalizer hasn't run yet, kept alive until it does
+    pending: VecDeque<ObjectRef>,
+    /// Bytes taken by the live objects
+    size: u64,
+    /// Bytes allocated since the last collection
+    allocated: u64,
+    budget: u64,
+    collections: u32,
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self {
+            budget: MIN_BUDGET,
+            ..Self::default()
+        }
+    }
+
+    /// Allocates `object`, registering it for finalization if its type has a finalizer.
+    pub fn allocate(&mut self, object: Object) -> ObjectRef {
+        let size = object.size();
+        let finalizable = match object {
+            Object::Instance { ref ty, .. } => ty.finalizer.is_some(),
+            _ => false,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.objects[index as usize] = Some(object);
+                index
+            }
+            None => {
+                self.objects.push(Some(object));
+                self.objects.len() as u32 - 1
+            }
+        };
+        self.size += size;
+        self.allocated += size;
+        if finalizable {
+            self.finalizable.push(ObjectRef(index));
+        }
+        ObjectRef(index)
+    }
+
+    /// The object `object` refers to, `None` once it has been collected.
+    pub fn get(&self, object: ObjectRef) -> Option<&Object> {
+        self.objects.get(object.0 as usize).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, object: ObjectRef) -> Option<&mut Object> {
+        self.objects.get_mut(object.0 as usize).and_then(Option::as_mut)
+    }
+
+    /// Number of live objects.
+    pub fn len(&self) -> usize {
+        self.objects.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes taken by the live objects, those waiting for their finalizer included.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn collections(&self) -> u32 {
+        self.collections
+    }
+
+    /// Whether enough was allocated since the last collection to collect again.
+    pub fn needs_collection(&self) -> bool {
+        self.allocated >= self.budget
+    }
+
+    /// Frees the objects not reachable from `roots`, strong handles or the finalization queue.
+    ///
+    /// Unreachable objects with a finalizer are queued for it instead, they and everything they
+    /// reference survive until the next collection after the finalizer ran. Weak handles are
+    /// cleared before finalizers resurrect their targets, those tracking resurrection after.
+    pub fn collect<I: IntoIterator<Item = ObjectRef>>(&mut self, roots: I) -> Collection {
+        let mut marks = vec![false; self.objects.len()];
+        let strong = self
+            .handles
+            .iter()
+            .flatten()
+            .filter(|x| x.kind.is_strong())
+            .filter_map(|x| x.target);
+        let roots = roots
+            .into_iter()
+            .chain(strong)
+            .chain(self.pending.iter().cloned())
+            .collect::<Vec<_>>();
+        self.mark(&mut marks, roots);
+        self.clear_handles(&marks, HandleKind::Weak);
+
+        let (reachable, unreachable): (Vec<_>, Vec<_>) = self
+            .finalizable
+            .iter()
+            .partition(|x| marks.get(x.0 as usize).copied().unwrap_or(false));
+        self.finalizable = reachable;
+        let finalizable = unreachable.len();
+        self.mark(&mut marks, unreachable.clone());
+        self.pending.extend(unreachable);
+        self.clear_handles(&marks, HandleKind::WeakTrackResurrection);
+
+        let mut freed = 0;
+        for (index, mark) in marks.into_iter().enumerate() {
+            if mark {
+                continue;
+            }
+            if let Some(object) = self.objects[index].take() {
+                self.size -= object.size();
+                self.free.push(index as u32);
+                freed += 1;
+            }
+        }
+        self.allocated = 0;
+        self.budget = self.size.max(MIN_BUDGET);
+        self.collections += 1;
+        Collection { freed, finalizable }
+    }
+
+    fn mark(&self, marks: &mut [bool], roots: Vec<ObjectRef>) {
+        let mut stack = roots;
+        while let Some(object) = stack.pop() {
+            match marks.get_mut(object.0 as usize) {
+                Some(mark) if !*mark => *mark = true,
+                _ => continue,
+            }
+            if let Some(object) = self.get(object) {
+                stack.extend(object.references());
+            }
+        }
+    }
+
+    fn clear_handles(&mut self, marks: &[bool], kind: HandleKind) {
+        for handle in self.handles.iter_mut().flatten().filter(|x| x.kind == kind) {
+            if let Some(target) = handle.target {
+                if !marks.get(target.0 as usize).copied().unwrap_or(false) {
+                    handle.target = None;
+                }
+            }
+        }
+    }
+
+    /// The next object whose finalizer has to run, which the collector stops keeping alive.
+    pub fn next_pending(&mut self) -> Option<ObjectRef> {
+        self.pending.pop_front()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// `GC.SuppressFinalize`, the finalizer of `object` won't run.
+    pub fn suppress_finalize(&mut self, object: ObjectRef) {
+        self.finalizable.retain(|&x| x != object);
+    }
+
+    /// `GC.ReRegisterForFinalize`, the finalizer of `object` runs again once it is unreachable.
+    pub fn register_for_finalize(&mut self, object: ObjectRef) {
+        let finalizable = match self.get(object) {
+            Some(Object::Instance { ty, .. }) => ty.finalizer.is_some(),
+            _ => false,
+        };
+        if finalizable && !self.finalizable.contains(&object) {
+            self.finalizable.push(object);
+        }
+    }
+
+    /// Allocates a GC handle and returns its index, which is never 0.
+    pub fn allocate_handle(&mut self, handle: Handle) -> u32 {
+        match self.handles.iter().position(Option::is_none) {
+            Some(index) => {
+                self.handles[index] = Some(handle);
+                index as u32 + 1
+            }
+            None => {
+                self.handles.push(Some(handle));
+                self.handles.len() as u32
+            }
+        }
+    }
+
+    pub fn handle(&self, index: u32) -> Option<&Handle> {
+        self.handles.get(index.checked_sub(1)? as usize)?.as_ref()
+    }
+
+    pub fn handle_mut(&mut self, index: u32) -> Option<&mut Handle> {
+        self.handles.get_mut(index.checked_sub(1)? as usize)?.as_mut()
+    }
+
+    /// Frees a GC handle, `None` if it wasn't allocated.
+    pub fn free_handle(&mut self, index: u32) -> Option<Handle> {
+        self.handles.get_mut(index.checked_sub(1)? as usize)?.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{encode_reference, MethodTable, RowId};
+    use crate::tables::TableId;
+    use crate::token::{Token, TokenKind};
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    /// A class with a single object reference field, and a finalizer if `finalizer` says so.
+    fn class(finalizer: bool) -> Rc<MethodTable> {
+        Rc::new(MethodTable {
+            id: 1,
+            image: 0,
+            token: Token::new(TokenKind::Table(TableId::TypeDef), 2),
+            name: "Node".to_string(),
+            parent: None,
+            instantiation: Vec::new(),
+            element: None,
+            is_value_type: false,
+            is_interface: false,
+            is_abstract: false,
+            primitive: None,
+            size: 8,
+            alignment: 8,
+            fields: Vec::new(),
+            references: vec![0],
+            vtable: Vec::new(),
+            slots: HashMap::new(),
+            interfaces: Vec::new(),
+            external_slots: HashMap::new(),
+            finalizer: if finalizer { Some(RowId::new(0, 1)) } else { None },
+        })
+    }
+
+    fn node(heap: &mut Heap, ty: &Rc<MethodTable>, next: Option<ObjectRef>) -> ObjectRef {
+        heap.allocate(Object::Instance {
+            ty: ty.clone(),
+            data: encode_reference(next).to_le_bytes().to_vec(),
+        })
+    }
+
+    fn string(heap: &mut Heap) -> ObjectRef {
+        heap.allocate(Object::String("value".to_string()))
+    }
+
+    fn target(heap: &Heap, handle: u32) -> Option<ObjectRef> {
+        heap.handle(handle).unwrap().target
+    }
+
+    #[test]
+    fn collect_frees_unreachable_objects() {
+        let mut heap = Heap::new();
+        let ty = class(false);
+        let leaf = string(&mut heap);
+        let root = node(&mut heap, &ty, Some(leaf));
+        let garbage = string(&mut heap);
+        let cycle = node(&mut heap, &ty, None);
+        if let Some(Object::Instance { data, .. }) = heap.get_mut(cycle) {
+            data.copy_from_slice(&encode_reference(Some(cycle)).to_le_bytes());
+        }
+
+        let collection = heap.collect(vec![root]);
+        assert_eq!(
+            collection,
+            Collection {
+                freed: 2,
+                finalizable: 0
+            }
+        );
+        assert!(heap.get(root).is_some());
+        assert!(heap.get(leaf).is_some());
+        assert!(heap.get(garbage).is_none());
+        assert!(heap.get(cycle).is_none());
+        assert_eq!(heap.len(), 2);
+        assert_eq!(heap.collections(), 1);
+        // Freed slots are reused
+        let reused = string(&mut heap);
+        assert!(reused == garbage || reused == cycle);
+    }
+
+    #[test]
+    fn roots_and_strong_handles_keep_objects_alive() {
+        let mut heap = Heap::new();
+        let kept = string(&mut heap);
+        let normal = string(&mut heap);
+        let pinned = string(&mut heap);
+        let weak = string(&mut heap);
+        let handles = [
+            (HandleKind::Normal, normal),
+            (HandleKind::Pinned, pinned),
+            (HandleKind::Weak, weak),
+        ]
+        .iter()
+        .map(|&(kind, target)| {
+            heap.allocate_handle(Handle {
+                kind,
+                target: Some(target),
+            })
+        })
+        .collect::<Vec<_>>();
+
+        for _ in 0..2 {
+            heap.collect(vec![kept]);
+            assert!(heap.get(kept).is_some());
+            assert!(heap.get(normal).is_some());
+            assert!(heap.get(pinned).is_some());
+            assert!(heap.get(weak).is_none());
+        }
+        assert_eq!(target(&heap, handles[0]), Some(normal));
+        assert_eq!(target(&heap, handles[2]), None);
+
+        assert!(heap.free_handle(handles[0]).is_some());
+        heap.collect(vec![kept]);
+        assert!(heap.get(normal).is_none());
+        assert!(heap.handle(handles[0]).is_none());
+    }
+
+    #[test]
+    fn weak_handles_are_cleared_before_resurrection() {
+        let mut heap = Heap::new();
+        let object = node(&mut heap, &class(true), None);
+        let short = heap.allocate_handle(Handle {
+            kind: HandleKind::Weak,
+            target: Some(object),
+        });
+        let long = heap.allocate_handle(Handle {
+            kind: HandleKind::WeakTrackResurrection,
+            target: Some(object),
+        });
+
+        // The finalizer resurrects the object: short weak handles no longer see it, long ones still do
+        heap.collect(Vec::new());
+        assert_eq!(target(&heap, short), None);
+        assert_eq!(target(&heap, long), Some(object));
+        assert!(heap.get(object).is_some());
+
+        // Once the finalizer ran the object is freed and the long handle cleared
+        assert_eq!(heap.next_pending(), Some(object));
+        heap.collect(Vec::new());
+        assert_eq!(target(&heap, long), None);
+        assert!(heap.get(object).is_none());
+    }
+
+    #[test]
+    fn finalizers_are_queued() {
+        let mut heap = Heap::new();
+        let ty = class(true);
+        let referenced = string(&mut heap);
+        let finalizable = node(&mut heap, &ty, Some(referenced));
+        let suppressed = node(&mut heap, &ty, None);
+        let reachable = node(&mut heap, &ty, None);
+        heap.suppress_finalize(suppressed);
+
+        let collection = heap.collect(vec![reachable]);
+        assert_eq!(
+            collection,
+            Collection {
+                freed: 1,
+                finalizable: 1
+            }
+        );
+        assert!(heap.get(suppressed).is_none());
+        // Queued objects keep what they reference alive until their finalizer ran
+        assert!(heap.get(referenced).is_some());
+        assert!(heap.has_pending());
+        assert_eq!(heap.next_pending(), Some(finalizable));
+        assert_eq!(heap.next_pending(), None);
+
+        let collection = heap.collect(vec![reachable]);
+        assert_eq!(
+            collection,
+            Collection {
+                freed: 2,
+                finalizable: 0
+            }
+        );
+
+        // Finalizers run once unless the object registers again
+        assert_eq!(heap.collect(Vec::new()).finalizable, 1);
+        assert_eq!(heap.next_pending(), Some(reachable));
+        heap.register_for_finalize(reachable);
+        assert_eq!(heap.collect(Vec::new()).finalizable, 1);
+        assert_eq!(heap.next_pending(), Some(reachable));
+        assert_eq!(
+            heap.collect(Vec::new()),
+            Collection {
+                freed: 1,
+                finalizable: 0
+            }
+        );
+    }
+
+    #[test]
+    fn stale_references_are_ignored() {
+        let mut heap = Heap::new();
+        let stale = ObjectRef(100);
+        let handle = heap.allocate_handle(Handle {
+            kind: HandleKind::Weak,
+            target: Some(stale),
+        });
+        let strong = heap.allocate_handle(Handle {
+            kind: HandleKind::Normal,
+            target: Some(stale),
+        });
+        heap.collect(vec![stale]);
+        assert_eq!(target(&heap, handle), None);
+        assert_eq!(target(&heap, strong), Some(stale));
+    }
+}
@@ -0,0 +1,18 @@
+//! Runtime representation of types and objects: method tables with their layouts and vtables, the
+//! objects on the managed heap and its garbage collector.
+
+mod dispatch;
+mod gc;
+mod object;
+mod type_loader;
+
+pub use self::gc::{Collection, Handle, HandleKind, Heap};
+pub(crate) use self::object::{decode_reference, encode_reference};
+pub use self::object::{FieldSlot, Kind, Object, ObjectRef};
+pub use self::type_loader::{Generics, Image, InterfaceMap, MethodTable, RowId, TypeLoader};
+
+/// Size of an object reference or a native int.
+const POINTER_SIZE: u32 = 8;
+
+/// Objects and arrays larger than this many bytes can't be allocated, as with the CLR.
+pub(crate) const MAX_ARRAY_SIZE: u64 = i32::MAX as u64;
@@ -0,0 +1,149 @@
+//! Objects on the managed heap and how values are stored in them.
+//!
+//! Object data is kept as raw bytes laid out the way the method table says, object references
+//! taking 8 bytes each.
+
+use super::{MethodTable, RowId, POINTER_SIZE};
+use scroll::Pread;
+use std::rc::Rc;
+
+/// Bytes every object takes besides its data, as with the CLR on 64-bit platforms.
+const OBJECT_HEADER_SIZE: u64 = 16;
+
+/// A reference to an object on the managed heap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ObjectRef(pub(crate) u32);
+
+/// How a value is stored in a field, array element, argument or local variable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    I1,
+    U1,
+    I2,
+    U2,
+    /// int32 and unsigned int32, which only differ in the opcodes applied to them
+    I4,
+    I8,
+    I,
+    R4,
+    R8,
+    Object,
+    /// Managed pointer, only arguments and locals may hold one
+    Pointer,
+    /// Value type defined in the images by the id of its method table, enums are stored as their underlying
+    /// type instead
+    ValueType(u32),
+}
+
+impl Kind {
+    /// Size and alignment of every kind but value types.
+    pub fn primitive_size(self) -> Option<u32> {
+        match self {
+            Kind::I1 | Kind::U1 => Some(1),
+            Kind::I2 | Kind::U2 => Some(2),
+            Kind::I4 | Kind::R4 => Some(4),
+            Kind::I8 | Kind::R8 => Some(8),
+            Kind::I | Kind::Object | Kind::Pointer => Some(POINTER_SIZE),
+            Kind::ValueType(_) => None,
+        }
+    }
+
+    pub(super) fn of_primitive(full_name: &str) -> Option<Self> {
+        Some(match full_name {
+            "System.Boolean" | "System.Byte" => Kind::U1,
+            "System.SByte" => Kind::I1,
+            "System.Char" | "System.UInt16" => Kind::U2,
+            "System.Int16" => Kind::I2,
+            "System.Int32" | "System.UInt32" => Kind::I4,
+            "System.Int64" | "System.UInt64" => Kind::I8,
+            "System.IntPtr" | "System.UIntPtr" => Kind::I,
+            "System.Single" => Kind::R4,
+            "System.Double" => Kind::R8,
+            // A struct wrapping the handle index, which is all the interpreter keeps of it
+            "System.Runtime.InteropServices.GCHandle" => Kind::I,
+            _ => return None,
+        })
+    }
+}
+
+/// An instance field and where it lives in the object data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FieldSlot {
+    /// Field row
+    pub field: RowId,
+    pub offset: u32,
+    pub kind: Kind,
+}
+
+#[derive(Debug)]
+pub enum Object {
+    /// Instance of a class or a boxed value type, its data laid out as the method table says
+    Instance {
+        ty: Rc<MethodTable>,
+        data: Vec<u8>,
+    },
+    String(String),
+    /// Single-dimensional array with a zero lower bound
+    Array {
+        element_type: Rc<MethodTable>,
+        element: Kind,
+        element_size: u32,
+        /// Offsets of the object references in each element
+        element_references: Vec<u32>,
+        length: u32,
+        data: Vec<u8>,
+    },
+}
+
+impl Object {
+    /// Name of the type of the object.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Object::Instance { ty, .. } => &ty.name,
+            Object::String(_) => "System.String",
+            Object::Array { .. } => "System.Array",
+        }
+    }
+
+    /// Bytes the object takes on the heap.
+    pub(super) fn size(&self) -> u64 {
+        OBJECT_HEADER_SIZE
+            + match self {
+                Object::Instance { data, .. } | Object::Array { data, .. } => data.len() as u64,
+                Object::String(value) => value.encode_utf16().count() as u64 * 2,
+            }
+    }
+
+    /// The objects this one references.
+    pub(super) fn references(&self) -> Vec<ObjectRef> {
+        let (data, offsets, stride, count) = match self {
+            Object::Instance { ty, data } => (data, &ty.references, 0, 1),
+            Object::Array {
+                element_size,
+                element_references,
+                length,
+                data,
+                ..
+            } => (data, element_references, *element_size as usize, *length as usize),
+            Object::String(_) => return Vec::new(),
+        };
+        let mut references = Vec::new();
+        for element in 0..count {
+            for &offset in offsets {
+                if let Ok(value) = data.pread_with(element * stride + offset as usize, scroll::LE) {
+                    references.extend(decode_reference(value));
+                }
+            }
+        }
+        references
+    }
+}
+
+/// A reference as stored in object data: 0 for null, the object index plus one otherwise.
+pub(crate) fn encode_reference(object: Option<ObjectRef>) -> u64 {
+    object.map_or(0, |x| u64::from(x.0) + 1)
+}
+
+pub(crate) fn decode_reference(value: u64) -> Option<ObjectRef> {
+    value.checked_sub(1).map(|x| ObjectRef(x as u32))
+}
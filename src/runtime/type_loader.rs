@@ -0,0 +1,1287 @@
+//! Method tables (ECMA-335 I.8.9, II.10.7): the instance layouts of the types the interpreter runs and of
+//! their generic instantiations.
+//!
+//! Types come from the image being run and from the assemblies an `AssemblyResolver` finds for its
+//! references. Types outside those images only get a method table for their name: the primitive value
+//! types of `System` are recognised, any other is taken for a class without fields.
+
+use super::dispatch::RowIndex;
+use super::{FieldSlot, Kind, MAX_ARRAY_SIZE, POINTER_SIZE};
+use crate::assembly::{Assembly, MethodDefinition, MAX_DEPTH};
+use crate::coded_index::{MemberRefParent, ResolutionScope, TypeDefOrRef, TypeOrMethodDef};
+use crate::error::Error;
+use crate::resolver::AssemblyResolver;
+use crate::signature::{CustomMod, FieldSignature, MethodSignature, Param, Type};
+use crate::tables::TableId;
+use crate::token::{Token, TokenKind};
+use scroll::Pread;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::rc::Rc;
+
+const TYPE_ATTRIBUTES_LAYOUT_MASK: u32 = 0x18;
+const TYPE_ATTRIBUTES_EXPLICIT_LAYOUT: u32 = 0x10;
+const TYPE_ATTRIBUTES_INTERFACE: u32 = 0x20;
+const TYPE_ATTRIBUTES_ABSTRACT: u32 = 0x80;
+const FIELD_ATTRIBUTES_STATIC: u16 = 0x10;
+const METHOD_ATTRIBUTES_MEMBER_ACCESS_MASK: u16 = 0x7;
+const METHOD_ATTRIBUTES_PUBLIC: u16 = 0x6;
+const GENERIC_PARAM_ATTRIBUTES_REFERENCE_TYPE: u16 = 0x4;
+const GENERIC_PARAM_ATTRIBUTES_NOT_NULLABLE_VALUE_TYPE: u16 = 0x8;
+const GENERIC_PARAM_ATTRIBUTES_DEFAULT_CONSTRUCTOR: u16 = 0x10;
+
+/// Packing of types without a ClassLayout row, or with a packing size of 0.
+const DEFAULT_PACKING: u32 = 8;
+
+/// A row of a table of one of the images of a loader, `image` being its index among them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RowId {
+    pub image: usize,
+    pub rid: u32,
+}
+
+impl RowId {
+    pub fn new(image: usize, rid: u32) -> Self {
+        Self { image, rid }
+    }
+}
+
+/// An image the loader has types from: the one being run, or an assembly its resolver found.
+#[derive(Clone)]
+pub enum Image<'a> {
+    Borrowed(&'a Assembly<'a>),
+    Shared(Rc<Assembly<'static>>),
+}
+
+impl<'a> Deref for Image<'a> {
+    type Target = Assembly<'a>;
+
+    fn deref(&self) -> &Assembly<'a> {
+        match self {
+            Image::Borrowed(assembly) => assembly,
+            Image::Shared(assembly) => assembly,
+        }
+    }
+}
+
+/// A type as the loader identifies it, whichever image names it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Named {
+    Definition(RowId),
+    /// A type outside the images by full name
+    External(String),
+}
+
+/// Type arguments signatures are read with: those of the type for VAR and those of the method for MVAR.
+///
+/// The arguments are canonical types, written the same whichever image their signature comes from: the
+/// TypeDefOrRef of a named type is a TypeDef whose row is the index of the type among those the loader
+/// names, see `TypeLoader::canonical`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Generics {
+    pub types: Vec<Type>,
+    pub methods: Vec<Type>,
+}
+
+impl Generics {
+    pub fn new(types: Vec<Type>, methods: Vec<Type>) -> Self {
+        Self { types, methods }
+    }
+}
+
+/// Whether `ty` has no generic parameters left.
+fn is_closed(ty: &Type) -> bool {
+    match ty {
+        Type::Var(_) | Type::MVar(_) => false,
+        Type::Ptr(_, x) | Type::ByRef(x) | Type::SzArray(_, x) | Type::Array(x, _) => is_closed(x),
+        Type::FnPtr(signature) => std::iter::once(&signature.return_type)
+            .chain(&signature.params)
+            .all(|x| is_closed(&x.ty)),
+        Type::GenericInst { args, .. } => args.iter().all(is_closed),
+        _ => true,
+    }
+}
+
+/// Name of the `System` type an element type stands for.
+fn element_type_name(ty: &Type) -> Option<&'static str> {
+    Some(match ty {
+        Type::Void => "System.Void",
+        Type::Boolean => "System.Boolean",
+        Type::Char => "System.Char",
+        Type::I1 => "System.SByte",
+        Type::U1 => "System.Byte",
+        Type::I2 => "System.Int16",
+        Type::U2 => "System.UInt16",
+        Type::I4 => "System.Int32",
+        Type::U4 => "System.UInt32",
+        Type::I8 => "System.Int64",
+        Type::U8 => "System.UInt64",
+        Type::R4 => "System.Single",
+        Type::R8 => "System.Double",
+        Type::String => "System.String",
+        Type::Object => "System.Object",
+        Type::I => "System.IntPtr",
+        Type::U => "System.UIntPtr",
+        Type::TypedByRef => "System.TypedReference",
+        _ => return None,
+    })
+}
+
+/// Runtime shape of a type: its instance layout and virtual methods.
+///
+/// Every instantiation of a generic type gets a method table of its own, laid out for its type arguments.
+#[derive(Debug)]
+pub struct MethodTable {
+    /// Identifies the method table among those of its loader, values of value types are stored by it
+    pub id: u32,
+    /// Image defining the type, 0 for types outside the images
+    pub image: usize,
+    /// TypeDef in `image`, a null TypeRef for types outside the images
+    pub token: Token,
+    /// Full name, followed by the type arguments for generic instantiations, e.g. ``Namespace.Pair`2<System.Int32,System.String>``
+    pub name: String,
+    pub parent: Option<Rc<MethodTable>>,
+    /// Canonical type arguments of a generic instantiation, empty for other types
+    pub instantiation: Vec<Type>,
+    /// Element type of array types
+    pub element: Option<Rc<MethodTable>>,
+    pub is_value_type: bool,
+    pub is_interface: bool,
+    pub is_abstract: bool,
+    /// Underlying type of enums and of the primitive value types, which is how their values are stored
+    pub primitive: Option<Kind>,
+    /// Size of the instance fields, inherited ones included; the data of a boxed value type
+    pub size: u32,
+    pub alignment: u32,
+    /// Instance fields in layout order, those of the base types first
+    pub fields: Vec<FieldSlot>,
+    /// Offsets of the object references in the instance data, those in nested value types included
+    pub references: Vec<u32>,
+    /// MethodDefs of the virtual methods by slot, the slots of the base type first
+    pub vtable: Vec<RowId>,
+    /// Slot of each virtual method of the type and its base types by MethodDef, overridden ones included
+    pub slots: HashMap<RowId, usize>,
+    /// Every interface the type implements, those of its base types and of other interfaces included
+    pub interfaces: Vec<InterfaceMap>,
+    /// Slots of the methods overriding virtual methods outside the images, by MemberRef of the latter in
+    /// the image defining the type
+    pub external_slots: HashMap<RowId, usize>,
+    /// MethodDef of the override of `System.Object::Finalize`, inherited ones included
+    pub finalizer: Option<RowId>,
+}
+
+/// An interface and the vtable slots of the type implementing it.
+#[derive(Debug, Clone)]
+pub struct InterfaceMap {
+    pub interface: Rc<MethodTable>,
+    /// Slot implementing each slot of the interface, `None` when an abstract type leaves it to its subclasses
+    pub slots: Vec<Option<usize>>,
+}
+
+impl MethodTable {
+    pub fn field(&self, field: RowId) -> Option<&FieldSlot> {
+        self.fields.iter().find(|x| x.field == field)
+    }
+
+    /// The TypeDef of the type, `None` for types outside the images.
+    pub fn definition(&self) -> Option<RowId> {
+        match self.token.kind {
+            TokenKind::Table(TableId::TypeDef) => Some(RowId::new(self.image, self.token.rid)),
+            _ => None,
+        }
+    }
+
+    /// Whether this type is `other`, derives from it or implements it. Both must come from the same loader,
+    /// which gives each type a single method table.
+    ///
+    /// Arrays are covariant (I.8.7.1): `V[]` is a `W[]` when `V` is a `W` for reference types, and when
+    /// both are stored the same way for value types, e.g. `int[]` is a `uint[]`.
+    pub fn is_subclass_of(&self, other: &MethodTable) -> bool {
+        if let (Some(element), Some(other_element)) = (&self.element, &other.element) {
+            return if element.is_value_type || other_element.is_value_type {
+                element.id == other_element.id
+                    || element.primitive.is_some() && element.primitive == other_element.primitive
+            } else {
+                element.is_subclass_of(other_element)
+            };
+        }
+        let mut ty = Some(self);
+        while let Some(x) = ty {
+            if x.id == other.id || x.interfaces.iter().any(|x| x.interface.id == other.id) {
+                return true;
+            }
+            ty = x.parent.as_deref();
+        }
+        other.name == "System.Object" && !self.is_value_type
+    }
+
+    /// The type arguments this type gives the generic type `owner` it is, derives from or implements,
+    /// which is what the inherited methods of `owner` run with.
+    pub fn instantiation_of(&self, owner: RowId) -> Option<&[Type]> {
+        let mut ty = Some(self);
+        while let Some(x) = ty {
+            if x.definition() == Some(owner) {
+                return Some(&x.instantiation);
+            }
+            ty = x.parent.as_deref();
+        }
+        self.interfaces
+            .iter()
+            .find(|x| x.interface.definition() == Some(owner))
+            .map(|x| x.interface.instantiation.as_slice())
+    }
+}
+
+/// Builds method tables for the types of an image and of the assemblies it refers to, each once.
+pub struct TypeLoader<'a> {
+    /// The image being run first, then those the resolver found as types refer to them
+    images: Vec<Image<'a>>,
+    resolver: Option<Box<dyn AssemblyResolver + 'a>>,
+    /// Types canonical types refer to, by index plus one
+    named: Vec<Named>,
+    named_indexes: HashMap<Named, u32>,
+    /// Index in `named` of the types TypeRefs refer to
+    type_refs: HashMap<RowId, u32>,
+    /// Image of the core library, the one the first image gets `System.Object` from, once looked for
+    corlib: Option<Option<usize>>,
+    /// TypeDefs of the core library element types stand for
+    corlib_types: HashMap<&'static str, Option<RowId>>,
+    /// By TypeDef, then by type arguments, none for types that aren't generic
+    definitions: HashMap<RowId, HashMap<Vec<Type>, Rc<MethodTable>>>,
+    /// Types outside the images by name, type arguments included, for the TypeRefs and element types naming
+    /// the same type to share a method table
+    externals: HashMap<String, Rc<MethodTable>>,
+    /// Value types defined in the images by method table id
+    value_types: HashMap<u32, Rc<MethodTable>>,
+    /// Array types by method table id of their element type
+    arrays: HashMap<u32, Rc<MethodTable>>,
+    next_id: u32,
+    /// Types being loaded, for types containing or deriving from themselves
+    loading: Vec<(RowId, Vec<Type>)>,
+    /// TypeSpecs being read, for those referring to themselves
+    specifications: Vec<RowId>,
+    /// The MethodDef or Field a MemberRef refers to, once found
+    members: HashMap<RowId, RowId>,
+    /// Rows of each image by the type they belong to, built the first time a type of the image is loaded
+    rows: HashMap<usize, Rc<RowIndex>>,
+}
+
+impl<'a> TypeLoader<'a> {
+    /// Loader for the types of `assembly`, those of other assemblies get a method table for their name.
+    pub fn new(assembly: &'a Assembly<'a>) -> Self {
+        Self::with_images(Image::Borrowed(assembly), None)
+    }
+
+    /// Loader for the types of `assembly` and of the assemblies `resolver` finds for its references.
+    pub fn with_resolver<R: AssemblyResolver + 'a>(assembly: Rc<Assembly<'static>>, resolver: R) -> Self {
+        Self::with_images(Image::Shared(assembly), Some(Box::new(resolver)))
+    }
+
+    fn with_images(image: Image<'a>, resolver: Option<Box<dyn AssemblyResolver + 'a>>) -> Self {
+        Self {
+            images: vec![image],
+            resolver,
+            named: Vec::new(),
+            named_indexes: HashMap::new(),
+            type_refs: HashMap::new(),
+            corlib: None,
+            corlib_types: HashMap::new(),
+            definitions: HashMap::new(),
+            externals: HashMap::new(),
+            value_types: HashMap::new(),
+            arrays: HashMap::new(),
+            next_id: 0,
+            loading: Vec::new(),
+            specifications: Vec::new(),
+            members: HashMap::new(),
+            rows: HashMap::new(),
+        }
+    }
+
+    /// Image `index`, 0 being the one being run.
+    pub fn image(&self, index: usize) -> Image<'a> {
+        self.images[index].clone()
+    }
+
+    /// A MethodDef of one of the images.
+    pub fn method(&self, method: RowId) -> Result<MethodDefinition<'_>, Error> {
+        let assembly = &self.images[method.image];
+        assembly.method(method.rid).ok_or(Error::TokenOutOfRange {
+            token: Token::new(TokenKind::Table(TableId::MethodDef), method.rid),
+            rows: assembly.tables().methods.len(),
+        })
+    }
+
+    /// The method table of a TypeDef, TypeRef or TypeSpec of `image`, `None` for a null index. TypeSpecs
+    /// are read with `generics`.
+    pub fn load(
+        &mut self,
+        image: usize,
+        index: TypeDefOrRef,
+        generics: &Generics,
+    ) -> Result<Option<Rc<MethodTable>>, Error> {
+        if index.is_null() {
+            return Ok(None);
+        }
+        let ty = self.canonical_index(image, index, false, generics)?;
+        match self.load_canonical(&ty)? {
+            Some(ty) => Ok(Some(ty)),
+            None => Err(Error::TypeLoad {
+                token: Token::new(TokenKind::Table(index.table()), index.row()),
+                reason: format!("{} has no method table", self.type_name(&ty)?),
+            }),
+        }
+    }
+
+    /// The method table of the type a signature of `image` describes, read with `generics`. `None` for
+    /// multi-dimensional arrays, pointers and generic parameters without an argument, which have none.
+    pub fn load_type(
+        &mut self,
+        image: usize,
+        ty: &Type,
+        generics: &Generics,
+    ) -> Result<Option<Rc<MethodTable>>, Error> {
+        let ty = self.canonical(image, ty, generics)?;
+        self.load_canonical(&ty)
+    }
+
+    /// The method table of a canonical type, see `load_type`.
+    pub fn load_canonical(&mut self, ty: &Type) -> Result<Option<Rc<MethodTable>>, Error> {
+        match ty {
+            Type::Class(TypeDefOrRef::TypeDef(index)) | Type::ValueType(TypeDefOrRef::TypeDef(index)) => {
+                match self.named(*index)? {
+                    Named::Definition(definition) => self.type_definition(definition).map(Some),
+                    Named::External(name) => Ok(Some(self.external(name, Vec::new(), false))),
+                }
+            }
+            Type::GenericInst { args, .. } if !args.iter().all(is_closed) => Ok(None),
+            Type::GenericInst {
+                is_value_type,
+                generic_type: TypeDefOrRef::TypeDef(index),
+                args,
+            } => match self.named(*index)? {
+                Named::Definition(definition) => self.instantiate(definition, args.clone()).map(Some),
+                Named::External(name) => {
+                    let mut names = Vec::with_capacity(args.len());
+                    for arg in args {
+                        names.push(self.type_name(arg)?);
+                    }
+                    let name = format!("{}<{}>", name, names.join(","));
+                    Ok(Some(self.external(name, args.clone(), *is_value_type)))
+                }
+            },
+            Type::SzArray(_, element) => match self.load_canonical(element)? {
+                Some(element) => Ok(Some(self.array_of(element))),
+                None => Ok(None),
+            },
+            ty => match element_type_name(ty) {
+                Some(name) => match self.corlib_type(name)? {
+                    Some(definition) => self.type_definition(definition).map(Some),
+                    None => Ok(Some(self.external(name.to_string(), Vec::new(), false))),
+                },
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Rows of `image` by the type they belong to, indexed the first time they're needed.
+    pub(super) fn rows(&mut self, image: usize) -> Result<Rc<RowIndex>, Error> {
+        if let Some(rows) = self.rows.get(&image) {
+            return Ok(rows.clone());
+        }
+        let rows = Rc::new(self.index_rows(image)?);
+        self.rows.insert(image, rows.clone());
+        Ok(rows)
+    }
+
+    /// The method table of a canonical type outside the images, `None` for types of the images, which
+    /// aren't loaded.
+    pub(super) fn load_external(&mut self, ty: &Type) -> Result<Option<Rc<MethodTable>>, Error> {
+        let index = match ty {
+            Type::Class(TypeDefOrRef::TypeDef(index))
+            | Type::ValueType(TypeDefOrRef::TypeDef(index))
+            | Type::GenericInst {
+                generic_type: TypeDefOrRef::TypeDef(index),
+                ..
+            } => *index,
+            _ => return Ok(None),
+        };
+        match self.named(index)? {
+            Named::External(_) => self.load_canonical(ty),
+            Named::Definition(_) => Ok(None),
+        }
+    }
+
+    /// `ty`, read in `image` with `generics`, as a canonical type: its generic parameters replaced by their
+    /// arguments, those without one are left as they are, and the types it names by their index in the loader.
+    pub fn canonical(&mut self, image: usize, ty: &Type, generics: &Generics) -> Result<Type, Error> {
+        self.canonical_nested(image, ty, generics, 0)
+    }
+
+    fn canonical_nested(&mut self, image: usize, ty: &Type, generics: &Generics, depth: u32) -> Result<Type, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::BadSignature {
+                offset: 0,
+                reason: "Type nested too deeply".to_string(),
+            });
+        }
+        let depth = depth + 1;
+        Ok(match ty {
+            Type::Var(index) => generics
+                .types
+                .get(*index as usize)
+                .cloned()
+                .unwrap_or_else(|| ty.clone()),
+            Type::MVar(index) => generics
+                .methods
+                .get(*index as usize)
+                .cloned()
+                .unwrap_or_else(|| ty.clone()),
+            Type::Class(index) => self.canonical_index(image, *index, false, generics)?,
+            Type::ValueType(index) => self.canonical_index(image, *index, true, generics)?,
+            Type::Ptr(custom_mods, x) => Type::Ptr(
+                self.canonical_mods(image, custom_mods)?,
+                Box::new(self.canonical_nested(image, x, generics, depth)?),
+            ),
+            Type::ByRef(x) => Type::ByRef(Box::new(self.canonical_nested(image, x, generics, depth)?)),
+            Type::FnPtr(signature) => Type::FnPtr(Box::new(self.canonical_signature(image, signature, generics)?)),
+            Type::SzArray(custom_mods, x) => Type::SzArray(
+                self.canonical_mods(image, custom_mods)?,
+                Box::new(self.canonical_nested(image, x, generics, depth)?),
+            ),
+            Type::Array(x, shape) => Type::Array(
+                Box::new(self.canonical_nested(image, x, generics, depth)?),
+                shape.clone(),
+            ),
+            Type::GenericInst {
+                is_value_type,
+                generic_type,
+                args,
+            } => {
+                let generic_type = TypeDefOrRef::TypeDef(self.named_index(image, *generic_type)?);
+                let mut canonical = Vec::with_capacity(args.len());
+                for arg in args {
+                    canonical.push(self.canonical_nested(image, arg, generics, depth)?);
+                }
+                Type::GenericInst {
+                    is_value_type: *is_value_type,
+                    generic_type,
+                    args: canonical,
+                }
+            }
+            ty => ty.clone(),
+        })
+    }
+
+    /// A method signature of `image` read with `generics`, its types canonical, see `canonical`.
+    pub fn canonical_signature(
+        &mut self,
+        image: usize,
+        signature: &MethodSignature,
+        generics: &Generics,
+    ) -> Result<MethodSignature, Error> {
+        let mut params = |params: &[Param]| -> Result<Vec<Param>, Error> {
+            let mut canonical = Vec::with_capacity(params.len());
+            for param in params {
+                canonical.push(Param {
+                    custom_mods: self.canonical_mods(image, &param.custom_mods)?,
+                    ty: self.canonical(image, &param.ty, generics)?,
+                    ..param.clone()
+                });
+            }
+            Ok(canonical)
+        };
+        Ok(MethodSignature {
+            return_type: params(std::slice::from_ref(&signature.return_type))?.remove(0),
+            params: params(&signature.params)?,
+            vararg_params: params(&signature.vararg_params)?,
+            ..signature.clone()
+        })
+    }
+
+    fn canonical_field(&mut self, image: usize, signature: &FieldSignature) -> Result<FieldSignature, Error> {
+        Ok(FieldSignature {
+            custom_mods: self.canonical_mods(image, &signature.custom_mods)?,
+            ty: self.canonical(image, &signature.ty, &Generics::default())?,
+        })
+    }
+
+    /// Custom modifiers with the types they name by index in the loader, TypeSpecs are left as they are.
+    fn canonical_mods(&mut self, image: usize, custom_mods: &[CustomMod]) -> Result<Vec<CustomMod>, Error> {
+        let mut canonical = Vec::with_capacity(custom_mods.len());
+        for custom_mod in custom_mods {
+            let modifier = match custom_mod.modifier {
+                TypeDefOrRef::TypeSpec(rid) => TypeDefOrRef::TypeSpec(rid),
+                modifier => TypeDefOrRef::TypeDef(self.named_index(image, modifier)?),
+            };
+            canonical.push(CustomMod {
+                modifier,
+                ..custom_mod.clone()
+            });
+        }
+        Ok(canonical)
+    }
+
+    /// The canonical type a TypeDef, TypeRef or TypeSpec of `image` stands for, TypeSpecs being read with `generics`.
+    fn canonical_index(
+        &mut self,
+        image: usize,
+        index: TypeDefOrRef,
+        is_value_type: bool,
+        generics: &Generics,
+    ) -> Result<Type, Error> {
+        let rid = match index {
+            TypeDefOrRef::TypeSpec(rid) => rid,
+            index => {
+                let named = TypeDefOrRef::TypeDef(self.named_index(image, index)?);
+                return Ok(if is_value_type {
+                    Type::ValueType(named)
+                } else {
+                    Type::Class(named)
+                });
+            }
+        };
+        let assembly = self.image(image);
+        let token = Token::new(TokenKind::Table(TableId::TypeSpec), rid);
+        let ty = assembly
+            .type_specification(rid)
+            .ok_or(Error::TokenOutOfRange {
+                token,
+                rows: assembly.tables().type_specs.len(),
+            })?
+            .signature()?;
+        let key = RowId::new(image, rid);
+        if self.specifications.contains(&key) {
+            return Err(Error::TypeLoad {
+                token,
+                reason: "TypeSpec refers to itself".to_string(),
+            });
+        }
+        self.specifications.push(key);
+        let canonical = self.canonical(image, &ty, generics);
+        self.specifications.pop();
+        canonical
+    }
+
+    /// Index in `named` plus one of the type a TypeDef or TypeRef of `image` refers to.
+    fn named_index(&mut self, image: usize, index: TypeDefOrRef) -> Result<u32, Error> {
+        let assembly = self.image(image);
+        let out_of_range = || Error::TokenOutOfRange {
+            token: Token::new(TokenKind::Table(index.table()), index.row()),
+            rows: assembly.tables().row_count(index.table()),
+        };
+        match index {
+            TypeDefOrRef::TypeDef(rid) => {
+                assembly.type_definition(rid).ok_or_else(out_of_range)?;
+                Ok(self.intern(Named::Definition(RowId::new(image, rid))))
+            }
+            TypeDefOrRef::TypeRef(rid) => self.type_reference(image, rid),
+            TypeDefOrRef::TypeSpec(rid) => Err(Error::UnexpectedToken {
+                token: Token::new(TokenKind::Table(TableId::TypeSpec), rid),
+                expected: TableId::TypeRef,
+            }),
+        }
+    }
+
+    fn intern(&mut self, named: Named) -> u32 {
+        if let Some(&index) = self.named_indexes.get(&named) {
+            return index;
+        }
+        self.named.push(named.clone());
+        let index = self.named.len() as u32;
+        self.named_indexes.insert(named, index);
+        index
+    }
+
+    fn named(&self, index: u32) -> Result<Named, Error> {
+        self.named
+            .get((index as usize).wrapping_sub(1))
+            .cloned()
+            .ok_or_else(|| Error::TypeLoad {
+                token: Token::new(TokenKind::Table(TableId::TypeDef), 0),
+                reason: format!("Type {} is not canonical", index),
+            })
+    }
+
+    pub fn type_definition(&mut self, definition: RowId) -> Result<Rc<MethodTable>, Error> {
+        self.instantiate(definition, Vec::new())
+    }
+
+    /// The instantiation of a generic TypeDef with closed canonical type `args`, no arguments giving types
+    /// that aren't generic.
+    pub fn instantiate(&mut self, definition: RowId, args: Vec<Type>) -> Result<Rc<MethodTable>, Error> {
+        if let Some(ty) = self.definitions.get(&definition).and_then(|x| x.get(&args)) {
+            return Ok(ty.clone());
+        }
+        let token = Token::new(TokenKind::Table(TableId::TypeDef), definition.rid);
+        if self.loading.iter().any(|x| x.0 == definition && x.1 == args) {
+            return Err(Error::TypeLoad {
+                token,
+                reason: "Type contains or derives from itself".to_string(),
+            });
+        }
+        if self.loading.len() as u32 > MAX_DEPTH {
+            return Err(Error::NestedTooDeeply(token));
+        }
+        self.loading.push((definition, args.clone()));
+        let ty = self.build(definition, args.clone());
+        self.loading.pop();
+        let ty = Rc::new(ty?);
+        self.definitions.entry(definition).or_default().insert(args, ty.clone());
+        if ty.is_value_type {
+            self.value_types.insert(ty.id, ty.clone());
+        }
+        Ok(ty)
+    }
+
+    /// Index in `named` plus one of the type TypeRef `rid` of `image` refers to: a TypeDef of the module
+    /// for references to it, the TypeDef the resolver finds for those to other assemblies and the name of
+    /// the type when there is none.
+    fn type_reference(&mut self, image: usize, rid: u32) -> Result<u32, Error> {
+        let key = RowId::new(image, rid);
+        if let Some(&index) = self.type_refs.get(&key) {
+            return Ok(index);
+        }
+        let assembly = self.image(image);
+        let token = Token::new(TokenKind::Table(TableId::TypeRef), rid);
+        let reference = assembly.type_reference(rid).ok_or(Error::TokenOutOfRange {
+            token,
+            rows: assembly.tables().type_refs.len(),
+        })?;
+        let name = reference.full_name()?;
+        // References to types of this module name their definition
+        let mut outermost = reference;
+        while let Some(x) = outermost.enclosing_type()? {
+            outermost = x;
+        }
+        let named = match outermost.resolution_scope() {
+            ResolutionScope::Module(scope) if scope != 0 => match assembly.find_type(&name) {
+                Some(definition) => Named::Definition(RowId::new(image, definition.rid)),
+                None => {
+                    return Err(Error::TypeLoad {
+                        token,
+                        reason: format!("{} is not defined in this module", name),
+                    })
+                }
+            },
+            _ => match self.resolve(image, rid)? {
+                Some(definition) => Named::Definition(definition),
+                None => Named::External(name),
+            },
+        };
+        let index = self.intern(named);
+        self.type_refs.insert(key, index);
+        Ok(index)
+    }
+
+    /// The TypeDef the resolver finds for TypeRef `rid` of `image`, `None` without a resolver or when the
+    /// assembly or the type can't be found.
+    fn resolve(&mut self, image: usize, rid: u32) -> Result<Option<RowId>, Error> {
+        let (resolver, assembly) = match (&self.resolver, &self.images[image]) {
+            (Some(resolver), Image::Shared(assembly)) => (resolver, assembly),
+            _ => return Ok(None),
+        };
+        let definition = match resolver.resolve_type_ref(assembly, rid) {
+            Ok(definition) => definition,
+            Err(Error::UnresolvedAssembly(_)) | Err(Error::UnresolvedType { .. }) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        // The resolver loads each assembly once, an image may still be found again under its name
+        let name = definition.assembly.name();
+        let index = match self.images.iter().position(|x| match x {
+            Image::Shared(x) if Rc::ptr_eq(x, &definition.assembly) => true,
+            x => name.is_some() && x.name() == name,
+        }) {
+            Some(index) => index,
+            None => {
+                self.images.push(Image::Shared(definition.assembly.clone()));
+                self.images.len() - 1
+            }
+        };
+        Ok(Some(RowId::new(index, definition.token.rid)))
+    }
+
+    /// The TypeDef of the core library an element type stands for, `None` without a resolver or a core library.
+    fn corlib_type(&mut self, name: &'static str) -> Result<Option<RowId>, Error> {
+        if let Some(&definition) = self.corlib_types.get(name) {
+            return Ok(definition);
+        }
+        let definition = match self.corlib()? {
+            Some(corlib) => self.images[corlib].find_type(name).map(|x| RowId::new(corlib, x.rid)),
+            None => None,
+        };
+        self.corlib_types.insert(name, definition);
+        Ok(definition)
+    }
+
+    /// Image of the core library: the one defining the `System.Object` of the image being run.
+    fn corlib(&mut self) -> Result<Option<usize>, Error> {
+        if let Some(corlib) = self.corlib {
+            return Ok(corlib);
+        }
+        let mut corlib = None;
+        if self.resolver.is_some() {
+            let assembly = self.image(0);
+            if assembly.find_type("System.Object").is_some() {
+                corlib = Some(0);
+            }
+            let strings = assembly.strings();
+            for (index, row) in assembly.tables().type_refs.iter().enumerate() {
+                if corlib.is_some() {
+                    break;
+                }
+                if strings.get(row.type_namespace)? == "System" && strings.get(row.type_name)? == "Object" {
+                    let named = self.type_reference(0, index as u32 + 1)?;
+                    if let Named::Definition(definition) = self.named(named)? {
+                        corlib = Some(definition.image);
+                    }
+                }
+            }
+        }
+        self.corlib = Some(corlib);
+        Ok(corlib)
+    }
+
+    /// The method table of the single-dimensional arrays of `element`, classes deriving from `System.Array`.
+    pub fn array_of(&mut self, element: Rc<MethodTable>) -> Rc<MethodTable> {
+        if let Some(ty) = self.arrays.get(&element.id) {
+            return ty.clone();
+        }
+        let null = Token::new(TokenKind::Table(TableId::TypeRef), 0);
+        let parent = self.external("System.Array".to_string(), Vec::new(), false);
+        self.next_id += 1;
+        let ty = Rc::new(MethodTable {
+            id: self.next_id,
+            image: 0,
+            token: null,
+            name: format!("{}[]", element.name),
+            parent: Some(parent),
+            instantiation: Vec::new(),
+            element: Some(element.clone()),
+            is_value_type: false,
+            is_interface: false,
+            is_abstract: false,
+            primitive: None,
+            size: 0,
+            alignment: 1,
+            fields: Vec::new(),
+            references: Vec::new(),
+            vtable: Vec::new(),
+            slots: HashMap::new(),
+            interfaces: Vec::new(),
+            external_slots: HashMap::new(),
+            finalizer: None,
+        });
+        self.arrays.insert(element.id, ty.clone());
+        ty
+    }
+
+    /// Method table of a type outside the images, a class without fields unless it is a primitive value type.
+    fn external(&mut self, name: String, instantiation: Vec<Type>, is_value_type: bool) -> Rc<MethodTable> {
+        if let Some(ty) = self.externals.get(&name) {
+            return ty.clone();
+        }
+        let primitive = Kind::of_primitive(&name);
+        let size = primitive.and_then(Kind::primitive_size).unwrap_or(0);
+        self.next_id += 1;
+        let ty = Rc::new(MethodTable {
+            id: self.next_id,
+            image: 0,
+            token: Token::new(TokenKind::Table(TableId::TypeRef), 0),
+            is_value_type: is_value_type || primitive.is_some(),
+            is_interface: false,
+            is_abstract: false,
+            name,
+            parent: None,
+            instantiation,
+            element: None,
+            primitive,
+            size,
+            alignment: size.max(1),
+            fields: Vec::new(),
+            references: Vec::new(),
+            vtable: Vec::new(),
+            slots: HashMap::new(),
+            interfaces: Vec::new(),
+            external_slots: HashMap::new(),
+            finalizer: None,
+        });
+        self.externals.insert(ty.name.clone(), ty.clone());
+        ty
+    }
+
+    /// A value type defined in the images by the id of its method table.
+    pub fn value_type(&self, id: u32) -> Result<Rc<MethodTable>, Error> {
+        self.value_types.get(&id).cloned().ok_or_else(|| Error::TypeLoad {
+            token: Token::new(TokenKind::Table(TableId::TypeDef), 0),
+            reason: format!("No value type has id {}", id),
+        })
+    }
+
+    /// How values of `ty` are stored, `None` for types the interpreter can't represent yet. `ty` is read in
+    /// `image` with `generics`.
+    pub fn kind(&mut self, image: usize, ty: &Type, generics: &Generics) -> Result<Option<Kind>, Error> {
+        let ty = self.canonical(image, ty, generics)?;
+        self.canonical_kind(&ty)
+    }
+
+    /// How values of a canonical type are stored, see `kind`.
+    pub fn canonical_kind(&mut self, ty: &Type) -> Result<Option<Kind>, Error> {
+        Ok(Some(match ty {
+            Type::Boolean | Type::U1 => Kind::U1,
+            Type::I1 => Kind::I1,
+            Type::Char | Type::U2 => Kind::U2,
+            Type::I2 => Kind::I2,
+            Type::I4 | Type::U4 => Kind::I4,
+            Type::I8 | Type::U8 => Kind::I8,
+            Type::I | Type::U | Type::Ptr(..) | Type::FnPtr(_) => Kind::I,
+            Type::R4 => Kind::R4,
+            Type::R8 => Kind::R8,
+            Type::String | Type::Object | Type::Class(_) | Type::SzArray(..) | Type::Array(..) => Kind::Object,
+            Type::GenericInst {
+                is_value_type: false, ..
+            } => Kind::Object,
+            Type::ByRef(_) => Kind::Pointer,
+            Type::ValueType(_) | Type::GenericInst { .. } => match self.load_canonical(ty)? {
+                Some(ty) => return Ok(self.kind_of(&ty)),
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        }))
+    }
+
+    /// How values of the type `ty` describes are stored, `None` for value types outside the images.
+    pub fn kind_of(&self, ty: &MethodTable) -> Option<Kind> {
+        match ty.primitive {
+            Some(kind) => Some(kind),
+            None if !ty.is_value_type => Some(Kind::Object),
+            None if ty.definition().is_some() => Some(Kind::ValueType(ty.id)),
+            None => None,
+        }
+    }
+
+    /// Size and alignment of a value of `kind`.
+    pub fn size(&mut self, kind: Kind) -> Result<(u32, u32), Error> {
+        match kind {
+            Kind::ValueType(id) => {
+                let ty = self.value_type(id)?;
+                Ok((ty.size, ty.alignment))
+            }
+            kind => {
+                let size = kind.primitive_size().unwrap_or(POINTER_SIZE);
+                Ok((size, size))
+            }
+        }
+    }
+
+    /// The MethodDef a MemberRef of `image` refers to and the type declaring it, instantiated with the
+    /// type arguments of its parent read with `generics`. `None` for methods of types outside the images.
+    pub fn member_method(
+        &mut self,
+        image: usize,
+        rid: u32,
+        generics: &Generics,
+    ) -> Result<Option<(Rc<MethodTable>, RowId)>, Error> {
+        self.member(image, rid, generics, TableId::MethodDef)
+    }
+
+    /// The Field a MemberRef of `image` refers to and the type declaring it, see `member_method`.
+    pub fn member_field(
+        &mut self,
+        image: usize,
+        rid: u32,
+        generics: &Generics,
+    ) -> Result<Option<(Rc<MethodTable>, RowId)>, Error> {
+        self.member(image, rid, generics, TableId::Field)
+    }
+
+    /// The MethodDef or Field a MemberRef refers to if one of the images defines its parent, with the
+    /// parent. Members are matched by name and signature, the types of the signatures by the types they
+    /// refer to whichever image names them.
+    fn member(
+        &mut self,
+        image: usize,
+        rid: u32,
+        generics: &Generics,
+        table: TableId,
+    ) -> Result<Option<(Rc<MethodTable>, RowId)>, Error> {
+        let assembly = self.image(image);
+        let row = *assembly
+            .tables()
+            .member_refs
+            .get((rid as usize).wrapping_sub(1))
+            .ok_or(Error::TokenOutOfRange {
+                token: Token::new(TokenKind::Table(TableId::MemberRef), rid),
+                rows: assembly.tables().member_refs.len(),
+            })?;
+        let parent = match row.class {
+            MemberRefParent::TypeDef(rid) => TypeDefOrRef::TypeDef(rid),
+            MemberRefParent::TypeRef(rid) => TypeDefOrRef::TypeRef(rid),
+            MemberRefParent::TypeSpec(rid) => TypeDefOrRef::TypeSpec(rid),
+            _ => return Ok(None),
+        };
+        let owner = match self.load(image, parent, generics)? {
+            Some(owner) => owner,
+            None => return Ok(None),
+        };
+        let definition = match owner.definition() {
+            Some(definition) => definition,
+            None => return Ok(None),
+        };
+        let key = RowId::new(image, rid);
+        if let Some(&member) = self.members.get(&key) {
+            return Ok(Some((owner, member)));
+        }
+        let name = assembly.strings().get(row.name)?;
+        let signature = assembly.blobs().get(row.signature)?;
+        let declaring = self.image(definition.image);
+        let ty = declaring.type_definition(definition.rid);
+        let mut found = None;
+        if table == TableId::MethodDef {
+            let signature: MethodSignature = signature.pread_with(0, scroll::LE)?;
+            let signature = self.canonical_signature(image, &signature, &Generics::default())?;
+            for method in ty.iter().flat_map(|x| x.methods()) {
+                if method.name()? == name
+                    && self.canonical_signature(definition.image, &method.signature()?, &Generics::default())?
+                        == signature
+                {
+                    found = Some(method.rid);
+                    break;
+                }
+            }
+        } else {
+            let signature: FieldSignature = signature.pread_with(0, scroll::LE)?;
+            let signature = self.canonical_field(image, &signature)?;
+            for field in ty.iter().flat_map(|x| x.fields()) {
+                if field.name()? == name && self.canonical_field(definition.image, &field.signature()?)? == signature {
+                    found = Some(field.rid);
+                    break;
+                }
+            }
+        }
+        match found {
+            Some(member) => {
+                let member = RowId::new(definition.image, member);
+                self.members.insert(key, member);
+                Ok(Some((owner, member)))
+            }
+            None => Err(Error::UnresolvedMember {
+                parent: owner.name.clone(),
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Checks canonical type arguments against the constraints of the generic parameters of `owner` in
+    /// `image` (II.10.1.7), the constraints being read with `generics`. Types outside the images are taken
+    /// to satisfy their base type and interface constraints, the loader doesn't know what they derive from.
+    pub fn check_constraints(
+        &mut self,
+        image: usize,
+        owner: TypeOrMethodDef,
+        args: &[Type],
+        generics: &Generics,
+    ) -> Result<(), Error> {
+        let token = Token::new(TokenKind::Table(owner.table()), owner.row());
+        let violation = |reason: String| Error::TypeLoad { token, reason };
+        let assembly = self.image(image);
+        let params = assembly.generic_params_of(owner).collect::<Vec<_>>();
+        if params.len() != args.len() {
+            return Err(violation(format!(
+                "Takes {} type arguments, got {}",
+                params.len(),
+                args.len()
+            )));
+        }
+        for param in params {
+            let name = param.name()?;
+            let arg = args
+                .get(param.number() as usize)
+                .ok_or_else(|| violation(format!("Generic parameter {} is out of range", name)))?;
+            let ty = self.load_canonical(arg)?;
+            let is_value_type = match ty {
+                Some(ref ty) => ty.is_value_type,
+                None => false,
+            };
+            let arg_name = self.type_name(arg)?;
+            let unsatisfied = |what: &str| violation(format!("{} for {} {}", arg_name, name, what));
+            let flags = param.row.flags;
+            if flags & GENERIC_PARAM_ATTRIBUTES_REFERENCE_TYPE != 0 && is_value_type {
+                return Err(unsatisfied("is not a reference type"));
+            }
+            let is_nullable = ty.as_ref().map(|x| x.name.starts_with("System.Nullable`1<")) == Some(true);
+            if flags & GENERIC_PARAM_ATTRIBUTES_NOT_NULLABLE_VALUE_TYPE != 0 && (!is_value_type || is_nullable) {
+                return Err(unsatisfied("is not a non-nullable value type"));
+            }
+            if flags & GENERIC_PARAM_ATTRIBUTES_DEFAULT_CONSTRUCTOR != 0
+                && !is_value_type
+                && !self.has_default_constructor(ty.as_deref())?
+            {
+                return Err(unsatisfied("has no public parameterless constructor"));
+            }
+            let ty = match ty {
+                Some(ty) if ty.definition().is_some() => ty,
+                _ => continue,
+            };
+            for constraint in param.constraints() {
+                let constraint = match self.load(image, constraint, generics)? {
+                    Some(constraint) => constraint,
+                    None => continue,
+                };
+                if !ty.is_subclass_of(&constraint) {
+                    return Err(unsatisfied(&format!("doesn't derive from {}", constraint.name)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn has_default_constructor(&self, ty: Option<&MethodTable>) -> Result<bool, Error> {
+        let (ty, definition) = match ty {
+            Some(ty) if ty.is_abstract => return Ok(false),
+            Some(ty) => match ty.definition() {
+                Some(definition) => (ty, definition),
+                None => return Ok(true),
+            },
+            None => return Ok(false),
+        };
+        if let Some(definition) = self.images[ty.image].type_definition(definition.rid) {
+            for method in definition.methods() {
+                let public = method.row.flags & METHOD_ATTRIBUTES_MEMBER_ACCESS_MASK == METHOD_ATTRIBUTES_PUBLIC;
+                if public && method.name()? == ".ctor" {
+                    let signature = method.signature()?;
+                    if signature.has_this && signature.params.is_empty() {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Name of a canonical type, the way the names of instantiations spell their type arguments.
+    pub fn type_name(&self, ty: &Type) -> Result<String, Error> {
+        if let Some(name) = element_type_name(ty) {
+            return Ok(name.to_string());
+        }
+        let name = |index: &TypeDefOrRef| -> Result<String, Error> {
+            match self.named(index.row())? {
+                Named::Definition(definition) => match self.images[definition.image].type_definition(definition.rid) {
+                    Some(ty) => ty.full_name(),
+                    None => Ok(String::new()),
+                },
+                Named::External(name) => Ok(name),
+            }
+        };
+        let nested = |x: &Type| self.type_name(x);
+        Ok(match ty {
+            Type::Class(index) | Type::ValueType(index) => name(index)?,
+            Type::Ptr(_, x) => format!("{}*", nested(x)?),
+            Type::ByRef(x) => format!("{}&", nested(x)?),
+            Type::SzArray(_, x) => format!("{}[]", nested(x)?),
+            Type::Array(x, shape) => format!("{}[{}]", nested(x)?, ",".repeat(shape.rank.saturating_sub(1) as usize)),
+            Type::GenericInst { generic_type, args, .. } => {
+                let args = args.iter().map(nested).collect::<Result<Vec<_>, Error>>()?;
+                format!("{}<{}>", name(generic_type)?, args.join(","))
+            }
+            Type::Var(index) => format!("!{}", index),
+            Type::MVar(index) => format!("!!{}", index),
+            _ => "method".to_string(),
+        })
+    }
+
+    fn build(&mut self, definition: RowId, args: Vec<Type>) -> Result<MethodTable, Error> {
+        let image = definition.image;
+        let rid = definition.rid;
+        let assembly = self.image(image);
+        let token = Token::new(TokenKind::Table(TableId::TypeDef), rid);
+        let ty = assembly.type_definition(rid).ok_or(Error::TokenOutOfRange {
+            token,
+            rows: assembly.tables().type_defs.len(),
+        })?;
+        let type_load = |reason: String| Error::TypeLoad { token, reason };
+        self.next_id += 1;
+        let id = self.next_id;
+        let generics = Generics::new(args, Vec::new());
+        let mut name = ty.full_name()?;
+        if !generics.types.is_empty() {
+            self.check_constraints(image, TypeOrMethodDef::TypeDef(rid), &generics.types, &generics)?;
+            let mut names = Vec::with_capacity(generics.types.len());
+            for arg in &generics.types {
+                names.push(self.type_name(arg)?);
+            }
+            name = format!("{}<{}>", name, names.join(","));
+        }
+        let parent = self.load(image, ty.row.extends, &generics)?;
+        let parent_name = parent.as_ref().map_or("", |x| x.name.as_str());
+        let is_enum = parent_name == "System.Enum";
+        let is_value_type = (is_enum || parent_name == "System.ValueType") && name != "System.Enum";
+        let explicit = ty.row.flags & TYPE_ATTRIBUTES_LAYOUT_MASK == TYPE_ATTRIBUTES_EXPLICIT_LAYOUT;
+        let class_layout = assembly.tables().class_layouts.iter().find(|x| x.parent == rid);
+        let packing = match class_layout.map_or(0, |x| u32::from(x.packing_size)) {
+            0 => DEFAULT_PACKING,
+            packing if packing.is_power_of_two() && packing <= 128 => packing,
+            packing => {
+                return Err(type_load(format!(
+                    "Packing size {} is not a power of 2 up to 128",
+                    packing
+                )))
+            }
+        };
+
+        // Value types can't be derived from, their fields start at 0 whatever the base type
+        let (start, mut alignment, mut fields, mut references) = match parent {
+            Some(ref parent) if !is_value_type => (
+                parent.size,
+                parent.alignment,
+                parent.fields.clone(),
+                parent.references.clone(),
+            ),
+            _ => (0, 1, Vec::new(), Vec::new()),
+        };
+        let inherited = fields.len();
+        let mut end = start;
+        for field in ty.fields().filter(|x| x.row.flags & FIELD_ATTRIBUTES_STATIC == 0) {
+            let name = field.name()?;
+            let kind = self
+                .kind(image, &field.signature()?.ty, &generics)?
+                .filter(|&x| x != Kind::Pointer)
+                .ok_or_else(|| type_load(format!("Field {} has a type that can't be stored", name)))?;
+            let (size, field_alignment) = self.size(kind)?;
+            let field_alignment = field_alignment.min(packing);
+            let offset = if explicit {
+                let offset = assembly
+                    .tables()
+                    .field_layouts
+                    .iter()
+                    .find(|x| x.field == field.rid)
+                    .ok_or_else(|| type_load(format!("Field {} has no FieldLayout", name)))?
+                    .offset;
+                start
+                    .checked_add(offset)
+                    .ok_or_else(|| type_load(format!("Field {} is out of range", name)))?
+            } else {
+                align(end, field_alignment).ok_or_else(|| type_load(format!("Field {} is out of range", name)))?
+            };
+            end = end.max(
+                offset
+                    .checked_add(size)
+                    .ok_or_else(|| type_load(format!("Field {} is out of range", name)))?,
+            );
+            alignment = alignment.max(field_alignment);
+            match kind {
+                Kind::Object => references.push(offset),
+                Kind::ValueType(id) => {
+                    let nested = self.value_type(id)?;
+                    references.extend(nested.references.iter().map(|x| offset + x));
+                }
+                _ => {}
+            }
+            fields.push(FieldSlot {
+                field: RowId::new(image, field.rid),
+                offset,
+                kind,
+            });
+        }
+        if explicit {
+            check_overlaps(self, &fields[inherited..], &references).map_err(type_load)?;
+        }
+
+        let primitive = if is_enum {
+            match fields.as_slice() {
+                [field] if field.kind.primitive_size().is_some() && field.kind != Kind::Object => Some(field.kind),
+                _ => {
+                    return Err(type_load(
+                        "Enum doesn't have a single primitive instance field".to_string(),
+                    ))
+                }
+            }
+        } else {
+            Kind::of_primitive(&name).filter(|_| is_value_type)
+        };
+        let mut size = if explicit {
+            end
+        } else {
+            align(end, alignment).ok_or_else(|| type_load(format!("Instance size {} is too large", end)))?
+        };
+        size = size.max(class_layout.map_or(0, |x| x.class_size));
+        if u64::from(size) > MAX_ARRAY_SIZE {
+            return Err(type_load(format!("Instance size {} is too large", size)));
+        }
+        if is_value_type && size == 0 {
+            size = 1;
+        }
+
+        let is_interface = ty.row.flags & TYPE_ATTRIBUTES_INTERFACE != 0;
+        let is_abstract = ty.row.flags & TYPE_ATTRIBUTES_ABSTRACT != 0;
+        let dispatch = self.dispatch(
+            image,
+            &ty,
+            &generics,
+            parent.as_deref(),
+            is_value_type,
+            is_interface || is_abstract,
+        )?;
+
+        Ok(MethodTable {
+            id,
+            image,
+            token,
+            name,
+            parent,
+            instantiation: generics.types,
+            element: None,
+            is_value_type,
+            is_interface,
+            is_abstract,
+            primitive,
+            size,
+            alignment,
+            fields,
+            references,
+            vtable: dispatch.vtable,
+            slots: dispatch.slots,
+            interfaces: dispatch.interfaces,
+            external_slots: dispatch.external_slots,
+            finalizer: dispatch.finalizer,
+        })
+    }
+}
+
+/// Object references of an explicit layout must be aligned and may only overlap each other.
+fn check_overlaps(loader: &mut TypeLoader<'_>, fields: &[FieldSlot], references: &[u32]) -> Result<(), String> {
+    for &reference in references {
+        if reference & (POINTER_SIZE - 1) != 0 {
+            return Err(format!("Object reference at offset {} is misaligned", reference));
+        }
+    }
+    for field in fields {
+        let (size, _) = loader.size(field.kind).map_err(|x| x.to_string())?;
+        let range = field.offset..field.offset + size;
+        for &reference in references {
+            let overlaps = reference < range.end && range.start < reference + POINTER_SIZE;
+            let same = field.kind == Kind::Object && field.offset == reference;
+            let inside = match field.kind {
+                Kind::ValueType(id) => loader
+                    .value_type(id)
+                    .map_err(|x| x.to_string())?
+                    .references
+                    .iter()
+                    .any(|x| field.offset + x == reference),
+                _ => false,
+            };
+            if overlaps && !same && !inside {
+                return Err(format!(
+                    "Object reference at offset {} overlaps another field",
+                    reference
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `offset` rounded up to a multiple of `alignment`, `None` past `u32::MAX`.
+fn align(offset: u32, alignment: u32) -> Option<u32> {
+    Some(offset.checked_add(alignment - 1)? & !(alignment - 1))
+}
@@ -0,0 +1,568 @@
+//! Signature blobs (ECMA-335 II.23.2) decoded into a typed tree.
+
+use crate::coded_index::TypeDefOrRef;
+use crate::error::Error;
+use crate::heaps::{read_compressed_i32, read_compressed_u32};
+use goblin::container::Endian;
+use scroll::ctx::TryFromCtx;
+use scroll::{self, Pread};
+
+pub const ELEMENT_TYPE_END: u8 = 0x00;
+pub const ELEMENT_TYPE_VOID: u8 = 0x01;
+pub const ELEMENT_TYPE_BOOLEAN: u8 = 0x02;
+pub const ELEMENT_TYPE_CHAR: u8 = 0x03;
+pub const ELEMENT_TYPE_I1: u8 = 0x04;
+pub const ELEMENT_TYPE_U1: u8 = 0x05;
+pub const ELEMENT_TYPE_I2: u8 = 0x06;
+pub const ELEMENT_TYPE_U2: u8 = 0x07;
+pub const ELEMENT_TYPE_I4: u8 = 0x08;
+pub const ELEMENT_TYPE_U4: u8 = 0x09;
+pub const ELEMENT_TYPE_I8: u8 = 0x0a;
+pub const ELEMENT_TYPE_U8: u8 = 0x0b;
+pub const ELEMENT_TYPE_R4: u8 = 0x0c;
+pub const ELEMENT_TYPE_R8: u8 = 0x0d;
+pub const ELEMENT_TYPE_STRING: u8 = 0x0e;
+pub const ELEMENT_TYPE_PTR: u8 = 0x0f;
+pub const ELEMENT_TYPE_BYREF: u8 = 0x10;
+pub const ELEMENT_TYPE_VALUETYPE: u8 = 0x11;
+pub const ELEMENT_TYPE_CLASS: u8 = 0x12;
+pub const ELEMENT_TYPE_VAR: u8 = 0x13;
+pub const ELEMENT_TYPE_ARRAY: u8 = 0x14;
+pub const ELEMENT_TYPE_GENERICINST: u8 = 0x15;
+pub const ELEMENT_TYPE_TYPEDBYREF: u8 = 0x16;
+pub const ELEMENT_TYPE_I: u8 = 0x18;
+pub const ELEMENT_TYPE_U: u8 = 0x19;
+pub const ELEMENT_TYPE_FNPTR: u8 = 0x1b;
+pub const ELEMENT_TYPE_OBJECT: u8 = 0x1c;
+pub const ELEMENT_TYPE_SZARRAY: u8 = 0x1d;
+pub const ELEMENT_TYPE_MVAR: u8 = 0x1e;
+pub const ELEMENT_TYPE_CMOD_REQD: u8 = 0x1f;
+pub const ELEMENT_TYPE_CMOD_OPT: u8 = 0x20;
+pub const ELEMENT_TYPE_SENTINEL: u8 = 0x41;
+pub const ELEMENT_TYPE_PINNED: u8 = 0x45;
+
+pub const SIG_HASTHIS: u8 = 0x20;
+pub const SIG_EXPLICITTHIS: u8 = 0x40;
+pub const SIG_GENERIC: u8 = 0x10;
+pub const SIG_KIND_MASK: u8 = 0x0f;
+pub const SIG_FIELD: u8 = 0x06;
+pub const SIG_LOCAL_SIG: u8 = 0x07;
+pub const SIG_PROPERTY: u8 = 0x08;
+pub const SIG_GENERICINST: u8 = 0x0a;
+
+/// Deeper nesting than this is rejected rather than risking the stack on hostile blobs.
+const MAX_DEPTH: u32 = 64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CallingConvention {
+    Default,
+    C,
+    StdCall,
+    ThisCall,
+    FastCall,
+    VarArg,
+    Unmanaged,
+    NativeVarArg,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomMod {
+    pub required: bool,
+    pub modifier: TypeDefOrRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArrayShape {
+    pub rank: u32,
+    pub sizes: Vec<u32>,
+    pub lower_bounds: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Void,
+    Boolean,
+    Char,
+    I1,
+    U1,
+    I2,
+    U2,
+    I4,
+    U4,
+    I8,
+    U8,
+    R4,
+    R8,
+    String,
+    Object,
+    I,
+    U,
+    TypedByRef,
+    ValueType(TypeDefOrRef),
+    Class(TypeDefOrRef),
+    Ptr(Vec<CustomMod>, Box<Type>),
+    ByRef(Box<Type>),
+    FnPtr(Box<MethodSignature>),
+    SzArray(Vec<CustomMod>, Box<Type>),
+    Array(Box<Type>, ArrayShape),
+    GenericInst {
+        is_value_type: bool,
+        generic_type: TypeDefOrRef,
+        args: Vec<Type>,
+    },
+    /// Generic parameter of the enclosing type
+    Var(u32),
+    /// Generic parameter of the enclosing method
+    MVar(u32),
+}
+
+/// A parameter or return type: `CustomMod* [BYREF CustomMod*] Type`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Param {
+    pub custom_mods: Vec<CustomMod>,
+    pub by_ref: bool,
+    pub ty: Type,
+}
+
+/// MethodDefSig, MethodRefSig and StandAloneMethodSig.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSignature {
+    pub has_this: bool,
+    pub explicit_this: bool,
+    pub calling_convention: CallingConvention,
+    pub generic_param_count: u32,
+    pub return_type: Param,
+    pub params: Vec<Param>,
+    /// Arguments after the sentinel of a vararg call site
+    pub vararg_params: Vec<Param>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSignature {
+    pub custom_mods: Vec<CustomMod>,
+    pub ty: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertySignature {
+    pub has_this: bool,
+    pub custom_mods: Vec<CustomMod>,
+    pub ty: Type,
+    pub params: Vec<Param>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVar {
+    pub custom_mods: Vec<CustomMod>,
+    pub pinned: bool,
+    pub by_ref: bool,
+    pub ty: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVarSignature {
+    pub locals: Vec<LocalVar>,
+}
+
+/// Generic arguments of a MethodSpec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodSpecSignature {
+    pub args: Vec<Type>,
+}
+
+fn invalid(what: &str, value: u8, offset: usize) -> Error {
+    Error::BadSignature {
+        offset,
+        reason: format!("Invalid {} 0x{:02x}", what, value),
+    }
+}
+
+fn peek(src: &[u8], offset: usize) -> Result<u8, Error> {
+    Ok(src.pread(offset)?)
+}
+
+fn read_type_def_or_ref(src: &[u8], offset: &mut usize) -> Result<TypeDefOrRef, Error> {
+    TypeDefOrRef::decode(read_compressed_u32(src, offset)?)
+}
+
+fn read_custom_mods(src: &[u8], offset: &mut usize) -> Result<Vec<CustomMod>, Error> {
+    let mut custom_mods = Vec::new();
+    loop {
+        let required = match peek(src, *offset)? {
+            ELEMENT_TYPE_CMOD_REQD => true,
+            ELEMENT_TYPE_CMOD_OPT => false,
+            _ => return Ok(custom_mods),
+        };
+        *offset += 1;
+        custom_mods.push(CustomMod {
+            required,
+            modifier: read_type_def_or_ref(src, offset)?,
+        });
+    }
+}
+
+fn read_array_shape(src: &[u8], offset: &mut usize) -> Result<ArrayShape, Error> {
+    let rank = read_compressed_u32(src, offset)?;
+    let num_sizes = read_compressed_u32(src, offset)?;
+    let mut sizes = Vec::new();
+    for _ in 0..num_sizes {
+        sizes.push(read_compressed_u32(src, offset)?);
+    }
+    let num_lower_bounds = read_compressed_u32(src, offset)?;
+    let mut lower_bounds = Vec::new();
+    for _ in 0..num_lower_bounds {
+        lower_bounds.push(read_compressed_i32(src, offset)?);
+    }
+    Ok(ArrayShape {
+        rank,
+        sizes,
+        lower_bounds,
+    })
+}
+
+fn read_type(src: &[u8], offset: &mut usize, depth: u32) -> Result<Type, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::BadSignature {
+            offset: *offset,
+            reason: "Signature is nested too deeply".to_string(),
+        });
+    }
+    let start = *offset;
+    let element_type: u8 = src.gread(offset)?;
+    Ok(match element_type {
+        ELEMENT_TYPE_VOID => Type::Void,
+        ELEMENT_TYPE_BOOLEAN => Type::Boolean,
+        ELEMENT_TYPE_CHAR => Type::Char,
+        ELEMENT_TYPE_I1 => Type::I1,
+        ELEMENT_TYPE_U1 => Type::U1,
+        ELEMENT_TYPE_I2 => Type::I2,
+        ELEMENT_TYPE_U2 => Type::U2,
+        ELEMENT_TYPE_I4 => Type::I4,
+        ELEMENT_TYPE_U4 => Type::U4,
+        ELEMENT_TYPE_I8 => Type::I8,
+        ELEMENT_TYPE_U8 => Type::U8,
+        ELEMENT_TYPE_R4 => Type::R4,
+        ELEMENT_TYPE_R8 => Type::R8,
+        ELEMENT_TYPE_STRING => Type::String,
+        ELEMENT_TYPE_OBJECT => Type::Object,
+        ELEMENT_TYPE_I => Type::I,
+        ELEMENT_TYPE_U => Type::U,
+        ELEMENT_TYPE_TYPEDBYREF => Type::TypedByRef,
+        ELEMENT_TYPE_VALUETYPE => Type::ValueType(read_type_def_or_ref(src, offset)?),
+        ELEMENT_TYPE_CLASS => Type::Class(read_type_def_or_ref(src, offset)?),
+        ELEMENT_TYPE_PTR => {
+            let custom_mods = read_custom_mods(src, offset)?;
+            Type::Ptr(custom_mods, Box::new(read_type(src, offset, depth + 1)?))
+        }
+        ELEMENT_TYPE_BYREF => Type::ByRef(Box::new(read_type(src, offset, depth + 1)?)),
+        ELEMENT_TYPE_FNPTR => Type::FnPtr(Box::new(read_method_signature(src, offset, depth + 1)?)),
+        ELEMENT_TYPE_SZARRAY => {
+            let custom_mods = read_custom_mods(src, offset)?;
+            Type::SzArray(custom_mods, Box::new(read_type(src, offset, depth + 1)?))
+        }
+        ELEMENT_TYPE_ARRAY => {
+            let element = read_type(src, offset, depth + 1)?;
+            Type::Array(Box::new(element), read_array_shape(src, offset)?)
+        }
+        ELEMENT_TYPE_GENERICINST => {
+            let is_value_type = match src.gread::<u8>(offset)? {
+                ELEMENT_TYPE_CLASS => false,
+                ELEMENT_TYPE_VALUETYPE => true,
+                other => return Err(invalid("generic instantiation kind", other, *offset - 1)),
+            };
+            let generic_type = read_type_def_or_ref(src, offset)?;
+            let count = read_compressed_u32(src, offset)?;
+            let mut args = Vec::new();
+            for _ in 0..count {
+                args.push(read_type(src, offset, depth + 1)?);
+            }
+            Type::GenericInst {
+                is_value_type,
+                generic_type,
+                args,
+            }
+        }
+        ELEMENT_TYPE_VAR => Type::Var(read_compressed_u32(src, offset)?),
+        ELEMENT_TYPE_MVAR => Type::MVar(read_compressed_u32(src, offset)?),
+        other => return Err(invalid("element type", other, start)),
+    })
+}
+
+fn read_param(src: &[u8], offset: &mut usize, depth: u32) -> Result<Param, Error> {
+    let mut custom_mods = read_custom_mods(src, offset)?;
+    let by_ref = peek(src, *offset)? == ELEMENT_TYPE_BYREF;
+    if by_ref {
+        *offset += 1;
+        // `ref modreq(...) T` puts the modifiers of the referenced type after BYREF (II.23.2.10)
+        custom_mods.extend(read_custom_mods(src, offset)?);
+    }
+    Ok(Param {
+        custom_mods,
+        by_ref,
+        ty: read_type(src, offset, depth + 1)?,
+    })
+}
+
+fn read_method_signature(src: &[u8], offset: &mut usize, depth: u32) -> Result<MethodSignature, Error> {
+    let flags: u8 = src.gread(offset)?;
+    let calling_convention = match flags & SIG_KIND_MASK {
+        0x00 => CallingConvention::Default,
+        0x01 => CallingConvention::C,
+        0x02 => CallingConvention::StdCall,
+        0x03 => CallingConvention::ThisCall,
+        0x04 => CallingConvention::FastCall,
+        0x05 => CallingConvention::VarArg,
+        0x09 => CallingConvention::Unmanaged,
+        0x0b => CallingConvention::NativeVarArg,
+        _ => return Err(invalid("method calling convention", flags, *offset - 1)),
+    };
+    let generic_param_count = if flags & SIG_GENERIC != 0 {
+        read_compressed_u32(src, offset)?
+    } else {
+        0
+    };
+    let param_count = read_compressed_u32(src, offset)?;
+    let return_type = read_param(src, offset, depth)?;
+    let mut params = Vec::new();
+    let mut vararg_params = Vec::new();
+    let mut after_sentinel = false;
+    for _ in 0..param_count {
+        if peek(src, *offset)? == ELEMENT_TYPE_SENTINEL {
+            *offset += 1;
+            after_sentinel = true;
+        }
+        let param = read_param(src, offset, depth)?;
+        if after_sentinel {
+            vararg_params.push(param);
+        } else {
+            params.push(param);
+        }
+    }
+    Ok(MethodSignature {
+        has_this: flags & SIG_HASTHIS != 0,
+        explicit_this: flags & SIG_EXPLICITTHIS != 0,
+        calling_convention,
+        generic_param_count,
+        return_type,
+        params,
+        vararg_params,
+    })
+}
+
+impl<'a> TryFromCtx<'a, Endian> for MethodSignature {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let signature = read_method_signature(src, offset, 0)?;
+        Ok((signature, *offset))
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for FieldSignature {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags: u8 = src.gread(offset)?;
+        if flags & SIG_KIND_MASK != SIG_FIELD {
+            return Err(invalid("field signature", flags, 0));
+        }
+        let custom_mods = read_custom_mods(src, offset)?;
+        let ty = read_type(src, offset, 0)?;
+        Ok((Self { custom_mods, ty }, *offset))
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for PropertySignature {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags: u8 = src.gread(offset)?;
+        if flags & SIG_KIND_MASK != SIG_PROPERTY {
+            return Err(invalid("property signature", flags, 0));
+        }
+        let param_count = read_compressed_u32(src, offset)?;
+        let custom_mods = read_custom_mods(src, offset)?;
+        let ty = read_type(src, offset, 0)?;
+        let mut params = Vec::new();
+        for _ in 0..param_count {
+            params.push(read_param(src, offset, 0)?);
+        }
+        Ok((
+            Self {
+                has_this: flags & SIG_HASTHIS != 0,
+                custom_mods,
+                ty,
+                params,
+            },
+            *offset,
+        ))
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for LocalVarSignature {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags: u8 = src.gread(offset)?;
+        if flags != SIG_LOCAL_SIG {
+            return Err(invalid("local variable signature", flags, 0));
+        }
+        let count = read_compressed_u32(src, offset)?;
+        let mut locals = Vec::new();
+        for _ in 0..count {
+            // Custom modifiers and the pinned constraint may come in any order
+            let mut custom_mods = Vec::new();
+            let mut pinned = false;
+            loop {
+                custom_mods.extend(read_custom_mods(src, offset)?);
+                if peek(src, *offset)? != ELEMENT_TYPE_PINNED {
+                    break;
+                }
+                *offset += 1;
+                pinned = true;
+            }
+            let by_ref = peek(src, *offset)? == ELEMENT_TYPE_BYREF;
+            if by_ref {
+                *offset += 1;
+            }
+            locals.push(LocalVar {
+                custom_mods,
+                pinned,
+                by_ref,
+                ty: read_type(src, offset, 0)?,
+            });
+        }
+        Ok((Self { locals }, *offset))
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for MethodSpecSignature {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags: u8 = src.gread(offset)?;
+        if flags != SIG_GENERICINST {
+            return Err(invalid("method instantiation signature", flags, 0));
+        }
+        let count = read_compressed_u32(src, offset)?;
+        let mut args = Vec::new();
+        for _ in 0..count {
+            args.push(read_type(src, offset, 0)?);
+        }
+        Ok((Self { args }, *offset))
+    }
+}
+
+/// TypeSpec blobs are a single type.
+impl<'a> TryFromCtx<'a, Endian> for Type {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let ty = read_type(src, offset, 0)?;
+        Ok((ty, *offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method(src: &[u8]) -> MethodSignature {
+        src.pread_with(0, scroll::LE).unwrap()
+    }
+
+    fn is_invalid(src: &[u8]) -> bool {
+        src.pread_with::<MethodSignature>(0, scroll::LE).is_err()
+    }
+
+    #[test]
+    fn custom_mods_after_byref() {
+        // instance void (int32& modreq([1]TypeRef) x)
+        let signature = method(&[0x20, 0x01, 0x01, 0x10, 0x1f, 0x05, 0x08]);
+        assert_eq!(
+            signature.params,
+            vec![Param {
+                custom_mods: vec![CustomMod {
+                    required: true,
+                    modifier: TypeDefOrRef::TypeRef(1),
+                }],
+                by_ref: true,
+                ty: Type::I4,
+            }]
+        );
+    }
+
+    #[test]
+    fn method_signatures() {
+        // static !!0 M<T>(int32, string[], class [2]TypeRef<!!0>)
+        let signature = method(&[
+            0x10, 0x01, 0x03, 0x1e, 0x00, 0x08, 0x1d, 0x0e, 0x15, 0x12, 0x09, 0x01, 0x1e, 0x00,
+        ]);
+        assert!(!signature.has_this);
+        assert_eq!(signature.calling_convention, CallingConvention::Default);
+        assert_eq!(signature.generic_param_count, 1);
+        assert_eq!(signature.return_type.ty, Type::MVar(0));
+        let params = signature.params.into_iter().map(|x| x.ty).collect::<Vec<_>>();
+        assert_eq!(
+            params,
+            vec![
+                Type::I4,
+                Type::SzArray(Vec::new(), Box::new(Type::String)),
+                Type::GenericInst {
+                    is_value_type: false,
+                    generic_type: TypeDefOrRef::TypeRef(2),
+                    args: vec![Type::MVar(0)],
+                },
+            ]
+        );
+
+        // vararg void (int32, ..., int64)
+        let signature = method(&[0x05, 0x02, 0x01, 0x08, 0x41, 0x0a]);
+        assert_eq!(signature.calling_convention, CallingConvention::VarArg);
+        assert_eq!(signature.params.len(), 1);
+        assert_eq!(signature.vararg_params[0].ty, Type::I8);
+
+        assert!(is_invalid(&[0x06, 0x00, 0x01]));
+        assert!(is_invalid(&[0x00, 0x01, 0x01, 0x50]));
+    }
+
+    #[test]
+    fn field_local_and_method_spec_signatures() {
+        // modopt([1]TypeRef) valuetype [3]TypeDef
+        let field: FieldSignature = [0x06, 0x20, 0x05, 0x11, 0x0c][..].pread_with(0, scroll::LE).unwrap();
+        assert_eq!(
+            field,
+            FieldSignature {
+                custom_mods: vec![CustomMod {
+                    required: false,
+                    modifier: TypeDefOrRef::TypeRef(1),
+                }],
+                ty: Type::ValueType(TypeDefOrRef::TypeDef(3)),
+            }
+        );
+        assert!([0x07, 0x08][..].pread_with::<FieldSignature>(0, scroll::LE).is_err());
+
+        // uint8& pinned, int32, float64[4...,-1...]
+        let src = [
+            0x07, 0x03, 0x45, 0x10, 0x05, 0x08, 0x14, 0x0d, 0x02, 0x01, 0x04, 0x02, 0x00, 0x7f,
+        ];
+        let locals: LocalVarSignature = src[..].pread_with(0, scroll::LE).unwrap();
+        let first = &locals.locals[0];
+        assert_eq!((first.pinned, first.by_ref, &first.ty), (true, true, &Type::U1));
+        assert_eq!(locals.locals[1].ty, Type::I4);
+        assert_eq!(
+            locals.locals[2].ty,
+            Type::Array(
+                Box::new(Type::R8),
+                ArrayShape {
+                    rank: 2,
+                    sizes: vec![4],
+                    lower_bounds: vec![0, -1],
+                }
+            )
+        );
+
+        let spec: MethodSpecSignature = [0x0a, 0x02, 0x08, 0x1c][..].pread_with(0, scroll::LE).unwrap();
+        assert_eq!(spec.args, vec![Type::I4, Type::Object]);
+    }
+}
@@ -0,0 +1,1966 @@
+use crate::coded_index::*;
+use crate::error::Error;
+use goblin::container::Endian;
+use scroll::ctx::TryFromCtx;
+use scroll::{self, Pread};
+use serde::Serialize;
+use std::ops::Range;
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct TildaStream {
+    _reserved: u32,
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub heap_sizes: u8,
+    _reserved2: u8,
+    pub valid: u64,
+    pub sorted: u64,
+    pub rows: Vec<(u32, u32)>,
+    pub modules: Vec<Module>,
+    pub type_refs: Vec<TypeRef>,
+    pub type_defs: Vec<TypeDef>,
+    pub field_ptrs: Vec<FieldPtr>,
+    pub fields: Vec<Field>,
+    pub method_ptrs: Vec<MethodPtr>,
+    pub methods: Vec<MethodDef>,
+    pub param_ptrs: Vec<ParamPtr>,
+    pub params: Vec<Param>,
+    pub interface_impls: Vec<InterfaceImpl>,
+    pub member_refs: Vec<MemberRef>,
+    pub constants: Vec<Constant>,
+    pub custom_attributes: Vec<CustomAttribute>,
+    pub field_marshals: Vec<FieldMarshal>,
+    pub decl_securities: Vec<DeclSecurity>,
+    pub class_layouts: Vec<ClassLayout>,
+    pub field_layouts: Vec<FieldLayout>,
+    pub stand_alone_sigs: Vec<StandAloneSig>,
+    pub event_maps: Vec<EventMap>,
+    pub event_ptrs: Vec<EventPtr>,
+    pub events: Vec<Event>,
+    pub property_maps: Vec<PropertyMap>,
+    pub property_ptrs: Vec<PropertyPtr>,
+    pub properties: Vec<Property>,
+    pub method_semantics: Vec<MethodSemantics>,
+    pub method_impls: Vec<MethodImpl>,
+    pub module_refs: Vec<ModuleRef>,
+    pub type_specs: Vec<TypeSpec>,
+    pub impl_maps: Vec<ImplMap>,
+    pub field_rvas: Vec<FieldRva>,
+    pub enc_logs: Vec<EncLog>,
+    pub enc_maps: Vec<EncMap>,
+    pub assemblies: Vec<Assembly>,
+    pub assembly_processors: Vec<AssemblyProcessor>,
+    pub assembly_oses: Vec<AssemblyOs>,
+    pub assembly_refs: Vec<AssemblyRef>,
+    pub assembly_ref_processors: Vec<AssemblyRefProcessor>,
+    pub assembly_ref_oses: Vec<AssemblyRefOs>,
+    pub files: Vec<File>,
+    pub exported_types: Vec<ExportedType>,
+    pub manifest_resources: Vec<ManifestResource>,
+    pub nested_classes: Vec<NestedClass>,
+    pub generic_params: Vec<GenericParam>,
+    pub method_specs: Vec<MethodSpec>,
+    pub generic_param_constraints: Vec<GenericParamConstraint>,
+}
+
+fn read_rows<'a, T>(
+    src: &'a [u8],
+    offset: &mut usize,
+    table: TableId,
+    count: u32,
+    ctx: &TableContext,
+    rows: &mut Vec<T>,
+) -> Result<(), Error>
+where
+    for<'b> T: TryFromCtx<'a, &'b TableContext, Error = Error>,
+{
+    // The count comes from the image, every row takes at least two bytes of what is left
+    rows.reserve((count as usize).min(src.len().saturating_sub(*offset) / 2));
+    for row in 1..=count {
+        let start = *offset;
+        rows.push(src.gread_with(offset, ctx).map_err(|error| match error {
+            Error::Read(_) => Error::TruncatedTable {
+                table,
+                row,
+                offset: start,
+            },
+            error => error,
+        })?);
+    }
+    Ok(())
+}
+
+impl<'a> TryFromCtx<'a, Endian> for TildaStream {
+    type Error = Error;
+    // and the lifetime annotation on `&'a [u8]` here
+    fn try_from_ctx(src: &'a [u8], endian: Endian) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let _reserved = src.gread_with(offset, endian)?;
+        let major_version = src.gread_with(offset, endian)?;
+        let minor_version = src.gread_with(offset, endian)?;
+        let heap_sizes = src.gread_with(offset, endian)?;
+        let _reserved2 = src.gread_with(offset, endian)?;
+        let valid = src.gread_with(offset, endian)?;
+        let sorted = src.gread_with(offset, endian)?;
+        let mut rows = Vec::new();
+
+        let mut j = 1;
+        for i in 0..64_u32 {
+            if valid & j == j {
+                let count = src.gread_with(offset, endian)?;
+                rows.push((i, count));
+            }
+            j <<= 1;
+        }
+        // Set in uncompressed (#-) streams written by edit-and-continue
+        if heap_sizes & 0x40 != 0 {
+            let _extra_data: u32 = src.gread_with(offset, endian)?;
+        }
+
+        let ctx = TableContext::new(heap_sizes, &rows);
+        let mut stream = Self {
+            _reserved,
+            major_version,
+            minor_version,
+            heap_sizes,
+            _reserved2,
+            valid,
+            sorted,
+            rows: Vec::new(),
+            modules: Vec::new(),
+            type_refs: Vec::new(),
+            type_defs: Vec::new(),
+            field_ptrs: Vec::new(),
+            fields: Vec::new(),
+            method_ptrs: Vec::new(),
+            methods: Vec::new(),
+            param_ptrs: Vec::new(),
+            params: Vec::new(),
+            interface_impls: Vec::new(),
+            member_refs: Vec::new(),
+            constants: Vec::new(),
+            custom_attributes: Vec::new(),
+            field_marshals: Vec::new(),
+            decl_securities: Vec::new(),
+            class_layouts: Vec::new(),
+            field_layouts: Vec::new(),
+            stand_alone_sigs: Vec::new(),
+            event_maps: Vec::new(),
+            event_ptrs: Vec::new(),
+            events: Vec::new(),
+            property_maps: Vec::new(),
+            property_ptrs: Vec::new(),
+            properties: Vec::new(),
+            method_semantics: Vec::new(),
+            method_impls: Vec::new(),
+            module_refs: Vec::new(),
+            type_specs: Vec::new(),
+            impl_maps: Vec::new(),
+            field_rvas: Vec::new(),
+            enc_logs: Vec::new(),
+            enc_maps: Vec::new(),
+            assemblies: Vec::new(),
+            assembly_processors: Vec::new(),
+            assembly_oses: Vec::new(),
+            assembly_refs: Vec::new(),
+            assembly_ref_processors: Vec::new(),
+            assembly_ref_oses: Vec::new(),
+            files: Vec::new(),
+            exported_types: Vec::new(),
+            manifest_resources: Vec::new(),
+            nested_classes: Vec::new(),
+            generic_params: Vec::new(),
+            method_specs: Vec::new(),
+            generic_param_constraints: Vec::new(),
+        };
+
+        // Tables are stored back to back in ascending table number order
+        for (i, count) in rows.iter().cloned() {
+            let table = TableId::from_u8(i as u8).ok_or(Error::UnknownTable(i as u8))?;
+            match table {
+                TableId::Module => read_rows(src, offset, table, count, &ctx, &mut stream.modules)?,
+                TableId::TypeRef => read_rows(src, offset, table, count, &ctx, &mut stream.type_refs)?,
+                TableId::TypeDef => read_rows(src, offset, table, count, &ctx, &mut stream.type_defs)?,
+                TableId::FieldPtr => read_rows(src, offset, table, count, &ctx, &mut stream.field_ptrs)?,
+                TableId::Field => read_rows(src, offset, table, count, &ctx, &mut stream.fields)?,
+                TableId::MethodPtr => read_rows(src, offset, table, count, &ctx, &mut stream.method_ptrs)?,
+                TableId::MethodDef => read_rows(src, offset, table, count, &ctx, &mut stream.methods)?,
+                TableId::ParamPtr => read_rows(src, offset, table, count, &ctx, &mut stream.param_ptrs)?,
+                TableId::Param => read_rows(src, offset, table, count, &ctx, &mut stream.params)?,
+                TableId::InterfaceImpl => read_rows(src, offset, table, count, &ctx, &mut stream.interface_impls)?,
+                TableId::MemberRef => read_rows(src, offset, table, count, &ctx, &mut stream.member_refs)?,
+                TableId::Constant => read_rows(src, offset, table, count, &ctx, &mut stream.constants)?,
+                TableId::CustomAttribute => read_rows(src, offset, table, count, &ctx, &mut stream.custom_attributes)?,
+                TableId::FieldMarshal => read_rows(src, offset, table, count, &ctx, &mut stream.field_marshals)?,
+                TableId::DeclSecurity => read_rows(src, offset, table, count, &ctx, &mut stream.decl_securities)?,
+                TableId::ClassLayout => read_rows(src, offset, table, count, &ctx, &mut stream.class_layouts)?,
+                TableId::FieldLayout => read_rows(src, offset, table, count, &ctx, &mut stream.field_layouts)?,
+                TableId::StandAloneSig => read_rows(src, offset, table, count, &ctx, &mut stream.stand_alone_sigs)?,
+                TableId::EventMap => read_rows(src, offset, table, count, &ctx, &mut stream.event_maps)?,
+                TableId::EventPtr => read_rows(src, offset, table, count, &ctx, &mut stream.event_ptrs)?,
+                TableId::Event => read_rows(src, offset, table, count, &ctx, &mut stream.events)?,
+                TableId::PropertyMap => read_rows(src, offset, table, count, &ctx, &mut stream.property_maps)?,
+                TableId::PropertyPtr => read_rows(src, offset, table, count, &ctx, &mut stream.property_ptrs)?,
+                TableId::Property => read_rows(src, offset, table, count, &ctx, &mut stream.properties)?,
+                TableId::MethodSemantics => read_rows(src, offset, table, count, &ctx, &mut stream.method_semantics)?,
+                TableId::MethodImpl => read_rows(src, offset, table, count, &ctx, &mut stream.method_impls)?,
+                TableId::ModuleRef => read_rows(src, offset, table, count, &ctx, &mut stream.module_refs)?,
+                TableId::TypeSpec => read_rows(src, offset, table, count, &ctx, &mut stream.type_specs)?,
+                TableId::ImplMap => read_rows(src, offset, table, count, &ctx, &mut stream.impl_maps)?,
+                TableId::FieldRva => read_rows(src, offset, table, count, &ctx, &mut stream.field_rvas)?,
+                TableId::EncLog => read_rows(src, offset, table, count, &ctx, &mut stream.enc_logs)?,
+                TableId::EncMap => read_rows(src, offset, table, count, &ctx, &mut stream.enc_maps)?,
+                TableId::Assembly => read_rows(src, offset, table, count, &ctx, &mut stream.assemblies)?,
+                TableId::AssemblyProcessor => {
+                    read_rows(src, offset, table, count, &ctx, &mut stream.assembly_processors)?
+                }
+                TableId::AssemblyOs => read_rows(src, offset, table, count, &ctx, &mut stream.assembly_oses)?,
+                TableId::AssemblyRef => read_rows(src, offset, table, count, &ctx, &mut stream.assembly_refs)?,
+                TableId::AssemblyRefProcessor => {
+                    read_rows(src, offset, table, count, &ctx, &mut stream.assembly_ref_processors)?
+                }
+                TableId::AssemblyRefOs => read_rows(src, offset, table, count, &ctx, &mut stream.assembly_ref_oses)?,
+                TableId::File => read_rows(src, offset, table, count, &ctx, &mut stream.files)?,
+                TableId::ExportedType => read_rows(src, offset, table, count, &ctx, &mut stream.exported_types)?,
+                TableId::ManifestResource => {
+                    read_rows(src, offset, table, count, &ctx, &mut stream.manifest_resources)?
+                }
+                TableId::NestedClass => read_rows(src, offset, table, count, &ctx, &mut stream.nested_classes)?,
+                TableId::GenericParam => read_rows(src, offset, table, count, &ctx, &mut stream.generic_params)?,
+                TableId::MethodSpec => read_rows(src, offset, table, count, &ctx, &mut stream.method_specs)?,
+                TableId::GenericParamConstraint => {
+                    read_rows(src, offset, table, count, &ctx, &mut stream.generic_param_constraints)?
+                }
+            }
+        }
+        stream.rows = rows;
+
+        Ok((stream, *offset))
+    }
+}
+
+/// Rows from `start` up to the start of the next owner's list, or to the end of the table for the last owner.
+fn list_range(start: u32, next_start: Option<u32>, count: usize) -> Range<u32> {
+    let start = start.max(1);
+    let end = next_start.unwrap_or(count as u32 + 1).min(count as u32 + 1);
+    start..end.max(start)
+}
+
+/// Maps list positions through a Ptr table if the stream has one, otherwise they are the RIDs themselves.
+fn follow_ptr<'a, T>(
+    range: Range<u32>,
+    ptrs: &'a [T],
+    target: impl Fn(&T) -> u32 + 'a,
+) -> impl Iterator<Item = u32> + 'a {
+    range.map(move |i| ptrs.get(i as usize - 1).map_or(i, &target))
+}
+
+/// Member enumeration. Uncompressed (#-) streams may add a level of indirection between an
+/// owner's list column and the member table through FieldPtr, MethodPtr, ParamPtr, EventPtr
+/// and PropertyPtr, these methods follow it and always yield RIDs of the member table itself.
+impl TildaStream {
+    fn list_len<T, P>(rows: &[T], ptrs: &[P]) -> usize {
+        if ptrs.is_empty() {
+            rows.len()
+        } else {
+            ptrs.len()
+        }
+    }
+
+    fn owned_range<T>(owners: &[T], owner: u32, list: impl Fn(&T) -> u32, count: usize) -> Range<u32> {
+        let i = owner as usize;
+        match owner.checked_sub(1).and_then(|x| owners.get(x as usize)) {
+            Some(x) => list_range(list(x), owners.get(i).map(&list), count),
+            None => 0..0,
+        }
+    }
+
+    pub fn type_fields(&self, type_def: u32) -> impl Iterator<Item = u32> + '_ {
+        let count = Self::list_len(&self.fields, &self.field_ptrs);
+        let range = Self::owned_range(&self.type_defs, type_def, |x| x.field_list, count);
+        follow_ptr(range, &self.field_ptrs, |x| x.field)
+    }
+
+    pub fn type_methods(&self, type_def: u32) -> impl Iterator<Item = u32> + '_ {
+        let count = Self::list_len(&self.methods, &self.method_ptrs);
+        let range = Self::owned_range(&self.type_defs, type_def, |x| x.method_list, count);
+        follow_ptr(range, &self.method_ptrs, |x| x.method)
+    }
+
+    pub fn method_params(&self, method: u32) -> impl Iterator<Item = u32> + '_ {
+        let count = Self::list_len(&self.params, &self.param_ptrs);
+        let range = Self::owned_range(&self.methods, method, |x| x.param_list, count);
+        follow_ptr(range, &self.param_ptrs, |x| x.param)
+    }
+
+    pub fn event_map_events(&self, event_map: u32) -> impl Iterator<Item = u32> + '_ {
+        let count = Self::list_len(&self.events, &self.event_ptrs);
+        let range = Self::owned_range(&self.event_maps, event_map, |x| x.event_list, count);
+        follow_ptr(range, &self.event_ptrs, |x| x.event)
+    }
+
+    pub fn property_map_properties(&self, property_map: u32) -> impl Iterator<Item = u32> + '_ {
+        let count = Self::list_len(&self.properties, &self.property_ptrs);
+        let range = Self::owned_range(&self.property_maps, property_map, |x| x.property_list, count);
+        follow_ptr(range, &self.property_ptrs, |x| x.property)
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub enum TableId {
+    Module = 0x00,
+    TypeRef = 0x01,
+    TypeDef = 0x02,
+    FieldPtr = 0x03,
+    Field = 0x04,
+    MethodPtr = 0x05,
+    MethodDef = 0x06,
+    ParamPtr = 0x07,
+    Param = 0x08,
+    InterfaceImpl = 0x09,
+    MemberRef = 0x0a,
+    Constant = 0x0b,
+    CustomAttribute = 0x0c,
+    FieldMarshal = 0x0d,
+    DeclSecurity = 0x0e,
+    ClassLayout = 0x0f,
+    FieldLayout = 0x10,
+    StandAloneSig = 0x11,
+    EventMap = 0x12,
+    EventPtr = 0x13,
+    Event = 0x14,
+    PropertyMap = 0x15,
+    PropertyPtr = 0x16,
+    Property = 0x17,
+    MethodSemantics = 0x18,
+    MethodImpl = 0x19,
+    ModuleRef = 0x1a,
+    TypeSpec = 0x1b,
+    ImplMap = 0x1c,
+    FieldRva = 0x1d,
+    EncLog = 0x1e,
+    EncMap = 0x1f,
+    Assembly = 0x20,
+    AssemblyProcessor = 0x21,
+    AssemblyOs = 0x22,
+    AssemblyRef = 0x23,
+    AssemblyRefProcessor = 0x24,
+    AssemblyRefOs = 0x25,
+    File = 0x26,
+    ExportedType = 0x27,
+    ManifestResource = 0x28,
+    NestedClass = 0x29,
+    GenericParam = 0x2a,
+    MethodSpec = 0x2b,
+    GenericParamConstraint = 0x2c,
+}
+
+impl TableId {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0x00 => TableId::Module,
+            0x01 => TableId::TypeRef,
+            0x02 => TableId::TypeDef,
+            0x03 => TableId::FieldPtr,
+            0x04 => TableId::Field,
+            0x05 => TableId::MethodPtr,
+            0x06 => TableId::MethodDef,
+            0x07 => TableId::ParamPtr,
+            0x08 => TableId::Param,
+            0x09 => TableId::InterfaceImpl,
+            0x0a => TableId::MemberRef,
+            0x0b => TableId::Constant,
+            0x0c => TableId::CustomAttribute,
+            0x0d => TableId::FieldMarshal,
+            0x0e => TableId::DeclSecurity,
+            0x0f => TableId::ClassLayout,
+            0x10 => TableId::FieldLayout,
+            0x11 => TableId::StandAloneSig,
+            0x12 => TableId::EventMap,
+            0x13 => TableId::EventPtr,
+            0x14 => TableId::Event,
+            0x15 => TableId::PropertyMap,
+            0x16 => TableId::PropertyPtr,
+            0x17 => TableId::Property,
+            0x18 => TableId::MethodSemantics,
+            0x19 => TableId::MethodImpl,
+            0x1a => TableId::ModuleRef,
+            0x1b => TableId::TypeSpec,
+            0x1c => TableId::ImplMap,
+            0x1d => TableId::FieldRva,
+            0x1e => TableId::EncLog,
+            0x1f => TableId::EncMap,
+            0x20 => TableId::Assembly,
+            0x21 => TableId::AssemblyProcessor,
+            0x22 => TableId::AssemblyOs,
+            0x23 => TableId::AssemblyRef,
+            0x24 => TableId::AssemblyRefProcessor,
+            0x25 => TableId::AssemblyRefOs,
+            0x26 => TableId::File,
+            0x27 => TableId::ExportedType,
+            0x28 => TableId::ManifestResource,
+            0x29 => TableId::NestedClass,
+            0x2a => TableId::GenericParam,
+            0x2b => TableId::MethodSpec,
+            0x2c => TableId::GenericParamConstraint,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TableId::Module => "Module",
+            TableId::TypeRef => "TypeRef",
+            TableId::TypeDef => "TypeDef",
+            TableId::FieldPtr => "FieldPtr",
+            TableId::Field => "Field",
+            TableId::MethodPtr => "MethodPtr",
+            TableId::MethodDef => "MethodDef",
+            TableId::ParamPtr => "ParamPtr",
+            TableId::Param => "Param",
+            TableId::InterfaceImpl => "InterfaceImpl",
+            TableId::MemberRef => "MemberRef",
+            TableId::Constant => "Constant",
+            TableId::CustomAttribute => "CustomAttribute",
+            TableId::FieldMarshal => "FieldMarshal",
+            TableId::DeclSecurity => "DeclSecurity",
+            TableId::ClassLayout => "ClassLayout",
+            TableId::FieldLayout => "FieldLayout",
+            TableId::StandAloneSig => "StandAloneSig",
+            TableId::EventMap => "EventMap",
+            TableId::EventPtr => "EventPtr",
+            TableId::Event => "Event",
+            TableId::PropertyMap => "PropertyMap",
+            TableId::PropertyPtr => "PropertyPtr",
+            TableId::Property => "Property",
+            TableId::MethodSemantics => "MethodSemantics",
+            TableId::MethodImpl => "MethodImpl",
+            TableId::ModuleRef => "ModuleRef",
+            TableId::TypeSpec => "TypeSpec",
+            TableId::ImplMap => "ImplMap",
+            TableId::FieldRva => "FieldRva",
+            TableId::EncLog => "EncLog",
+            TableId::EncMap => "EncMap",
+            TableId::Assembly => "Assembly",
+            TableId::AssemblyProcessor => "AssemblyProcessor",
+            TableId::AssemblyOs => "AssemblyOs",
+            TableId::AssemblyRef => "AssemblyRef",
+            TableId::AssemblyRefProcessor => "AssemblyRefProcessor",
+            TableId::AssemblyRefOs => "AssemblyRefOs",
+            TableId::File => "File",
+            TableId::ExportedType => "ExportedType",
+            TableId::ManifestResource => "ManifestResource",
+            TableId::NestedClass => "NestedClass",
+            TableId::GenericParam => "GenericParam",
+            TableId::MethodSpec => "MethodSpec",
+            TableId::GenericParamConstraint => "GenericParamConstraint",
+        }
+    }
+
+    /// Looks a table up by its ECMA-335 name, e.g. `TypeDef`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        (0..=0x2c).filter_map(Self::from_u8).find(|x| x.name() == name)
+    }
+
+    /// Columns in storage order, named as the fields of the row struct.
+    pub fn columns(self) -> &'static [(&'static str, ColumnKind)] {
+        match self {
+            TableId::Module => &[
+                ("generation", ColumnKind::U16),
+                ("name", ColumnKind::String),
+                ("mvid", ColumnKind::Guid),
+                ("enc_id", ColumnKind::Guid),
+                ("enc_base_id", ColumnKind::Guid),
+            ],
+            TableId::TypeRef => &[
+                ("resolution_scope", ColumnKind::Coded),
+                ("type_name", ColumnKind::String),
+                ("type_namespace", ColumnKind::String),
+            ],
+            TableId::TypeDef => &[
+                ("flags", ColumnKind::U32),
+                ("type_name", ColumnKind::String),
+                ("type_namespace", ColumnKind::String),
+                ("extends", ColumnKind::Coded),
+                ("field_list", ColumnKind::Table(TableId::Field)),
+                ("method_list", ColumnKind::Table(TableId::MethodDef)),
+            ],
+            TableId::FieldPtr => &[("field", ColumnKind::Table(TableId::Field))],
+            TableId::Field => &[
+                ("flags", ColumnKind::U16),
+                ("name", ColumnKind::String),
+                ("signature", ColumnKind::Blob),
+            ],
+            TableId::MethodPtr => &[("method", ColumnKind::Table(TableId::MethodDef))],
+            TableId::MethodDef => &[
+                ("rva", ColumnKind::U32),
+                ("impl_flags", ColumnKind::U16),
+                ("flags", ColumnKind::U16),
+                ("name", ColumnKind::String),
+                ("signature", ColumnKind::Blob),
+                ("param_list", ColumnKind::Table(TableId::Param)),
+            ],
+            TableId::ParamPtr => &[("param", ColumnKind::Table(TableId::Param))],
+            TableId::Param => &[
+                ("flags", ColumnKind::U16),
+                ("sequence", ColumnKind::U16),
+                ("name", ColumnKind::String),
+            ],
+            TableId::InterfaceImpl => &[
+                ("class", ColumnKind::Table(TableId::TypeDef)),
+                ("interface", ColumnKind::Coded),
+            ],
+            TableId::MemberRef => &[
+                ("class", ColumnKind::Coded),
+                ("name", ColumnKind::String),
+                ("signature", ColumnKind::Blob),
+            ],
+            TableId::Constant => &[
+                ("constant_type", ColumnKind::U8),
+                ("padding", ColumnKind::U8),
+                ("parent", ColumnKind::Coded),
+                ("value", ColumnKind::Blob),
+            ],
+            TableId::CustomAttribute => &[
+                ("parent", ColumnKind::Coded),
+                ("attribute_type", ColumnKind::Coded),
+                ("value", ColumnKind::Blob),
+            ],
+            TableId::FieldMarshal => &[("parent", ColumnKind::Coded), ("native_type", ColumnKind::Blob)],
+            TableId::DeclSecurity => &[
+                ("action", ColumnKind::U16),
+                ("parent", ColumnKind::Coded),
+                ("permission_set", ColumnKind::Blob),
+            ],
+            TableId::ClassLayout => &[
+                ("packing_size", ColumnKind::U16),
+                ("class_size", ColumnKind::U32),
+                ("parent", ColumnKind::Table(TableId::TypeDef)),
+            ],
+            TableId::FieldLayout => &[
+                ("offset", ColumnKind::U32),
+                ("field", ColumnKind::Table(TableId::Field)),
+            ],
+            TableId::StandAloneSig => &[("signature", ColumnKind::Blob)],
+            TableId::EventMap => &[
+                ("parent", ColumnKind::Table(TableId::TypeDef)),
+                ("event_list", ColumnKind::Table(TableId::Event)),
+            ],
+            TableId::EventPtr => &[("event", ColumnKind::Table(TableId::Event))],
+            TableId::Event => &[
+                ("event_flags", ColumnKind::U16),
+                ("name", ColumnKind::String),
+                ("event_type", ColumnKind::Coded),
+            ],
+            TableId::PropertyMap => &[
+                ("parent", ColumnKind::Table(TableId::TypeDef)),
+                ("property_list", ColumnKind::Table(TableId::Property)),
+            ],
+            TableId::PropertyPtr => &[("property", ColumnKind::Table(TableId::Property))],
+            TableId::Property => &[
+                ("flags", ColumnKind::U16),
+                ("name", ColumnKind::String),
+                ("property_type", ColumnKind::Blob),
+            ],
+            TableId::MethodSemantics => &[
+                ("semantics", ColumnKind::U16),
+                ("method", ColumnKind::Table(TableId::MethodDef)),
+                ("association", ColumnKind::Coded),
+            ],
+            TableId::MethodImpl => &[
+                ("class", ColumnKind::Table(TableId::TypeDef)),
+                ("method_body", ColumnKind::Coded),
+                ("method_declaration", ColumnKind::Coded),
+            ],
+            TableId::ModuleRef => &[("name", ColumnKind::String)],
+            TableId::TypeSpec => &[("signature", ColumnKind::Blob)],
+            TableId::ImplMap => &[
+                ("mapping_flags", ColumnKind::U16),
+                ("member_forwarded", ColumnKind::Coded),
+                ("import_name", ColumnKind::String),
+                ("import_scope", ColumnKind::Table(TableId::ModuleRef)),
+            ],
+            TableId::FieldRva => &[("rva", ColumnKind::U32), ("field", ColumnKind::Table(TableId::Field))],
+            TableId::EncLog => &[("token", ColumnKind::U32), ("func_code", ColumnKind::U32)],
+            TableId::EncMap => &[("token", ColumnKind::U32)],
+            TableId::Assembly => &[
+                ("hash_alg_id", ColumnKind::U32),
+                ("major_version", ColumnKind::U16),
+                ("minor_version", ColumnKind::U16),
+                ("build_number", ColumnKind::U16),
+                ("revision_number", ColumnKind::U16),
+                ("flags", ColumnKind::U32),
+                ("public_key", ColumnKind::Blob),
+                ("name", ColumnKind::String),
+                ("culture", ColumnKind::String),
+            ],
+            TableId::AssemblyProcessor => &[("processor", ColumnKind::U32)],
+            TableId::AssemblyOs => &[
+                ("os_platform_id", ColumnKind::U32),
+                ("os_major_version", ColumnKind::U32),
+                ("os_minor_version", ColumnKind::U32),
+            ],
+            TableId::AssemblyRef => &[
+                ("major_version", ColumnKind::U16),
+                ("minor_version", ColumnKind::U16),
+                ("build_number", ColumnKind::U16),
+                ("revision_number", ColumnKind::U16),
+                ("flags", ColumnKind::U32),
+                ("public_key_or_token", ColumnKind::Blob),
+                ("name", ColumnKind::String),
+                ("culture", ColumnKind::String),
+                ("hash_value", ColumnKind::Blob),
+            ],
+            TableId::AssemblyRefProcessor => &[
+                ("processor", ColumnKind::U32),
+                ("assembly_ref", ColumnKind::Table(TableId::AssemblyRef)),
+            ],
+            TableId::AssemblyRefOs => &[
+                ("os_platform_id", ColumnKind::U32),
+                ("os_major_version", ColumnKind::U32),
+                ("os_minor_version", ColumnKind::U32),
+                ("assembly_ref", ColumnKind::Table(TableId::AssemblyRef)),
+            ],
+            TableId::File => &[
+                ("flags", ColumnKind::U32),
+                ("name", ColumnKind::String),
+                ("hash_value", ColumnKind::Blob),
+            ],
+            TableId::ExportedType => &[
+                ("flags", ColumnKind::U32),
+                ("type_def_id", ColumnKind::U32),
+                ("type_name", ColumnKind::String),
+                ("type_namespace", ColumnKind::String),
+                ("implementation", ColumnKind::Coded),
+            ],
+            TableId::ManifestResource => &[
+                ("offset", ColumnKind::U32),
+                ("flags", ColumnKind::U32),
+                ("name", ColumnKind::String),
+                ("implementation", ColumnKind::Coded),
+            ],
+            TableId::NestedClass => &[
+                ("nested_class", ColumnKind::Table(TableId::TypeDef)),
+                ("enclosing_class", ColumnKind::Table(TableId::TypeDef)),
+            ],
+            TableId::GenericParam => &[
+                ("number", ColumnKind::U16),
+                ("flags", ColumnKind::U16),
+                ("owner", ColumnKind::Coded),
+                ("name", ColumnKind::String),
+            ],
+            TableId::MethodSpec => &[("method", ColumnKind::Coded), ("instantiation", ColumnKind::Blob)],
+            TableId::GenericParamConstraint => &[
+                ("owner", ColumnKind::Table(TableId::GenericParam)),
+                ("constraint", ColumnKind::Coded),
+            ],
+        }
+    }
+}
+
+/// How the value of a table column is interpreted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColumnKind {
+    U8,
+    U16,
+    U32,
+    /// Index into the #Strings heap
+    String,
+    /// 1-based index into the #GUID heap
+    Guid,
+    /// Index into the #Blob heap
+    Blob,
+    /// RID in the given table
+    Table(TableId),
+    /// Coded index, the table is part of the value
+    Coded,
+}
+
+/// Column widths of a particular image. Heap indices widen to 4 bytes when the
+/// corresponding `heap_sizes` bit is set, table indices when the referenced
+/// table(s) no longer fit into 16 bits.
+#[derive(Debug, Clone)]
+pub struct TableContext {
+    pub heap_sizes: u8,
+    pub row_counts: [u32; 64],
+}
+
+impl TableContext {
+    pub fn new(heap_sizes: u8, rows: &[(u32, u32)]) -> Self {
+        let mut row_counts = [0; 64];
+        for &(i, count) in rows {
+            row_counts[i as usize] = count;
+        }
+        Self { heap_sizes, row_counts }
+    }
+
+    pub fn string_index_size(&self) -> usize {
+        if self.heap_sizes & 0x01 != 0 {
+            4
+        } else {
+            2
+        }
+    }
+
+    pub fn guid_index_size(&self) -> usize {
+        if self.heap_sizes & 0x02 != 0 {
+            4
+        } else {
+            2
+        }
+    }
+
+    pub fn blob_index_size(&self) -> usize {
+        if self.heap_sizes & 0x04 != 0 {
+            4
+        } else {
+            2
+        }
+    }
+
+    pub fn row_count(&self, table: TableId) -> u32 {
+        self.row_counts[table as usize]
+    }
+
+    pub fn table_index_size(&self, table: TableId) -> usize {
+        if self.row_count(table) < 1 << 16 {
+            2
+        } else {
+            4
+        }
+    }
+
+    /// Size of a FieldList, MethodList, ParamList, EventList or PropertyList column, which indexes the Ptr
+    /// table of `table` instead when the stream has a non-empty one.
+    pub fn list_index_size(&self, table: TableId) -> usize {
+        let ptr = match table {
+            TableId::Field => TableId::FieldPtr,
+            TableId::MethodDef => TableId::MethodPtr,
+            TableId::Param => TableId::ParamPtr,
+            TableId::Event => TableId::EventPtr,
+            TableId::Property => TableId::PropertyPtr,
+            _ => table,
+        };
+        if self.row_count(ptr) != 0 {
+            self.table_index_size(ptr)
+        } else {
+            self.table_index_size(table)
+        }
+    }
+
+    pub fn coded_index_size(&self, kind: &CodedIndexKind) -> usize {
+        let max_rows = kind.tables.iter().map(|&x| self.row_count(x)).max().unwrap_or(0);
+        if max_rows < 1 << (16 - kind.tag_bits) {
+            2
+        } else {
+            4
+        }
+    }
+
+    fn read_index(src: &[u8], offset: &mut usize, size: usize) -> Result<u32, Error> {
+        if size == 2 {
+            Ok(u32::from(src.gread_with::<u16>(offset, scroll::LE)?))
+        } else {
+            Ok(src.gread_with(offset, scroll::LE)?)
+        }
+    }
+
+    pub fn read_string_index(&self, src: &[u8], offset: &mut usize) -> Result<u32, Error> {
+        Self::read_index(src, offset, self.string_index_size())
+    }
+
+    pub fn read_guid_index(&self, src: &[u8], offset: &mut usize) -> Result<u32, Error> {
+        Self::read_index(src, offset, self.guid_index_size())
+    }
+
+    pub fn read_blob_index(&self, src: &[u8], offset: &mut usize) -> Result<u32, Error> {
+        Self::read_index(src, offset, self.blob_index_size())
+    }
+
+    pub fn read_table_index(&self, src: &[u8], offset: &mut usize, table: TableId) -> Result<u32, Error> {
+        Self::read_index(src, offset, self.table_index_size(table))
+    }
+
+    pub fn read_list_index(&self, src: &[u8], offset: &mut usize, table: TableId) -> Result<u32, Error> {
+        Self::read_index(src, offset, self.list_index_size(table))
+    }
+
+    pub fn read_coded_index(&self, src: &[u8], offset: &mut usize, kind: &CodedIndexKind) -> Result<u32, Error> {
+        Self::read_index(src, offset, self.coded_index_size(kind))
+    }
+}
+
+/// 0x00
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Module {
+    pub generation: u16,
+    pub name: u32,
+    pub mvid: u32,
+    pub enc_id: u32,
+    pub enc_base_id: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for Module {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let generation = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let mvid = ctx.read_guid_index(src, offset)?;
+        let enc_id = ctx.read_guid_index(src, offset)?;
+        let enc_base_id = ctx.read_guid_index(src, offset)?;
+        Ok((
+            Self {
+                generation,
+                name,
+                mvid,
+                enc_id,
+                enc_base_id,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x01
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct TypeRef {
+    pub resolution_scope: ResolutionScope,
+    pub type_name: u32,
+    pub type_namespace: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for TypeRef {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let resolution_scope = src.gread_with(offset, ctx)?;
+        let type_name = ctx.read_string_index(src, offset)?;
+        let type_namespace = ctx.read_string_index(src, offset)?;
+        Ok((
+            Self {
+                resolution_scope,
+                type_name,
+                type_namespace,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x02
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct TypeDef {
+    pub flags: u32,
+    pub type_name: u32,
+    pub type_namespace: u32,
+    pub extends: TypeDefOrRef,
+    pub field_list: u32,
+    pub method_list: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for TypeDef {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let type_name = ctx.read_string_index(src, offset)?;
+        let type_namespace = ctx.read_string_index(src, offset)?;
+        let extends = src.gread_with(offset, ctx)?;
+        let field_list = ctx.read_list_index(src, offset, TableId::Field)?;
+        let method_list = ctx.read_list_index(src, offset, TableId::MethodDef)?;
+        Ok((
+            Self {
+                flags,
+                type_name,
+                type_namespace,
+                extends,
+                field_list,
+                method_list,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x03
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct FieldPtr {
+    pub field: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for FieldPtr {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let field = ctx.read_table_index(src, offset, TableId::Field)?;
+        Ok((Self { field }, *offset))
+    }
+}
+
+/// 0x04
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Field {
+    pub flags: u16,
+    pub name: u32,
+    pub signature: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for Field {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let signature = ctx.read_blob_index(src, offset)?;
+        Ok((Self { flags, name, signature }, *offset))
+    }
+}
+
+/// 0x05
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct MethodPtr {
+    pub method: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for MethodPtr {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let method = ctx.read_table_index(src, offset, TableId::MethodDef)?;
+        Ok((Self { method }, *offset))
+    }
+}
+
+/// 0x06
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct MethodDef {
+    pub rva: u32,
+    pub impl_flags: u16,
+    pub flags: u16,
+    pub name: u32,
+    pub signature: u32,
+    pub param_list: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for MethodDef {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let rva = src.gread_with(offset, scroll::LE)?;
+        let impl_flags = src.gread_with(offset, scroll::LE)?;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let signature = ctx.read_blob_index(src, offset)?;
+        let param_list = ctx.read_list_index(src, offset, TableId::Param)?;
+        Ok((
+            Self {
+                rva,
+                impl_flags,
+                flags,
+                name,
+                signature,
+                param_list,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x07
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct ParamPtr {
+    pub param: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for ParamPtr {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let param = ctx.read_table_index(src, offset, TableId::Param)?;
+        Ok((Self { param }, *offset))
+    }
+}
+
+/// 0x08
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Param {
+    pub flags: u16,
+    pub sequence: u16,
+    pub name: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for Param {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let sequence = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        Ok((Self { flags, sequence, name }, *offset))
+    }
+}
+
+/// 0x09
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct InterfaceImpl {
+    pub class: u32,
+    pub interface: TypeDefOrRef,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for InterfaceImpl {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let class = ctx.read_table_index(src, offset, TableId::TypeDef)?;
+        let interface = src.gread_with(offset, ctx)?;
+        Ok((Self { class, interface }, *offset))
+    }
+}
+
+/// 0x0A
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct MemberRef {
+    pub class: MemberRefParent,
+    pub name: u32,
+    pub signature: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for MemberRef {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let class = src.gread_with(offset, ctx)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let signature = ctx.read_blob_index(src, offset)?;
+        Ok((Self { class, name, signature }, *offset))
+    }
+}
+
+/// 0x0B
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Constant {
+    pub constant_type: u8,
+    pub padding: u8,
+    pub parent: HasConstant,
+    pub value: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for Constant {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let constant_type = src.gread_with(offset, scroll::LE)?;
+        let padding = src.gread_with(offset, scroll::LE)?;
+        let parent = src.gread_with(offset, ctx)?;
+        let value = ctx.read_blob_index(src, offset)?;
+        Ok((
+            Self {
+                constant_type,
+                padding,
+                parent,
+                value,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x0C
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct CustomAttribute {
+    pub parent: HasCustomAttribute,
+    pub attribute_type: CustomAttributeType,
+    pub value: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for CustomAttribute {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let parent = src.gread_with(offset, ctx)?;
+        let attribute_type = src.gread_with(offset, ctx)?;
+        let value = ctx.read_blob_index(src, offset)?;
+        Ok((
+            Self {
+                parent,
+                attribute_type,
+                value,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x0D
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct FieldMarshal {
+    pub parent: HasFieldMarshal,
+    pub native_type: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for FieldMarshal {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let parent = src.gread_with(offset, ctx)?;
+        let native_type = ctx.read_blob_index(src, offset)?;
+        Ok((Self { parent, native_type }, *offset))
+    }
+}
+
+/// 0x0E
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct DeclSecurity {
+    pub action: u16,
+    pub parent: HasDeclSecurity,
+    pub permission_set: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for DeclSecurity {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let action = src.gread_with(offset, scroll::LE)?;
+        let parent = src.gread_with(offset, ctx)?;
+        let permission_set = ctx.read_blob_index(src, offset)?;
+        Ok((
+            Self {
+                action,
+                parent,
+                permission_set,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x0F
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct ClassLayout {
+    pub packing_size: u16,
+    pub class_size: u32,
+    pub parent: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for ClassLayout {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let packing_size = src.gread_with(offset, scroll::LE)?;
+        let class_size = src.gread_with(offset, scroll::LE)?;
+        let parent = ctx.read_table_index(src, offset, TableId::TypeDef)?;
+        Ok((
+            Self {
+                packing_size,
+                class_size,
+                parent,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x10
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct FieldLayout {
+    pub offset: u32,
+    pub field: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for FieldLayout {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let offset_field = src.gread_with(offset, scroll::LE)?;
+        let field = ctx.read_table_index(src, offset, TableId::Field)?;
+        Ok((
+            Self {
+                offset: offset_field,
+                field,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x11
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct StandAloneSig {
+    pub signature: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for StandAloneSig {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let signature = ctx.read_blob_index(src, offset)?;
+        Ok((Self { signature }, *offset))
+    }
+}
+
+/// 0x12
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct EventMap {
+    pub parent: u32,
+    pub event_list: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for EventMap {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let parent = ctx.read_table_index(src, offset, TableId::TypeDef)?;
+        let event_list = ctx.read_list_index(src, offset, TableId::Event)?;
+        Ok((Self { parent, event_list }, *offset))
+    }
+}
+
+/// 0x13
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct EventPtr {
+    pub event: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for EventPtr {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let event = ctx.read_table_index(src, offset, TableId::Event)?;
+        Ok((Self { event }, *offset))
+    }
+}
+
+/// 0x14
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Event {
+    pub event_flags: u16,
+    pub name: u32,
+    pub event_type: TypeDefOrRef,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for Event {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let event_flags = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let event_type = src.gread_with(offset, ctx)?;
+        Ok((
+            Self {
+                event_flags,
+                name,
+                event_type,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x15
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct PropertyMap {
+    pub parent: u32,
+    pub property_list: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for PropertyMap {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let parent = ctx.read_table_index(src, offset, TableId::TypeDef)?;
+        let property_list = ctx.read_list_index(src, offset, TableId::Property)?;
+        Ok((Self { parent, property_list }, *offset))
+    }
+}
+
+/// 0x16
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct PropertyPtr {
+    pub property: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for PropertyPtr {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let property = ctx.read_table_index(src, offset, TableId::Property)?;
+        Ok((Self { property }, *offset))
+    }
+}
+
+/// 0x17
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Property {
+    pub flags: u16,
+    pub name: u32,
+    pub property_type: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for Property {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let property_type = ctx.read_blob_index(src, offset)?;
+        Ok((
+            Self {
+                flags,
+                name,
+                property_type,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x18
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct MethodSemantics {
+    pub semantics: u16,
+    pub method: u32,
+    pub association: HasSemantics,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for MethodSemantics {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let semantics = src.gread_with(offset, scroll::LE)?;
+        let method = ctx.read_table_index(src, offset, TableId::MethodDef)?;
+        let association = src.gread_with(offset, ctx)?;
+        Ok((
+            Self {
+                semantics,
+                method,
+                association,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x19
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct MethodImpl {
+    pub class: u32,
+    pub method_body: MethodDefOrRef,
+    pub method_declaration: MethodDefOrRef,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for MethodImpl {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let class = ctx.read_table_index(src, offset, TableId::TypeDef)?;
+        let method_body = src.gread_with(offset, ctx)?;
+        let method_declaration = src.gread_with(offset, ctx)?;
+        Ok((
+            Self {
+                class,
+                method_body,
+                method_declaration,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x1A
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct ModuleRef {
+    pub name: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for ModuleRef {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let name = ctx.read_string_index(src, offset)?;
+        Ok((Self { name }, *offset))
+    }
+}
+
+/// 0x1B
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct TypeSpec {
+    pub signature: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for TypeSpec {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let signature = ctx.read_blob_index(src, offset)?;
+        Ok((Self { signature }, *offset))
+    }
+}
+
+/// 0x1C
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct ImplMap {
+    pub mapping_flags: u16,
+    pub member_forwarded: MemberForwarded,
+    pub import_name: u32,
+    pub import_scope: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for ImplMap {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let mapping_flags = src.gread_with(offset, scroll::LE)?;
+        let member_forwarded = src.gread_with(offset, ctx)?;
+        let import_name = ctx.read_string_index(src, offset)?;
+        let import_scope = ctx.read_table_index(src, offset, TableId::ModuleRef)?;
+        Ok((
+            Self {
+                mapping_flags,
+                member_forwarded,
+                import_name,
+                import_scope,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x1D
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct FieldRva {
+    pub rva: u32,
+    pub field: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for FieldRva {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let rva = src.gread_with(offset, scroll::LE)?;
+        let field = ctx.read_table_index(src, offset, TableId::Field)?;
+        Ok((Self { rva, field }, *offset))
+    }
+}
+
+/// 0x1E
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct EncLog {
+    pub token: u32,
+    pub func_code: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for EncLog {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let token = src.gread_with(offset, scroll::LE)?;
+        let func_code = src.gread_with(offset, scroll::LE)?;
+        Ok((Self { token, func_code }, *offset))
+    }
+}
+
+/// 0x1F
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct EncMap {
+    pub token: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for EncMap {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let token = src.gread_with(offset, scroll::LE)?;
+        Ok((Self { token }, *offset))
+    }
+}
+
+/// 0x20
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Assembly {
+    pub hash_alg_id: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub build_number: u16,
+    pub revision_number: u16,
+    pub flags: u32,
+    pub public_key: u32,
+    pub name: u32,
+    pub culture: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for Assembly {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let hash_alg_id = src.gread_with(offset, scroll::LE)?;
+        let major_version = src.gread_with(offset, scroll::LE)?;
+        let minor_version = src.gread_with(offset, scroll::LE)?;
+        let build_number = src.gread_with(offset, scroll::LE)?;
+        let revision_number = src.gread_with(offset, scroll::LE)?;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let public_key = ctx.read_blob_index(src, offset)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let culture = ctx.read_string_index(src, offset)?;
+        Ok((
+            Self {
+                hash_alg_id,
+                major_version,
+                minor_version,
+                build_number,
+                revision_number,
+                flags,
+                public_key,
+                name,
+                culture,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x21
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct AssemblyProcessor {
+    pub processor: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for AssemblyProcessor {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let processor = src.gread_with(offset, scroll::LE)?;
+        Ok((Self { processor }, *offset))
+    }
+}
+
+/// 0x22
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct AssemblyOs {
+    pub os_platform_id: u32,
+    pub os_major_version: u32,
+    pub os_minor_version: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for AssemblyOs {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], _ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let os_platform_id = src.gread_with(offset, scroll::LE)?;
+        let os_major_version = src.gread_with(offset, scroll::LE)?;
+        let os_minor_version = src.gread_with(offset, scroll::LE)?;
+        Ok((
+            Self {
+                os_platform_id,
+                os_major_version,
+                os_minor_version,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x23
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct AssemblyRef {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub build_number: u16,
+    pub revision_number: u16,
+    pub flags: u32,
+    pub public_key_or_token: u32,
+    pub name: u32,
+    pub culture: u32,
+    pub hash_value: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for AssemblyRef {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let major_version = src.gread_with(offset, scroll::LE)?;
+        let minor_version = src.gread_with(offset, scroll::LE)?;
+        let build_number = src.gread_with(offset, scroll::LE)?;
+        let revision_number = src.gread_with(offset, scroll::LE)?;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let public_key_or_token = ctx.read_blob_index(src, offset)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let culture = ctx.read_string_index(src, offset)?;
+        let hash_value = ctx.read_blob_index(src, offset)?;
+        Ok((
+            Self {
+                major_version,
+                minor_version,
+                build_number,
+                revision_number,
+                flags,
+                public_key_or_token,
+                name,
+                culture,
+                hash_value,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x24
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct AssemblyRefProcessor {
+    pub processor: u32,
+    pub assembly_ref: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for AssemblyRefProcessor {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let processor = src.gread_with(offset, scroll::LE)?;
+        let assembly_ref = ctx.read_table_index(src, offset, TableId::AssemblyRef)?;
+        Ok((
+            Self {
+                processor,
+                assembly_ref,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x25
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct AssemblyRefOs {
+    pub os_platform_id: u32,
+    pub os_major_version: u32,
+    pub os_minor_version: u32,
+    pub assembly_ref: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for AssemblyRefOs {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let os_platform_id = src.gread_with(offset, scroll::LE)?;
+        let os_major_version = src.gread_with(offset, scroll::LE)?;
+        let os_minor_version = src.gread_with(offset, scroll::LE)?;
+        let assembly_ref = ctx.read_table_index(src, offset, TableId::AssemblyRef)?;
+        Ok((
+            Self {
+                os_platform_id,
+                os_major_version,
+                os_minor_version,
+                assembly_ref,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x26
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct File {
+    pub flags: u32,
+    pub name: u32,
+    pub hash_value: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for File {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let hash_value = ctx.read_blob_index(src, offset)?;
+        Ok((
+            Self {
+                flags,
+                name,
+                hash_value,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x27
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct ExportedType {
+    pub flags: u32,
+    pub type_def_id: u32,
+    pub type_name: u32,
+    pub type_namespace: u32,
+    pub implementation: Implementation,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for ExportedType {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let type_def_id = src.gread_with(offset, scroll::LE)?;
+        let type_name = ctx.read_string_index(src, offset)?;
+        let type_namespace = ctx.read_string_index(src, offset)?;
+        let implementation = src.gread_with(offset, ctx)?;
+        Ok((
+            Self {
+                flags,
+                type_def_id,
+                type_name,
+                type_namespace,
+                implementation,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x28
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct ManifestResource {
+    pub offset: u32,
+    pub flags: u32,
+    pub name: u32,
+    pub implementation: Implementation,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for ManifestResource {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let offset_field = src.gread_with(offset, scroll::LE)?;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let name = ctx.read_string_index(src, offset)?;
+        let implementation = src.gread_with(offset, ctx)?;
+        Ok((
+            Self {
+                offset: offset_field,
+                flags,
+                name,
+                implementation,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x29
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct NestedClass {
+    pub nested_class: u32,
+    pub enclosing_class: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for NestedClass {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let nested_class = ctx.read_table_index(src, offset, TableId::TypeDef)?;
+        let enclosing_class = ctx.read_table_index(src, offset, TableId::TypeDef)?;
+        Ok((
+            Self {
+                nested_class,
+                enclosing_class,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x2A
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct GenericParam {
+    pub number: u16,
+    pub flags: u16,
+    pub owner: TypeOrMethodDef,
+    pub name: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for GenericParam {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let number = src.gread_with(offset, scroll::LE)?;
+        let flags = src.gread_with(offset, scroll::LE)?;
+        let owner = src.gread_with(offset, ctx)?;
+        let name = ctx.read_string_index(src, offset)?;
+        Ok((
+            Self {
+                number,
+                flags,
+                owner,
+                name,
+            },
+            *offset,
+        ))
+    }
+}
+
+/// 0x2B
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct MethodSpec {
+    pub method: MethodDefOrRef,
+    pub instantiation: u32,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for MethodSpec {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let method = src.gread_with(offset, ctx)?;
+        let instantiation = ctx.read_blob_index(src, offset)?;
+        Ok((Self { method, instantiation }, *offset))
+    }
+}
+
+/// 0x2C
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct GenericParamConstraint {
+    pub owner: u32,
+    pub constraint: TypeDefOrRef,
+}
+
+impl<'a> TryFromCtx<'a, &TableContext> for GenericParamConstraint {
+    type Error = Error;
+    fn try_from_ctx(src: &'a [u8], ctx: &TableContext) -> Result<(Self, usize), Self::Error> {
+        let offset = &mut 0;
+        let owner = ctx.read_table_index(src, offset, TableId::GenericParam)?;
+        let constraint = src.gread_with(offset, ctx)?;
+        Ok((Self { owner, constraint }, *offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::{Token, TokenKind};
+
+    /// A table stream with these tables, given in ascending table order with their row counts and rows.
+    fn stream(tables: &[(TableId, u32, &[u8])]) -> TildaStream {
+        let mut src = vec![0, 0, 0, 0, 2, 0, 0, 1];
+        let valid = tables.iter().fold(0_u64, |valid, x| valid | 1 << x.0 as u8);
+        src.extend(&valid.to_le_bytes());
+        src.extend(&[0; 8]);
+        for (_, count, _) in tables {
+            src.extend(&count.to_le_bytes());
+        }
+        for (_, _, rows) in tables {
+            src.extend(*rows);
+        }
+        src.pread_with(0, scroll::LE).unwrap()
+    }
+
+    #[test]
+    fn every_table_decodes() {
+        // One row per table, each byte 0x22: coded indices get tag 2 or 0, which every kind has
+        let tables = (0..=0x2c)
+            .filter_map(TableId::from_u8)
+            .map(|table| {
+                let size = table.columns().iter().fold(0, |size, column| {
+                    size + match column.1 {
+                        ColumnKind::U8 => 1,
+                        ColumnKind::U32 => 4,
+                        _ => 2,
+                    }
+                });
+                (table, 1, vec![0x22; size])
+            })
+            .collect::<Vec<_>>();
+        let rows = tables.iter().map(|x| (x.0, x.1, &x.2[..])).collect::<Vec<_>>();
+        let stream = stream(&rows);
+        for &(table, _, _) in &tables {
+            assert_eq!(stream.row_count(table), 1, "{}", table.name());
+            let row = stream.resolve(Token::new(TokenKind::Table(table), 1)).unwrap();
+            let columns = match serde_json::to_value(row).unwrap() {
+                serde_json::Value::Object(columns) => columns,
+                other => panic!("{}: {}", table.name(), other),
+            };
+            // Rows read from the wrong offset wouldn't get these values
+            for &(name, kind) in table.columns() {
+                let expected = match kind {
+                    ColumnKind::U8 => 0x22,
+                    ColumnKind::U32 => 0x2222_2222,
+                    ColumnKind::Coded => continue,
+                    _ => 0x2222,
+                };
+                assert_eq!(columns[name], expected, "{}.{}", table.name(), name);
+            }
+        }
+    }
+
+    #[test]
+    fn heap_sizes_widen_heap_indices() {
+        let ctx = TableContext::new(0, &[]);
+        assert_eq!(
+            (ctx.string_index_size(), ctx.guid_index_size(), ctx.blob_index_size()),
+            (2, 2, 2)
+        );
+        let ctx = TableContext::new(0x01 | 0x04, &[]);
+        assert_eq!(
+            (ctx.string_index_size(), ctx.guid_index_size(), ctx.blob_index_size()),
+            (4, 2, 4)
+        );
+
+        // Module: generation, then a string index and three GUID indices
+        let row: &[u8] = &[0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0];
+        let offset = &mut 0;
+        let module: Module = row.gread_with(offset, &TableContext::new(0x01, &[])).unwrap();
+        assert_eq!((module.name, module.mvid, *offset), (1, 2, 12));
+        let row: &[u8] = &[0, 0, 1, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let offset = &mut 0;
+        let module: Module = row.gread_with(offset, &TableContext::new(0x02, &[])).unwrap();
+        assert_eq!((module.name, module.mvid, *offset), (1, 2, 16));
+    }
+
+    #[test]
+    fn list_columns_index_the_ptr_table() {
+        let ctx = TableContext::new(
+            0,
+            &[(TableId::MethodDef as u32, 2), (TableId::MethodPtr as u32, 0x10000)],
+        );
+        assert_eq!(ctx.list_index_size(TableId::MethodDef), 4);
+        assert_eq!(ctx.list_index_size(TableId::Field), 2);
+
+        // TypeDef: flags, name, namespace, extends, then the FieldList and MethodList columns
+        let row: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0, 0, 0];
+        let offset = &mut 0;
+        let type_def: TypeDef = row.gread_with(offset, &ctx).unwrap();
+        assert_eq!((type_def.field_list, type_def.method_list, *offset), (1, 2, 16));
+
+        let ctx = TableContext::new(0, &[(TableId::MethodDef as u32, 0x10000)]);
+        assert_eq!(ctx.list_index_size(TableId::MethodDef), 4);
+        let ctx = TableContext::new(
+            0,
+            &[(TableId::MethodDef as u32, 0x10000), (TableId::MethodPtr as u32, 2)],
+        );
+        assert_eq!(ctx.list_index_size(TableId::MethodDef), 2);
+    }
+
+    #[test]
+    fn members_follow_ptr_tables() {
+        // TypeDef: flags, name, namespace, extends, FieldList, MethodList
+        let type_defs = [
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0],
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 3, 0],
+        ]
+        .concat();
+        // MethodPtr rows list the methods of the first type, then the second
+        let method_ptrs = [3, 0, 1, 0, 2, 0];
+        let methods = [0; 3 * 14];
+        let tables = stream(&[
+            (TableId::TypeDef, 2, &type_defs),
+            (TableId::MethodPtr, 3, &method_ptrs),
+            (TableId::MethodDef, 3, &methods),
+        ]);
+        assert_eq!(tables.type_methods(1).collect::<Vec<_>>(), vec![3, 1]);
+        assert_eq!(tables.type_methods(2).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(tables.type_methods(3).count(), 0);
+        assert_eq!(tables.type_fields(1).count(), 0);
+
+        // Without a Ptr table the lists are RIDs of the member table
+        let tables = stream(&[(TableId::TypeDef, 2, &type_defs), (TableId::MethodDef, 3, &methods)]);
+        assert_eq!(tables.type_methods(1).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(tables.type_methods(2).collect::<Vec<_>>(), vec![3]);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::error::Error;
+use crate::tables::*;
+use serde::Serialize;
+
+/// A metadata token: table in the high byte, 1-based row (RID) in the low 24 bits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub rid: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Table(TableId),
+    /// 0x70, RID is an offset into the #US heap
+    UserString,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, rid: u32) -> Self {
+        Self { kind, rid }
+    }
+
+    pub fn decode(value: u32) -> Result<Self, Error> {
+        let rid = value & 0x00ff_ffff;
+        let kind = match (value >> 24) as u8 {
+            0x70 => TokenKind::UserString,
+            table => TokenKind::Table(TableId::from_u8(table).ok_or(Error::InvalidToken(value))?),
+        };
+        Ok(Self { kind, rid })
+    }
+
+    pub fn raw(&self) -> u32 {
+        let table = match self.kind {
+            TokenKind::Table(table) => table as u32,
+            TokenKind::UserString => 0x70,
+        };
+        table << 24 | self.rid
+    }
+}
+
+/// A row a token points to.
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Row<'a> {
+    Module(&'a Module),
+    TypeRef(&'a TypeRef),
+    TypeDef(&'a TypeDef),
+    FieldPtr(&'a FieldPtr),
+    Field(&'a Field),
+    MethodPtr(&'a MethodPtr),
+    MethodDef(&'a MethodDef),
+    ParamPtr(&'a ParamPtr),
+    Param(&'a Param),
+    InterfaceImpl(&'a InterfaceImpl),
+    MemberRef(&'a MemberRef),
+    Constant(&'a Constant),
+    CustomAttribute(&'a CustomAttribute),
+    FieldMarshal(&'a FieldMarshal),
+    DeclSecurity(&'a DeclSecurity),
+    ClassLayout(&'a ClassLayout),
+    FieldLayout(&'a FieldLayout),
+    StandAloneSig(&'a StandAloneSig),
+    EventMap(&'a EventMap),
+    EventPtr(&'a EventPtr),
+    Event(&'a Event),
+    PropertyMap(&'a PropertyMap),
+    PropertyPtr(&'a PropertyPtr),
+    Property(&'a Property),
+    MethodSemantics(&'a MethodSemantics),
+    MethodImpl(&'a MethodImpl),
+    ModuleRef(&'a ModuleRef),
+    TypeSpec(&'a TypeSpec),
+    ImplMap(&'a ImplMap),
+    FieldRva(&'a FieldRva),
+    EncLog(&'a EncLog),
+    EncMap(&'a EncMap),
+    Assembly(&'a Assembly),
+    AssemblyProcessor(&'a AssemblyProcessor),
+    AssemblyOs(&'a AssemblyOs),
+    AssemblyRef(&'a AssemblyRef),
+    AssemblyRefProcessor(&'a AssemblyRefProcessor),
+    AssemblyRefOs(&'a AssemblyRefOs),
+    File(&'a File),
+    ExportedType(&'a ExportedType),
+    ManifestResource(&'a ManifestResource),
+    NestedClass(&'a NestedClass),
+    GenericParam(&'a GenericParam),
+    MethodSpec(&'a MethodSpec),
+    GenericParamConstraint(&'a GenericParamConstraint),
+    UserString(u32),
+}
+
+pub(crate) fn get_row<T>(rows: &[T], token: Token) -> Result<&T, Error> {
+    if token.rid == 0 || token.rid as usize > rows.len() {
+        return Err(Error::TokenOutOfRange {
+            token,
+            rows: rows.len(),
+        });
+    }
+    Ok(&rows[token.rid as usize - 1])
+}
+
+impl TildaStream {
+    pub fn resolve(&self, token: Token) -> Result<Row<'_>, Error> {
+        let table = match token.kind {
+            TokenKind::Table(table) => table,
+            TokenKind::UserString => return Ok(Row::UserString(token.rid)),
+        };
+        Ok(match table {
+            TableId::Module => Row::Module(get_row(&self.modules, token)?),
+            TableId::TypeRef => Row::TypeRef(get_row(&self.type_refs, token)?),
+            TableId::TypeDef => Row::TypeDef(get_row(&self.type_defs, token)?),
+            TableId::FieldPtr => Row::FieldPtr(get_row(&self.field_ptrs, token)?),
+            TableId::Field => Row::Field(get_row(&self.fields, token)?),
+            TableId::MethodPtr => Row::MethodPtr(get_row(&self.method_ptrs, token)?),
+            TableId::MethodDef => Row::MethodDef(get_row(&self.methods, token)?),
+            TableId::ParamPtr => Row::ParamPtr(get_row(&self.param_ptrs, token)?),
+            TableId::Param => Row::Param(get_row(&self.params, token)?),
+            TableId::InterfaceImpl => Row::InterfaceImpl(get_row(&self.interface_impls, token)?),
+            TableId::MemberRef => Row::MemberRef(get_row(&self.member_refs, token)?),
+            TableId::Constant => Row::Constant(get_row(&self.constants, token)?),
+            TableId::CustomAttribute => Row::CustomAttribute(get_row(&self.custom_attributes, token)?),
+            TableId::FieldMarshal => Row::FieldMarshal(get_row(&self.field_marshals, token)?),
+            TableId::DeclSecurity => Row::DeclSecurity(get_row(&self.decl_securities, token)?),
+            TableId::ClassLayout => Row::ClassLayout(get_row(&self.class_layouts, token)?),
+            TableId::FieldLayout => Row::FieldLayout(get_row(&self.field_layouts, token)?),
+            TableId::StandAloneSig => Row::StandAloneSig(get_row(&self.stand_alone_sigs, token)?),
+            TableId::EventMap => Row::EventMap(get_row(&self.event_maps, token)?),
+            TableId::EventPtr => Row::EventPtr(get_row(&self.event_ptrs, token)?),
+            TableId::Event => Row::Event(get_row(&self.events, token)?),
+            TableId::PropertyMap => Row::PropertyMap(get_row(&self.property_maps, token)?),
+            TableId::PropertyPtr => Row::PropertyPtr(get_row(&self.property_ptrs, token)?),
+            TableId::Property => Row::Property(get_row(&self.properties, token)?),
+            TableId::MethodSemantics => Row::MethodSemantics(get_row(&self.method_semantics, token)?),
+            TableId::MethodImpl => Row::MethodImpl(get_row(&self.method_impls, token)?),
+            TableId::ModuleRef => Row::ModuleRef(get_row(&self.module_refs, token)?),
+            TableId::TypeSpec => Row::TypeSpec(get_row(&self.type_specs, token)?),
+            TableId::ImplMap => Row::ImplMap(get_row(&self.impl_maps, token)?),
+            TableId::FieldRva => Row::FieldRva(get_row(&self.field_rvas, token)?),
+            TableId::EncLog => Row::EncLog(get_row(&self.enc_logs, token)?),
+            TableId::EncMap => Row::EncMap(get_row(&self.enc_maps, token)?),
+            TableId::Assembly => Row::Assembly(get_row(&self.assemblies, token)?),
+            TableId::AssemblyProcessor => Row::AssemblyProcessor(get_row(&self.assembly_processors, token)?),
+            TableId::AssemblyOs => Row::AssemblyOs(get_row(&self.assembly_oses, token)?),
+            TableId::AssemblyRef => Row::AssemblyRef(get_row(&self.assembly_refs, token)?),
+            TableId::AssemblyRefProcessor => Row::AssemblyRefProcessor(get_row(&self.assembly_ref_processors, token)?),
+            TableId::AssemblyRefOs => Row::AssemblyRefOs(get_row(&self.assembly_ref_oses, token)?),
+            TableId::File => Row::File(get_row(&self.files, token)?),
+            TableId::ExportedType => Row::ExportedType(get_row(&self.exported_types, token)?),
+            TableId::ManifestResource => Row::ManifestResource(get_row(&self.manifest_resources, token)?),
+            TableId::NestedClass => Row::NestedClass(get_row(&self.nested_classes, token)?),
+            TableId::GenericParam => Row::GenericParam(get_row(&self.generic_params, token)?),
+            TableId::MethodSpec => Row::MethodSpec(get_row(&self.method_specs, token)?),
+            TableId::GenericParamConstraint => {
+                Row::GenericParamConstraint(get_row(&self.generic_param_constraints, token)?)
+            }
+        })
+    }
+
+    pub fn row_count(&self, table: TableId) -> usize {
+        match table {
+            TableId::Module => self.modules.len(),
+            TableId::TypeRef => self.type_refs.len(),
+            TableId::TypeDef => self.type_defs.len(),
+            TableId::FieldPtr => self.field_ptrs.len(),
+            TableId::Field => self.fields.len(),
+            TableId::MethodPtr => self.method_ptrs.len(),
+            TableId::MethodDef => self.methods.len(),
+            TableId::ParamPtr => self.param_ptrs.len(),
+            TableId::Param => self.params.len(),
+            TableId::InterfaceImpl => self.interface_impls.len(),
+            TableId::MemberRef => self.member_refs.len(),
+            TableId::Constant => self.constants.len(),
+            TableId::CustomAttribute => self.custom_attributes.len(),
+            TableId::FieldMarshal => self.field_marshals.len(),
+            TableId::DeclSecurity => self.decl_securities.len(),
+            TableId::ClassLayout => self.class_layouts.len(),
+            TableId::FieldLayout => self.field_layouts.len(),
+            TableId::StandAloneSig => self.stand_alone_sigs.len(),
+            TableId::EventMap => self.event_maps.len(),
+            TableId::EventPtr => self.event_ptrs.len(),
+            TableId::Event => self.events.len(),
+            TableId::PropertyMap => self.property_maps.len(),
+            TableId::PropertyPtr => self.property_ptrs.len(),
+            TableId::Property => self.properties.len(),
+            TableId::MethodSemantics => self.method_semantics.len(),
+            TableId::MethodImpl => self.method_impls.len(),
+            TableId::ModuleRef => self.module_refs.len(),
+            TableId::TypeSpec => self.type_specs.len(),
+            TableId::ImplMap => self.impl_maps.len(),
+            TableId::FieldRva => self.field_rvas.len(),
+            TableId::EncLog => self.enc_logs.len(),
+            TableId::EncMap => self.enc_maps.len(),
+            TableId::Assembly => self.assemblies.len(),
+            TableId::AssemblyProcessor => self.assembly_processors.len(),
+            TableId::AssemblyOs => self.assembly_oses.len(),
+            TableId::AssemblyRef => self.assembly_refs.len(),
+            TableId::AssemblyRefProcessor => self.assembly_ref_processors.len(),
+            TableId::AssemblyRefOs => self.assembly_ref_oses.len(),
+            TableId::File => self.files.len(),
+            TableId::ExportedType => self.exported_types.len(),
+            TableId::ManifestResource => self.manifest_resources.len(),
+            TableId::NestedClass => self.nested_classes.len(),
+            TableId::GenericParam => self.generic_params.len(),
+            TableId::MethodSpec => self.method_specs.len(),
+            TableId::GenericParamConstraint => self.generic_param_constraints.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_and_encode() {
+        let token = Token::decode(0x0600_0012).unwrap();
+        assert_eq!(token, Token::new(TokenKind::Table(TableId::MethodDef), 0x12));
+        assert_eq!(token.raw(), 0x0600_0012);
+
+        let token = Token::decode(0x7000_0001).unwrap();
+        assert_eq!(token, Token::new(TokenKind::UserString, 1));
+        assert_eq!(token.raw(), 0x7000_0001);
+
+        assert!(Token::decode(0x2d00_0001).is_err());
+    }
+
+    #[test]
+    fn rows_are_one_based() {
+        let rows = [10, 20];
+        let token = |rid| Token::new(TokenKind::Table(TableId::Field), rid);
+        assert_eq!(get_row(&rows, token(1)).unwrap(), &10);
+        assert_eq!(get_row(&rows, token(2)).unwrap(), &20);
+        assert!(get_row(&rows, token(0)).is_err());
+        assert!(get_row(&rows, token(3)).is_err());
+    }
+}
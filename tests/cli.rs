@@ -0,0 +1,89 @@
+//! Runs the `dotnet-rs` subcommands on `tests/fixtures/hierarchy.exe` and checks their output.
+
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn dotnet_rs(args: &[&str]) -> Output {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hierarchy.exe");
+    Command::new(env!("CARGO_BIN_EXE_dotnet-rs"))
+        .args(args)
+        .arg(fixture)
+        .output()
+        .unwrap()
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = dotnet_rs(args);
+    assert!(
+        output.status.success(),
+        "{:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn json(args: &[&str]) -> Value {
+    serde_json::from_str(&stdout(args)).unwrap()
+}
+
+#[test]
+fn tables() {
+    let listing = stdout(&["tables"]);
+    let lines = listing.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0].split_whitespace().collect::<Vec<_>>(),
+        ["id", "name", "rows", "sorted"]
+    );
+    assert!(lines.contains(&"0x02  TypeDef        6     false"));
+    assert!(lines.contains(&"0x29  NestedClass    1     false"));
+    // Empty tables aren't listed
+    assert!(!listing.contains("Field "));
+
+    let counts = json(&["--json", "tables"]);
+    let type_defs = counts
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|x| x["name"] == json!("TypeDef"));
+    assert_eq!(type_defs.unwrap()["rows"], json!(6));
+}
+
+#[test]
+fn table_filter() {
+    let listing = stdout(&["tables", "--table", "TypeDef"]);
+    let lines = listing.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2 + 6);
+    assert_eq!(
+        lines[5].split_whitespace().collect::<Vec<_>>(),
+        ["4", "1048577", "75", "63", "{\"TypeDef\":3}", "1", "3"]
+    );
+
+    assert_eq!(
+        json(&["--json", "tables", "--table", "NestedClass"]),
+        json!([{ "rid": 1, "nested_class": 5, "enclosing_class": 4 }])
+    );
+
+    let output = dotnet_rs(&["tables", "--table", "Bogus"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown table Bogus"));
+}
+
+#[test]
+fn entry_point() {
+    assert_eq!(
+        json(&["--json", "entrypoint"]),
+        json!({
+            "token": "0x06000007",
+            "name": "Main",
+            "type": "Shapes.Program",
+            "signature": "int32 Shapes.Program::Main()",
+            "rva": "0x00002065",
+        })
+    );
+    let listing = stdout(&["entrypoint"]);
+    assert!(listing
+        .lines()
+        .any(|x| x.split_whitespace().collect::<Vec<_>>() == ["name", "Main"]));
+}
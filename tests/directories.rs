@@ -0,0 +1,104 @@
+//! Reads the CLI header of `tests/fixtures/resources.exe` and the directories it points to.
+
+use dotnet_rs::cli_header::{COR_VTABLE_32BIT, COR_VTABLE_FROM_UNMANAGED};
+use dotnet_rs::coded_index::Implementation;
+use dotnet_rs::platform::Platform;
+use dotnet_rs::tables::ManifestResource;
+use dotnet_rs::{Assembly, Error};
+use goblin::pe::data_directories::DataDirectory;
+use std::path::Path;
+
+fn fixture(name: &str) -> Assembly<'static> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    Assembly::from_path(path.with_extension("exe")).unwrap()
+}
+
+#[test]
+fn flags() {
+    let assembly = fixture("resources");
+    let header = assembly.cli_header();
+    assert!(!header.is_il_only());
+    assert!(header.is_32bit_required());
+    assert!(!header.is_32bit_preferred());
+    assert!(!header.is_strong_name_signed());
+    assert!(!header.has_native_entry_point());
+    assert!(header.track_debug_data());
+    assert_eq!(header.entry_point_token(), Some(0x0600_0001));
+    assert_eq!(header.entry_point_rva(), None);
+    assert_eq!(assembly.platform(), Platform::X86);
+
+    let header = fixture("fib");
+    let header = header.cli_header();
+    assert!(header.is_il_only());
+    assert!(!header.is_32bit_required());
+    assert!(!header.track_debug_data());
+}
+
+#[test]
+fn directories() {
+    let assembly = fixture("resources");
+    let header = assembly.cli_header();
+    let metadata = assembly.directory(&header.metadata).unwrap().unwrap();
+    assert_eq!(metadata.len(), header.metadata.size as usize);
+    assert_eq!(&metadata[..4], b"BSJB");
+    assert!(assembly.strong_name_signature().unwrap().is_none());
+    assert!(assembly.code_manager_table().unwrap().is_none());
+    assert!(assembly.export_address_table_jumps().unwrap().is_none());
+    assert!(assembly.managed_native_header().unwrap().is_none());
+
+    let directory = |virtual_address, size| DataDirectory { virtual_address, size };
+    // A size of 0 means there is no directory, wherever it points
+    assert_eq!(
+        assembly
+            .directory(&directory(header.metadata.virtual_address, 0))
+            .unwrap(),
+        None
+    );
+    match assembly.directory(&directory(header.metadata.virtual_address, 0x10_0000)) {
+        Err(Error::OutOfBounds { what: "Directory", .. }) => {}
+        other => panic!("expected the directory to be out of bounds, got {:?}", other),
+    }
+    match assembly.directory(&directory(0x7000_0000, 4)) {
+        Err(Error::UnmappedRva(0x7000_0000)) => {}
+        other => panic!("expected the rva not to be mapped, got {:?}", other),
+    }
+}
+
+#[test]
+fn vtable_fixups() {
+    let fixups = fixture("resources").vtable_fixups().unwrap();
+    assert_eq!(fixups.len(), 1);
+    assert_eq!(fixups[0].count, 2);
+    assert_eq!(fixups[0].kind, COR_VTABLE_32BIT | COR_VTABLE_FROM_UNMANAGED);
+    // The slots hold the tokens of the methods until the loader patches them
+    let assembly = fixture("resources");
+    let slots = assembly.data_at_rva(fixups[0].rva).unwrap();
+    assert_eq!(&slots[..8], &[2, 0, 0, 6, 3, 0, 0, 6]);
+    assert!(fixture("fib").vtable_fixups().unwrap().is_empty());
+}
+
+#[test]
+fn manifest_resources() {
+    let assembly = fixture("resources");
+    let resources = &assembly.tables().manifest_resources;
+    let read = |index: usize| {
+        let name = assembly.strings().get(resources[index].name).unwrap();
+        (name, assembly.manifest_resource_data(&resources[index]).unwrap())
+    };
+    assert_eq!(read(0), ("greeting.txt", Some(&b"hello"[..])));
+    assert_eq!(read(1), ("data.bin", Some(&[1, 2, 3, 4, 5, 6, 7, 8, 9][..])));
+    assert_eq!(assembly.resources().unwrap().map(<[u8]>::len), Some(32));
+    // Those of other files aren't read
+    let linked = ManifestResource {
+        implementation: Implementation::File(1),
+        ..resources[0]
+    };
+    assert_eq!(assembly.manifest_resource_data(&linked).unwrap(), None);
+
+    // Resources of an image without a resources directory are out of bounds
+    let other = fixture("fib");
+    match other.manifest_resource_data(&resources[0]) {
+        Err(Error::OutOfBounds { what: "Resource", .. }) => {}
+        other => panic!("expected the resource to be out of bounds, got {:?}", other),
+    }
+}
@@ -0,0 +1,27 @@
+//! Disassembles fixtures and compares the listing with the `<name>.il` next to them.
+//!
+//! `finally.il` covers locals, branch labels and nested `.try`/`finally` blocks, `hierarchy.il` nested
+//! classes, interfaces and the members MemberRefs resolve to.
+
+use dotnet_rs::disasm::disassemble;
+use dotnet_rs::Assembly;
+use std::path::Path;
+
+fn check(name: &str) {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let assembly = Assembly::from_path(fixtures.join(name).with_extension("exe")).unwrap();
+    let mut listing = Vec::new();
+    disassemble(&assembly, &mut listing).unwrap();
+    let expected = std::fs::read_to_string(fixtures.join(name).with_extension("il")).unwrap();
+    assert_eq!(String::from_utf8(listing).unwrap(), expected, "{}", name);
+}
+
+#[test]
+fn protected_regions() {
+    check("finally");
+}
+
+#[test]
+fn hierarchy() {
+    check("hierarchy");
+}
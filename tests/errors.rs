@@ -0,0 +1,89 @@
+//! Loads damaged copies of `tests/fixtures/hierarchy.exe` and checks the error variant each damage gives.
+
+use dotnet_rs::tables::TableId;
+use dotnet_rs::token::{Token, TokenKind};
+use dotnet_rs::{Assembly, Error};
+use std::path::Path;
+
+fn image() -> Vec<u8> {
+    std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hierarchy.exe")).unwrap()
+}
+
+/// File offset of the stream `name`.
+fn stream_offset(data: &[u8], name: &str) -> usize {
+    let assembly = Assembly::from_bytes(data).unwrap();
+    let root = assembly
+        .rva_to_offset(assembly.cli_header().metadata.virtual_address)
+        .unwrap();
+    let header = assembly.metadata_root().unwrap();
+    let header = header.stream_headers.iter().find(|x| x.name == name).unwrap();
+    root + header.offset as usize
+}
+
+/// Renames the stream `name` in the stream headers, keeping its length.
+fn rename_stream(data: &mut [u8], name: &str, replacement: &str) {
+    let name = format!("{}\0", name);
+    let start = data.windows(name.len()).position(|x| x == name.as_bytes()).unwrap();
+    data[start..start + replacement.len()].copy_from_slice(replacement.as_bytes());
+}
+
+#[test]
+fn missing_streams() {
+    let mut data = image();
+    rename_stream(&mut data, "#Strings", "#Strangs");
+    match Assembly::from_vec(data) {
+        Err(Error::MissingStream("#Strings")) => {}
+        other => panic!("expected #Strings to be missing, got {:?}", other.err()),
+    }
+
+    let mut data = image();
+    rename_stream(&mut data, "#~", "#!");
+    match Assembly::from_vec(data) {
+        Err(Error::MissingStream("#~")) => {}
+        other => panic!("expected #~ to be missing, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn truncated_table() {
+    let mut data = image();
+    // Row counts follow the 24 byte header, that of NestedClass, the last of the 9 tables, is raised
+    let counts = stream_offset(&data, "#~") + 24;
+    data[counts + 32..counts + 36].copy_from_slice(&0xffffu32.to_le_bytes());
+    match Assembly::from_vec(data) {
+        Err(Error::TruncatedTable {
+            table: TableId::NestedClass,
+            row,
+            ..
+        }) => assert!(row > 1, "row {}", row),
+        other => panic!("expected the NestedClass table to be truncated, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn tokens_out_of_range() {
+    let data = image();
+    let assembly = Assembly::from_bytes(&data).unwrap();
+    for &(table, rows) in &[(TableId::TypeDef, 6), (TableId::MethodDef, 7), (TableId::Field, 0)] {
+        for &rid in &[0, rows as u32 + 1] {
+            let token = Token::new(TokenKind::Table(table), rid);
+            match assembly.resolve(token) {
+                Err(Error::TokenOutOfRange { token: x, rows: y }) => assert_eq!((x, y), (token, rows)),
+                other => panic!("expected {:?} to be out of range, got {:?}", token, other.err()),
+            }
+        }
+    }
+
+    // An entry point past the MethodDef table, the fixtures have their CLI header at rva 0x2000
+    let mut data = image();
+    let header = {
+        let assembly = Assembly::from_bytes(&data).unwrap();
+        assembly.rva_to_offset(0x2000).unwrap()
+    };
+    data[header + 20..header + 24].copy_from_slice(&0x0600_0063u32.to_le_bytes());
+    let assembly = Assembly::from_vec(data).unwrap();
+    match assembly.entry_point() {
+        Err(Error::TokenOutOfRange { token, rows: 7 }) => assert_eq!(token.raw(), 0x0600_0063),
+        other => panic!("expected the entry point to be out of range, got {:?}", other.err()),
+    }
+}
@@ -0,0 +1,80 @@
+//! Exports fixtures and checks the JSON document against the layout of `schema/metadata.schema.json`.
+
+use dotnet_rs::export::{export, SCHEMA_VERSION};
+use dotnet_rs::Assembly;
+use serde_json::{json, Value};
+use std::path::Path;
+
+fn exported(name: &str) -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    let assembly = Assembly::from_path(path.with_extension("exe")).unwrap();
+    serde_json::to_value(export(&assembly).unwrap()).unwrap()
+}
+
+#[test]
+fn document() {
+    let document = exported("hierarchy");
+    let mut keys = document
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    keys.sort_unstable();
+    assert_eq!(
+        keys,
+        [
+            "cli_header",
+            "heaps",
+            "image",
+            "metadata_root",
+            "schema_version",
+            "tables"
+        ]
+    );
+    assert_eq!(document["schema_version"], json!(SCHEMA_VERSION));
+    assert_eq!(
+        document["image"],
+        json!({ "machine": 0x14c, "platform": "AnyCpu", "pe32_plus": false })
+    );
+}
+
+#[test]
+fn rows() {
+    let document = exported("hierarchy");
+    let outer = &document["tables"]["rows"]["TypeDef"][3];
+    assert_eq!(outer["rid"], json!(4));
+    assert_eq!(outer["token"], json!(0x0200_0004));
+    assert_eq!(outer["display"], json!("Shapes.Outer"));
+    assert_eq!(outer["type_name"], json!("Outer"));
+    assert_eq!(outer["type_namespace"], json!("Shapes"));
+    assert_eq!(
+        outer["extends"],
+        json!({ "table": "TypeDef", "rid": 3, "display": "Shapes.Base" })
+    );
+    let constructor = &document["tables"]["rows"]["MemberRef"][0];
+    assert_eq!(
+        constructor["class"],
+        json!({ "table": "TypeRef", "rid": 1, "display": "[mscorlib]System.Object" })
+    );
+    assert_eq!(constructor["signature"], json!("200001"));
+    // Tables without rows are left out
+    assert!(document["tables"]["rows"].get("Field").is_none());
+}
+
+#[test]
+fn heaps() {
+    let document = exported("strings");
+    let heaps = &document["heaps"];
+    let user_strings = heaps["user_strings"].as_array().unwrap();
+    assert!(user_strings.contains(&json!({ "offset": 1, "value": "hello" })));
+    assert!(user_strings.contains(&json!({ "offset": 13, "value": "world" })));
+    let strings = heaps["strings"].as_array().unwrap();
+    assert_eq!(strings[0], json!({ "offset": 0, "value": "" }));
+    assert!(strings.iter().any(|x| x["value"] == json!("Program")));
+    assert_eq!(heaps["blobs"][0], json!({ "offset": 0, "data": "" }));
+    assert_eq!(
+        heaps["guids"],
+        json!([{ "index": 1, "value": "03020100-0504-0706-0809-0a0b0c0d0e0f" }])
+    );
+}
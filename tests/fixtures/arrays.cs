@@ -0,0 +1,21 @@
+// Arrays: newarr, stelem and ldelem of several element types, ldlen, and narrowing on store.
+// Exit code: 5 + 16 + 100 + 1 + 1 + 44 = 167
+class Program
+{
+    static int Main()
+    {
+        int[] squares = new int[5];
+        for (int i = 0; i < squares.Length; i++)
+        {
+            squares[i] = i * i;
+        }
+        string[] names = new string[2];
+        names[1] = "x";
+        long[] longs = new long[1];
+        longs[0] = 1L << 40;
+        byte[] bytes = new byte[1];
+        bytes[0] = unchecked((byte)300);
+        return squares.Length + squares[4] + (names[0] == null ? 100 : 0) + (names[1] != null ? 1 : 0)
+            + (int)(longs[0] >> 40) + bytes[0];
+    }
+}
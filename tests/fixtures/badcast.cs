@@ -0,0 +1,51 @@
+// castclass to a type the object isn't.
+// Fails with an unhandled System.InvalidCastException.
+class Program
+{
+    static int Main()
+    {
+        object d = new Derived();
+        Impl impl = (Impl)d;
+        return 0;
+    }
+}
+
+abstract class Base
+{
+    public virtual int Get() => 1;
+    public abstract int Abs();
+    public virtual int Fin() => 20;
+}
+
+class Derived : Base
+{
+    public override int Get() => 2;
+    public override int Abs() => 10;
+    public new virtual int Fin() => 100;
+}
+
+interface IFace
+{
+    int Val();
+    int Other();
+    int Def() => 7;
+}
+
+class Impl : IFace
+{
+    public int Val() => 3;
+    int IFace.Other() => 5;
+}
+
+struct Point : IFace
+{
+    public int x;
+
+    public int Val() => x;
+
+    public int Other()
+    {
+        x = 1;
+        return x;
+    }
+}
@@ -0,0 +1,20 @@
+// Generics: Use<Other> breaks the base type constraint of the generic method. C# refuses to compile
+// it, the image calls it anyway, so Use<Other> fails to load while Use<Derived> runs.
+class Program
+{
+    static int Use<T>(T value) where T : Base => 1;
+
+    static int Main() => Use(new Derived()) + Use(new Other());
+}
+
+class Base
+{
+}
+
+class Derived : Base
+{
+}
+
+class Other
+{
+}
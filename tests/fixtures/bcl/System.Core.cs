@@ -0,0 +1,24 @@
+// The part of System.Core the images in this folder use, against the mscorlib.dll next to it.
+using System.Collections.Generic;
+
+namespace System.Linq
+{
+    public static class Enumerable
+    {
+        public static int Count<TSource>(IEnumerable<TSource> source)
+        {
+            IEnumerator<TSource> e = source.GetEnumerator();
+            int count = 0;
+            while (e.MoveNext())
+                count = count + 1;
+            return count;
+        }
+
+        public static TSource First<TSource>(IEnumerable<TSource> source)
+        {
+            IEnumerator<TSource> e = source.GetEnumerator();
+            e.MoveNext();
+            return e.Current;
+        }
+    }
+}
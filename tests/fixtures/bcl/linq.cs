@@ -0,0 +1,16 @@
+// Generics: generic methods of System.Core.dll called over a List<int> of mscorlib.dll, both next to
+// this image.
+// Exit code: 2 * 10 + 3 = 23
+using System.Collections.Generic;
+using System.Linq;
+
+class Program
+{
+    static int Main()
+    {
+        List<int> list = new List<int>();
+        list.Add(3);
+        list.Add(2);
+        return list.Count() * 10 + list.First();
+    }
+}
@@ -0,0 +1,13 @@
+// Generics: List<T> from the mscorlib.dll next to this image.
+// Exit code: 1
+using System.Collections.Generic;
+
+class Program
+{
+    static int Main()
+    {
+        List<int> list = new List<int>();
+        list.Add(1);
+        return list.Count;
+    }
+}
@@ -0,0 +1,75 @@
+// The part of mscorlib the images in this folder use. Compiled with /nostdlib, it defines
+// System.Object itself.
+namespace System
+{
+    public class Object
+    {
+        public Object() { }
+    }
+
+    public abstract class ValueType { }
+}
+
+namespace System.Collections.Generic
+{
+    public interface IEnumerator<T>
+    {
+        T Current { get; }
+
+        bool MoveNext();
+    }
+
+    public interface IEnumerable<T>
+    {
+        IEnumerator<T> GetEnumerator();
+    }
+
+    public class List<T> : IEnumerable<T>
+    {
+        private T[] _items;
+        private int _size;
+
+        public List()
+        {
+            _items = new T[4];
+        }
+
+        public int Count => _size;
+
+        public void Add(T item)
+        {
+            // Doubles _items when it's full
+            if (_size >= _items.Length)
+            {
+                T[] items = new T[_items.Length * 2];
+                for (int i = 0; i < _size; i++)
+                    items[i] = _items[i];
+                _items = items;
+            }
+            _items[_size] = item;
+            _size = _size + 1;
+        }
+
+        public IEnumerator<T> GetEnumerator() => new Enumerator(this);
+
+        private sealed class Enumerator : IEnumerator<T>
+        {
+            private List<T> _list;
+            private int _index;
+
+            internal Enumerator(List<T> list)
+            {
+                _list = list;
+                _index = -1;
+            }
+
+            public T Current => _list._items[_index];
+
+            public bool MoveNext()
+            {
+                _index = _index + 1;
+                return _index < _list._size;
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+// Arrays: reading one past the last element.
+// Fails with an unhandled System.IndexOutOfRangeException.
+class Program
+{
+    static int Main() => new int[3][3];
+}
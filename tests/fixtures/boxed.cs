@@ -0,0 +1,54 @@
+// Interface calls on a boxed struct see and change the box, not the struct it was boxed from.
+// Exit code: 1 + 42 + 1 = 44
+class Program
+{
+    static int Main()
+    {
+        Point p = default;
+        p.x = 42;
+        IFace boxed = p;
+        boxed.Val();
+        boxed.Other();
+        return boxed.Val() + 42 + ((IFace)p).Other();
+    }
+}
+
+abstract class Base
+{
+    public virtual int Get() => 1;
+    public abstract int Abs();
+    public virtual int Fin() => 20;
+}
+
+class Derived : Base
+{
+    public override int Get() => 2;
+    public override int Abs() => 10;
+    public new virtual int Fin() => 100;
+}
+
+interface IFace
+{
+    int Val();
+    int Other();
+    int Def() => 7;
+}
+
+class Impl : IFace
+{
+    public int Val() => 3;
+    int IFace.Other() => 5;
+}
+
+struct Point : IFace
+{
+    public int x;
+
+    public int Val() => x;
+
+    public int Other()
+    {
+        x = 1;
+        return x;
+    }
+}
@@ -0,0 +1,14 @@
+// Calls: a local passed by reference and updated through ldind/stind.
+// Exit code: 42
+class Program
+{
+    static void Inc(ref int x) => x += 1;
+
+    static int Main()
+    {
+        int x = 40;
+        Inc(ref x);
+        Inc(ref x);
+        return x;
+    }
+}
@@ -0,0 +1,61 @@
+// constrained. callvirt: a struct method runs on the struct itself, a default interface method on a box
+// of it, and a reference is dereferenced for a virtual call. The image makes the calls in Main with
+// constrained. Point and constrained. Base, as the generic helpers do.
+// Exit code: 1 + 7 + 1 + 2 = 11
+class Program
+{
+    static int Other<T>(ref T value) where T : IFace => value.Other();
+
+    static int Def<T>(ref T value) where T : IFace => value.Def();
+
+    static int Get<T>(ref T value) where T : Base => value.Get();
+
+    static int Main()
+    {
+        Point p = default;
+        p.x = 42;
+        int result = Other(ref p) + Def(ref p) + p.x;
+        Derived d = new Derived();
+        return result + Get(ref d);
+    }
+}
+
+abstract class Base
+{
+    public virtual int Get() => 1;
+    public abstract int Abs();
+    public virtual int Fin() => 20;
+}
+
+class Derived : Base
+{
+    public override int Get() => 2;
+    public override int Abs() => 10;
+    public new virtual int Fin() => 100;
+}
+
+interface IFace
+{
+    int Val();
+    int Other();
+    int Def() => 7;
+}
+
+class Impl : IFace
+{
+    public int Val() => 3;
+    int IFace.Other() => 5;
+}
+
+struct Point : IFace
+{
+    public int x;
+
+    public int Val() => x;
+
+    public int Other()
+    {
+        x = 1;
+        return x;
+    }
+}
@@ -0,0 +1,17 @@
+// Resolver: references to library.dll, to Lib.Moved through forwarder.dll, which defined it when this
+// was compiled, and to a member the library doesn't have. The image also has an AssemblyRef named
+// `../fixtures/library` with a TypeRef to Lib.Widget, which C# can't write.
+class Program
+{
+    static int Main()
+    {
+        Lib.Widget widget = new Lib.Widget();
+        widget.size = 1;
+        widget.next = widget;
+        new Lib.Widget.Part();
+        new Lib.Moved();
+        return widget.Get(2) + (int)widget.Get(3L) + Lib.Widget.Twice(4);
+    }
+
+    // widget.Missing() is a MemberRef to a method Lib.Widget no longer has, nothing calls it
+}
@@ -0,0 +1,11 @@
+// Arithmetic: narrowing and floating point conversions.
+// Exit code: -56 + 255 + 3 = 202
+class Program
+{
+    static int Main()
+    {
+        int big = 200, minusOne = -1;
+        double d = 3.7;
+        return (sbyte)big + (byte)minusOne + (int)d;
+    }
+}
@@ -0,0 +1,24 @@
+// Arrays: isinst and castclass to array types check the element type. Arrays of reference types are covariant, and
+// arrays of value types stored the same way are interchangeable.
+// Exit code: 1 + 2 + 8 + 64 + 100 = 175
+class Program
+{
+    static int Main()
+    {
+        Animal[] animals = new Dog[1];
+        object numbers = new int[1];
+        object[] objects = new string[1];
+        objects[0] = "x";
+        return (animals is Dog[] ? 1 : 0) + (animals is Animal[] ? 2 : 0) + ((object)animals is string[] ? 4 : 0)
+            + (numbers is uint[] ? 8 : 0) + (numbers is long[] ? 16 : 0) + (numbers is object[] ? 32 : 0)
+            + ((object)animals is object[] ? 64 : 0) + ((Animal[])(object)animals).Length * 100;
+    }
+}
+
+class Animal
+{
+}
+
+class Dog : Animal
+{
+}
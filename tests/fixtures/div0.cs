@@ -0,0 +1,10 @@
+// Arithmetic: integer division by zero.
+// Fails with an unhandled System.DivideByZeroException.
+class Program
+{
+    static int Main()
+    {
+        int one = 1, zero = 0;
+        return one / zero;
+    }
+}
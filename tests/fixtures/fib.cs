@@ -0,0 +1,8 @@
+// Calls: recursion through `call` with arguments and return values.
+// Exit code: 6765 & 0xff = 109
+class Program
+{
+    static int Fib(int n) => n < 2 ? n : Fib(n - 1) + Fib(n - 2);
+
+    static int Main() => Fib(20) & 0xff;
+}
@@ -0,0 +1,26 @@
+// Finalization: an unreachable object with a finalizer gets it run after a collection.
+// Exit code: 1
+using System;
+using System.Runtime.InteropServices;
+
+class Program
+{
+    public static int s_finalized;
+    static Node s_kept;
+    static int s_counter = 100;
+
+    static int Main()
+    {
+        new Node();
+        GC.Collect();
+        GC.WaitForPendingFinalizers();
+        return s_finalized;
+    }
+}
+
+class Node
+{
+    Node next;
+
+    ~Node() => Program.s_finalized++;
+}
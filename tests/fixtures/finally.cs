@@ -0,0 +1,26 @@
+// leave and endfinally: leaving nested protected regions runs both finally blocks, innermost first.
+// Exit code: (2 + 1) * 10 + 5 = 35
+class Program
+{
+    static int Main()
+    {
+        int x = 0;
+        try
+        {
+            x = 2;
+            try
+            {
+                x = x + 1;
+            }
+            finally
+            {
+                x = x * 10;
+            }
+        }
+        finally
+        {
+            x = x + 5;
+        }
+        return x;
+    }
+}
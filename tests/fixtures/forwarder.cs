@@ -0,0 +1,2 @@
+// Resolver: Lib.Moved used to live here and is forwarded to library.dll now.
+[assembly: System.Runtime.CompilerServices.TypeForwardedTo(typeof(Lib.Moved))]
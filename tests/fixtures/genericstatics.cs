@@ -0,0 +1,18 @@
+// Generics: Counter<int> and Counter<string> have static fields and type initializers of their own.
+// Exit code: 11 + 12 + 11 = 34
+class Program
+{
+    static int Main() => Counter<int>.Next() + Counter<int>.Next() + Counter<string>.Next();
+}
+
+static class Counter<T>
+{
+    static int s_count;
+
+    static Counter()
+    {
+        s_count = 10;
+    }
+
+    public static int Next() => ++s_count;
+}
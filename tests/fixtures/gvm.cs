@@ -0,0 +1,25 @@
+// Generics: generic virtual methods, an abstract one and a virtual one, dispatched to the overrides
+// in Derived for each instantiation.
+// Exit code: 40 + 2 + 100 = 142
+class Program
+{
+    static int Main()
+    {
+        Base b = new Derived();
+        return b.Pick<int>(1, 40) + b.Id<string>() + ((object)b.Pick<string>("x", "y") == "y" ? 100 : 0);
+    }
+}
+
+abstract class Base
+{
+    public abstract T Pick<T>(T a, T b);
+
+    public virtual int Id<T>() => 1;
+}
+
+class Derived : Base
+{
+    public override T Pick<T>(T a, T b) => b;
+
+    public override int Id<T>() => 2;
+}
@@ -0,0 +1,31 @@
+// Type hierarchy: a nested type, a base class and interfaces from the image and from mscorlib.
+// Exit code: 4
+using System;
+
+namespace Shapes
+{
+    public interface IShape
+    {
+        int Area();
+    }
+
+    public abstract class Base
+    {
+    }
+
+    public class Outer : Base, IShape, IDisposable
+    {
+        public int Area() => 4;
+
+        public void Dispose() { }
+
+        class Inner
+        {
+        }
+    }
+
+    class Program
+    {
+        static int Main() => new Outer().Area();
+    }
+}
@@ -0,0 +1,19 @@
+// Layout: a struct one byte larger than an object may be. C# caps StructLayout.Size at int.MaxValue,
+// the image sets the ClassLayout size to 0x80000000, so Huge fails to load.
+using System.Runtime.InteropServices;
+
+class Program
+{
+    static int Main()
+    {
+        Huge huge;
+        huge.x = 0;
+        return huge.x;
+    }
+}
+
+[StructLayout(LayoutKind.Sequential, Size = int.MaxValue)]
+struct Huge
+{
+    public int x;
+}
@@ -0,0 +1,19 @@
+// Statics: only a `void .cctor()` is a type initializer. C# can't declare any other, in the image
+// Odd's .cctor returns an int32 and so never runs, Normal's runs before its field is read.
+// Exit code: 0 + 7 = 7
+class Program
+{
+    static int Main() => Odd.s_value + Normal.s_value;
+}
+
+static class Odd
+{
+    public static int s_value;
+
+    // static int .cctor() { s_value = 100; return 5; }
+}
+
+static class Normal
+{
+    public static int s_value = 7;
+}
@@ -0,0 +1,50 @@
+// Interface calls: an implicit implementation, an explicit one by MethodImpl and a default interface method.
+// Exit code: 3 + 5 * 10 + 7 * 20 = 193
+class Program
+{
+    static int Main()
+    {
+        IFace i = new Impl();
+        return i.Val() + i.Other() * 10 + i.Def() * 20;
+    }
+}
+
+abstract class Base
+{
+    public virtual int Get() => 1;
+    public abstract int Abs();
+    public virtual int Fin() => 20;
+}
+
+class Derived : Base
+{
+    public override int Get() => 2;
+    public override int Abs() => 10;
+    public new virtual int Fin() => 100;
+}
+
+interface IFace
+{
+    int Val();
+    int Other();
+    int Def() => 7;
+}
+
+class Impl : IFace
+{
+    public int Val() => 3;
+    int IFace.Other() => 5;
+}
+
+struct Point : IFace
+{
+    public int x;
+
+    public int Val() => x;
+
+    public int Other()
+    {
+        x = 1;
+        return x;
+    }
+}
@@ -0,0 +1,56 @@
+// Layout: explicit field offsets, and sequential structs with and without packing, read back through
+// overlapping fields.
+// Exit code: 4 + 10 + 20 * 2 + 100 * 2 = 254
+using System.Runtime.InteropServices;
+
+class Program
+{
+    static int Main()
+    {
+        Bytes bytes;
+        bytes.i = 0x01020304;
+        Packed packed;
+        packed.a = 1;
+        packed.b = 2;
+        Padded padded;
+        padded.a = 1;
+        padded.b = 2;
+        Overlay first;
+        first.raw = 0;
+        first.packed = packed;
+        Overlay second;
+        second.raw = 0;
+        second.padded = padded;
+        // b follows a right away in Packed and starts at offset 4 in Padded
+        return bytes.b0 + 10 * bytes.b3 + 20 * (int)(first.raw >> 8) + 100 * (int)(second.raw >> 32);
+    }
+}
+
+[StructLayout(LayoutKind.Explicit)]
+struct Bytes
+{
+    [FieldOffset(0)] public int i;
+    [FieldOffset(0)] public byte b0;
+    [FieldOffset(3)] public byte b3;
+}
+
+[StructLayout(LayoutKind.Sequential, Pack = 1)]
+struct Packed
+{
+    public byte a;
+    public int b;
+}
+
+struct Padded
+{
+    public byte a;
+    public int b;
+}
+
+[StructLayout(LayoutKind.Explicit)]
+struct Overlay
+{
+    [FieldOffset(0)] public Packed packed;
+    [FieldOffset(0)] public Padded padded;
+    [FieldOffset(0)] public long raw;
+}
@@ -0,0 +1,23 @@
+// Resolver: the assembly consumer.exe refers to, directly and through forwarder.dll.
+namespace Lib
+{
+    public class Widget
+    {
+        public int size;
+        public Widget next;
+
+        public int Get(int x) => x + size;
+
+        public long Get(long x) => x + size;
+
+        public static int Twice(int x) => 2 * x;
+
+        public class Part
+        {
+        }
+    }
+
+    public class Moved
+    {
+    }
+}
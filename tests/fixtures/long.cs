@@ -0,0 +1,11 @@
+// Arithmetic: zero extension to int64, a 64-bit shift and truncation back to int32.
+// Exit code: ((0xffffffff >> 16) & 0xff) - 200 = 55
+class Program
+{
+    static int Main()
+    {
+        int minusOne = -1;
+        long wide = (uint)minusOne;
+        return ((int)(wide >> 16) & 0xff) - 200;
+    }
+}
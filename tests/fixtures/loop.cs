@@ -0,0 +1,15 @@
+// Branches: a backward conditional branch over locals.
+// Exit code: 1 + 2 + ... + 10 = 55
+class Program
+{
+    static int Main()
+    {
+        int sum = 0, i = 1;
+        do
+        {
+            sum += i;
+            i++;
+        } while (i <= 10);
+        return sum;
+    }
+}
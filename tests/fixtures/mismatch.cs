@@ -0,0 +1,14 @@
+// Arrays: storing an object into an object[] that is a string[] throws System.ArrayTypeMismatchException.
+class Program
+{
+    static int Main()
+    {
+        object[] objects = new string[1];
+        objects[0] = new Thing();
+        return 0;
+    }
+}
+
+class Thing
+{
+}
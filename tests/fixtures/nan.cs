@@ -0,0 +1,12 @@
+// Arithmetic: ordered and unordered comparisons against NaN.
+// Exit code: 1 * 2 + 0 = 2
+class Program
+{
+    static int Main()
+    {
+        double nan = double.NaN, one = 1.0;
+        bool unordered = !(nan >= one);
+        bool ordered = nan < one;
+        return (unordered ? 1 : 0) * 2 + (ordered ? 1 : 0);
+    }
+}
@@ -0,0 +1,10 @@
+// Arrays: allocating an array of negative length.
+// Fails with an unhandled System.OverflowException.
+class Program
+{
+    static int Main()
+    {
+        int length = -1;
+        return new int[length].Length;
+    }
+}
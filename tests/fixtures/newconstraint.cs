@@ -0,0 +1,21 @@
+// Generics: Widget has no parameterless constructor for the `new()` constraint of T. C# refuses to
+// compile it, the image instantiates Factory<Widget> anyway, so it fails to load.
+class Program
+{
+    static int Main()
+    {
+        new Factory<Widget>();
+        return 0;
+    }
+}
+
+class Factory<T> where T : new()
+{
+}
+
+class Widget
+{
+    public Widget(int size)
+    {
+    }
+}
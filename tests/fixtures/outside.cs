@@ -0,0 +1,27 @@
+// Overrides of methods outside the image: an override of Object.GetHashCode, an implicit implementation
+// of IComparable.CompareTo and an explicit one by MethodImpl in a type re-implementing the interface.
+// Exit code: 1 + 1 * 2 + 2 * 10 + 5 * 30 = 173
+using System;
+
+class Program
+{
+    static int Main()
+    {
+        object i = new Implicit();
+        object e = new Explicit();
+        return i.GetHashCode() + e.GetHashCode() * 2 + ((IComparable)i).CompareTo(null) * 10
+            + ((IComparable)e).CompareTo(null) * 30;
+    }
+}
+
+class Implicit : IComparable
+{
+    public int CompareTo(object other) => 2;
+
+    public override int GetHashCode() => 1;
+}
+
+class Explicit : Implicit, IComparable
+{
+    int IComparable.CompareTo(object other) => 5;
+}
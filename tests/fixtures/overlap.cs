@@ -0,0 +1,20 @@
+// Layout: an object reference overlapping an int, which would let the program forge references, so Bad
+// fails to load.
+using System.Runtime.InteropServices;
+
+class Program
+{
+    static int Main()
+    {
+        Bad bad;
+        bad.i = 0;
+        return bad.i;
+    }
+}
+
+[StructLayout(LayoutKind.Explicit)]
+struct Bad
+{
+    [FieldOffset(0)] public object o;
+    [FieldOffset(0)] public int i;
+}
@@ -0,0 +1,52 @@
+// Virtual calls: overrides, a newslot method hiding the base one and a non-virtual call of an overridden method.
+// Exit code: 2 + 10 + 20 + 100 + 1 = 133
+class Program
+{
+    static int Main()
+    {
+        Derived d = new Derived();
+        Base b = d;
+        // The last call is `call Base::Get` on d, the non-virtual call base.Get() makes from Derived
+        return b.Get() + b.Abs() + b.Fin() + d.Fin() + BaseGet(d);
+    }
+}
+
+abstract class Base
+{
+    public virtual int Get() => 1;
+    public abstract int Abs();
+    public virtual int Fin() => 20;
+}
+
+class Derived : Base
+{
+    public override int Get() => 2;
+    public override int Abs() => 10;
+    public new virtual int Fin() => 100;
+}
+
+interface IFace
+{
+    int Val();
+    int Other();
+    int Def() => 7;
+}
+
+class Impl : IFace
+{
+    public int Val() => 3;
+    int IFace.Other() => 5;
+}
+
+struct Point : IFace
+{
+    public int x;
+
+    public int Val() => x;
+
+    public int Other()
+    {
+        x = 1;
+        return x;
+    }
+}
@@ -0,0 +1,19 @@
+// Arrays: long.MaxValue elements of int.MaxValue bytes each, a size past 64 bits, so newarr throws
+// System.OutOfMemoryException.
+using System.Runtime.InteropServices;
+
+class Program
+{
+    static int Main()
+    {
+        long length = long.MaxValue;
+        Big[] array = new Big[length];
+        return array.Length;
+    }
+}
+
+[StructLayout(LayoutKind.Sequential, Size = int.MaxValue)]
+struct Big
+{
+    public int x;
+}
@@ -0,0 +1,10 @@
+// Arithmetic: checked addition past int.MaxValue.
+// Fails with an unhandled System.OverflowException.
+class Program
+{
+    static int Main()
+    {
+        int max = int.MaxValue;
+        return checked(max + 1);
+    }
+}
@@ -0,0 +1,25 @@
+// Generics: the fields of Pair<T> are laid out for each type argument.
+// Exit code: 1 + 2 + 3 + 4 + 100 = 110
+class Program
+{
+    static int Main()
+    {
+        Pair<int> ints = new Pair<int>(1, 2);
+        Pair<long> longs = new Pair<long>(3L << 32, 4);
+        Pair<string> strings = new Pair<string>("a", "b");
+        return ints.first + ints.second + (int)(longs.first >> 32) + (int)longs.second
+            + ((object)strings.second == "b" ? 100 : 0);
+    }
+}
+
+class Pair<T>
+{
+    public T first;
+    public T second;
+
+    public Pair(T first, T second)
+    {
+        this.first = first;
+        this.second = second;
+    }
+}
@@ -0,0 +1,11 @@
+// CLI header directories: two embedded resources and a VTableFixups entry for First and Second, as a
+// mixed-mode image exporting them to native code would have. Written by hand, C# can't export methods.
+// Exit code: 0
+class Program
+{
+    static int Main() => 0;
+
+    public static int First() => 1;
+
+    public static int Second() => 2;
+}
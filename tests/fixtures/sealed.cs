@@ -0,0 +1,50 @@
+// Overriding a final method: Base.Get is `virtual final`, which C# only emits for interface
+// implementations, so Derived fails to load.
+class Program
+{
+    static int Main()
+    {
+        new Derived();
+        return 0;
+    }
+}
+
+abstract class Base
+{
+    public sealed virtual int Get() => 1;
+    public abstract int Abs();
+    public virtual int Fin() => 20;
+}
+
+class Derived : Base
+{
+    public override int Get() => 2;
+    public override int Abs() => 10;
+    public new virtual int Fin() => 100;
+}
+
+interface IFace
+{
+    int Val();
+    int Other();
+    int Def() => 7;
+}
+
+class Impl : IFace
+{
+    public int Val() => 3;
+    int IFace.Other() => 5;
+}
+
+struct Point : IFace
+{
+    public int x;
+
+    public int Val() => x;
+
+    public int Other()
+    {
+        x = 1;
+        return x;
+    }
+}
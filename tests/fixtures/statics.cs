@@ -0,0 +1,15 @@
+// Statics: the type initializer runs once, before the first static field access.
+// Exit code: 40 + 41 + 2 = 83
+class Program
+{
+    static int s_count = 40;
+    static int s_calls;
+
+    static int Next()
+    {
+        s_calls++;
+        return s_count++;
+    }
+
+    static int Main() => Next() + Next() + s_calls;
+}
@@ -0,0 +1,14 @@
+// Strings: ldstr of the same literal gives the same object, whichever method loads it.
+// Exit code: 1 + 2 + 4 = 7
+class Program
+{
+    static string Other() => "hello";
+
+    static int Main()
+    {
+        string a = "hello";
+        string b = "hello";
+        string c = Other();
+        return ((object)a == b ? 1 : 0) + ((object)a == c ? 2 : 0) + ((object)a != "world" ? 4 : 0);
+    }
+}
@@ -0,0 +1,15 @@
+// Generics: Holder<string> breaks the `struct` constraint of T. C# refuses to compile it, the image
+// instantiates it anyway, so Holder<string> fails to load.
+class Program
+{
+    static int Main()
+    {
+        new Holder<string>();
+        return 0;
+    }
+}
+
+class Holder<T> where T : struct
+{
+    public T value;
+}
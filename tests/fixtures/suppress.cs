@@ -0,0 +1,26 @@
+// Finalization: GC.SuppressFinalize keeps the finalizer of an object from running.
+// Exit code: 0
+using System;
+using System.Runtime.InteropServices;
+
+class Program
+{
+    public static int s_finalized;
+    static Node s_kept;
+    static int s_counter = 100;
+
+    static int Main()
+    {
+        GC.SuppressFinalize(new Node());
+        GC.Collect();
+        GC.WaitForPendingFinalizers();
+        return s_finalized;
+    }
+}
+
+class Node
+{
+    Node next;
+
+    ~Node() => Program.s_finalized++;
+}
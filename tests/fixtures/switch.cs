@@ -0,0 +1,16 @@
+// Branches: a jump table.
+// Exit code: 7
+class Program
+{
+    static int Main()
+    {
+        int value = 2;
+        switch (value)
+        {
+            case 0: return 0;
+            case 1: return 1;
+            case 2: return 7;
+            default: return -1;
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// Value types: stfld and ldfld on locals, arguments and a struct field of a class. Value types are
+// copied, so Sum changes its own copy of p.
+// Exit code: 104 + 3 + 40 + 30 = 177
+class Program
+{
+    static int Sum(Point p)
+    {
+        p.x = 100;
+        return p.x + p.y;
+    }
+
+    static int Main()
+    {
+        Point p;
+        p.x = 3;
+        p.y = 4;
+        Holder holder = new Holder();
+        holder.p = p;
+        return Sum(p) + p.x + 10 * p.y + 10 * holder.p.x;
+    }
+}
+
+struct Point
+{
+    public int x;
+    public int y;
+}
+
+class Holder
+{
+    public Point p;
+}
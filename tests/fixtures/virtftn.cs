@@ -0,0 +1,53 @@
+// ldvirtftn finds the override of a method, ldftn the method itself, both called through calli.
+// Exit code: 2 * 10 + 1 = 21
+class Program
+{
+    static int Main()
+    {
+        Derived d = new Derived();
+        // ldvirtftn Base::Get and ldftn Base::Get, the latter the way base.Get does from Derived
+        Func<int> virtual_ = ((Base)d).Get;
+        Func<int> direct = BaseGet(d);
+        return virtual_() * 10 + direct();
+    }
+}
+
+abstract class Base
+{
+    public virtual int Get() => 1;
+    public abstract int Abs();
+    public virtual int Fin() => 20;
+}
+
+class Derived : Base
+{
+    public override int Get() => 2;
+    public override int Abs() => 10;
+    public new virtual int Fin() => 100;
+}
+
+interface IFace
+{
+    int Val();
+    int Other();
+    int Def() => 7;
+}
+
+class Impl : IFace
+{
+    public int Val() => 3;
+    int IFace.Other() => 5;
+}
+
+struct Point : IFace
+{
+    public int x;
+
+    public int Val() => x;
+
+    public int Other()
+    {
+        x = 1;
+        return x;
+    }
+}
@@ -0,0 +1,30 @@
+// GC handles: a weak handle to an unreachable object is cleared by a collection, one to an object
+// GC.KeepAlive keeps alive until after the collection isn't.
+// Exit code: 1 * 2 + 1 = 3
+using System;
+using System.Runtime.InteropServices;
+
+class Program
+{
+    public static int s_finalized;
+    static Node s_kept;
+    static int s_counter = 100;
+
+    static int Main()
+    {
+        GCHandle collected = GCHandle.Alloc(new Node(), GCHandleType.Weak);
+        Node kept = new Node();
+        GCHandle alive = GCHandle.Alloc(kept, GCHandleType.Weak);
+        GC.Collect();
+        int result = (collected.Target == null ? 1 : 0) * 2 + (alive.Target != null ? 1 : 0);
+        GC.KeepAlive(kept);
+        return result;
+    }
+}
+
+class Node
+{
+    Node next;
+
+    ~Node() => Program.s_finalized++;
+}
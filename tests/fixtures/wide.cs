@@ -0,0 +1,26 @@
+// Layout: two structs of int.MaxValue bytes followed by an int, which would start past 4 GB, so Wide
+// fails to load.
+using System.Runtime.InteropServices;
+
+class Program
+{
+    static int Main()
+    {
+        Wide wide;
+        wide.c = 1;
+        return wide.c;
+    }
+}
+
+[StructLayout(LayoutKind.Sequential, Size = int.MaxValue)]
+struct Big
+{
+    public int x;
+}
+
+struct Wide
+{
+    public Big a;
+    public Big b;
+    public int c;
+}
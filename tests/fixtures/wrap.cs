@@ -0,0 +1,10 @@
+// Arithmetic: unchecked int addition wraps around.
+// Exit code: 1
+class Program
+{
+    static int Main()
+    {
+        int max = int.MaxValue;
+        return max + 1 == int.MinValue ? 1 : 0;
+    }
+}
@@ -0,0 +1,182 @@
+//! Replays the fuzz corpus seeds and the inputs that once crashed a fuzz target.
+//!
+//! Each test mirrors the fuzz target of the same name in `fuzz/fuzz_targets`, malformed inputs
+//! are expected to fail with an error, never to panic. Crashes found by `cargo fuzz` go to
+//! `fuzz/regressions/<target>/` once fixed.
+
+use dotnet_rs::cli_header::CliHeader;
+use dotnet_rs::disasm::disassemble;
+use dotnet_rs::export::export;
+use dotnet_rs::heaps::{BlobHeap, GuidHeap, StringHeap, UserStringHeap};
+use dotnet_rs::instruction::decode_instructions;
+use dotnet_rs::metadata::{MetadataRoot, ValidationMode};
+use dotnet_rs::method_body::MethodBody;
+use dotnet_rs::runtime::{RowId, TypeLoader};
+use dotnet_rs::signature::{
+    FieldSignature, LocalVarSignature, MethodSignature, MethodSpecSignature, PropertySignature, Type,
+};
+use dotnet_rs::tables::{TableId, TildaStream};
+use dotnet_rs::token::{Token, TokenKind};
+use dotnet_rs::Assembly;
+use scroll::Pread;
+use std::fs;
+use std::path::Path;
+
+/// Seeds and regressions of `target`, fails if there are none so a renamed directory isn't silently skipped.
+fn inputs(target: &str) -> Vec<Vec<u8>> {
+    let fuzz = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz");
+    let mut inputs = Vec::new();
+    for dir in &[fuzz.join("corpus").join(target), fuzz.join("regressions").join(target)] {
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries {
+                inputs.push(fs::read(entry.unwrap().path()).unwrap());
+            }
+        }
+    }
+    assert!(!inputs.is_empty(), "No inputs for {}", target);
+    inputs
+}
+
+#[test]
+fn assembly() {
+    for data in inputs("assembly") {
+        let _ = Assembly::from_bytes_with(&data, ValidationMode::Strict);
+        let assembly = match Assembly::from_bytes(&data) {
+            Ok(assembly) => assembly,
+            Err(_) => continue,
+        };
+        let _ = assembly.entry_point();
+        let _ = assembly.vtable_fixups();
+        for ty in assembly.types() {
+            let _ = ty.full_name();
+            let _ = ty.base_type();
+            ty.interfaces().for_each(drop);
+        }
+        let mut types = TypeLoader::new(&assembly);
+        for ty in assembly.types() {
+            let _ = types.type_definition(RowId::new(0, ty.rid));
+        }
+        for ty in 1..=assembly.tables().type_refs.len() as u32 {
+            let _ = assembly.type_reference(ty).map(|x| x.full_name());
+        }
+        for method in assembly.methods() {
+            let _ = method.signature();
+            let _ = method.locals();
+            let _ = method.instructions();
+        }
+        for field in assembly.fields() {
+            let _ = field.signature();
+        }
+        for resource in &assembly.tables().manifest_resources {
+            let _ = assembly.manifest_resource_data(resource);
+        }
+        let _ = export(&assembly);
+        let _ = disassemble(&assembly, &mut std::io::sink());
+    }
+}
+
+#[test]
+fn corpus_assemblies_load() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/assembly");
+    for entry in fs::read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        let assembly = Assembly::from_path_with(&path, ValidationMode::Strict).unwrap();
+        assert!(export(&assembly).is_ok(), "{}", path.display());
+        assert!(
+            disassemble(&assembly, &mut std::io::sink()).is_ok(),
+            "{}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn cli_header() {
+    for data in inputs("cli_header") {
+        if let Ok(header) = data.pread_with::<CliHeader>(0, scroll::LE) {
+            let _ = header.entry_point_token();
+            let _ = header.entry_point_rva();
+        }
+    }
+}
+
+#[test]
+fn metadata_root() {
+    for data in inputs("metadata_root") {
+        if let Ok(root) = data.pread_with::<MetadataRoot>(0, scroll::LE) {
+            let _ = root.validate(data.len());
+            for name in &["#~", "#-", "#Strings", "#US", "#Blob", "#GUID"] {
+                let _ = root.stream(&data, name);
+            }
+        }
+    }
+}
+
+#[test]
+fn tables() {
+    for data in inputs("tables") {
+        let tables = match data.pread_with::<TildaStream>(0, scroll::LE) {
+            Ok(tables) => tables,
+            Err(_) => continue,
+        };
+        for table in (0..=0x2c).filter_map(TableId::from_u8) {
+            for rid in 0..=tables.row_count(table) as u32 + 1 {
+                let _ = tables.resolve(Token::new(TokenKind::Table(table), rid));
+            }
+        }
+        for rid in 1..=tables.type_defs.len() as u32 {
+            tables.type_fields(rid).for_each(drop);
+            tables.type_methods(rid).for_each(drop);
+        }
+        for rid in 1..=tables.methods.len() as u32 {
+            tables.method_params(rid).for_each(drop);
+        }
+        for rid in 1..=tables.event_maps.len() as u32 {
+            tables.event_map_events(rid).for_each(drop);
+        }
+        for rid in 1..=tables.property_maps.len() as u32 {
+            tables.property_map_properties(rid).for_each(drop);
+        }
+    }
+}
+
+#[test]
+fn heaps() {
+    for data in inputs("heaps") {
+        let strings = StringHeap::new(&data);
+        let user_strings = UserStringHeap::new(&data);
+        let blobs = BlobHeap::new(&data);
+        let guids = GuidHeap::new(&data);
+        strings.iter().for_each(drop);
+        user_strings.iter().for_each(drop);
+        blobs.iter().for_each(drop);
+        guids.iter().for_each(drop);
+        for index in 0..=data.len() as u32 {
+            let _ = strings.get(index);
+            let _ = user_strings.get(index);
+            let _ = blobs.get(index);
+            let _ = guids.get(index);
+        }
+    }
+}
+
+#[test]
+fn signatures() {
+    for data in inputs("signatures") {
+        let _ = data.pread_with::<MethodSignature>(0, scroll::LE);
+        let _ = data.pread_with::<FieldSignature>(0, scroll::LE);
+        let _ = data.pread_with::<PropertySignature>(0, scroll::LE);
+        let _ = data.pread_with::<LocalVarSignature>(0, scroll::LE);
+        let _ = data.pread_with::<MethodSpecSignature>(0, scroll::LE);
+        let _ = data.pread_with::<Type>(0, scroll::LE);
+    }
+}
+
+#[test]
+fn method_body() {
+    for data in inputs("method_body") {
+        if let Ok(body) = data.pread_with::<MethodBody>(0, scroll::LE) {
+            let _ = decode_instructions(body.code, None);
+        }
+    }
+}
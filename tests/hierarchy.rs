@@ -0,0 +1,66 @@
+//! Reads the type hierarchy of `tests/fixtures/hierarchy.exe`: nesting, base types, interfaces and the
+//! types declaring methods.
+
+use dotnet_rs::Assembly;
+use std::path::Path;
+
+fn hierarchy() -> Assembly<'static> {
+    Assembly::from_path(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hierarchy.exe")).unwrap()
+}
+
+#[test]
+fn nesting() {
+    let assembly = hierarchy();
+    let outer = assembly.find_type("Shapes.Outer").unwrap();
+    let inner = assembly.find_type("Shapes.Outer+Inner").unwrap();
+    assert_eq!(inner.full_name().unwrap(), "Shapes.Outer+Inner");
+    assert_eq!(inner.name().unwrap(), "Inner");
+    assert_eq!(inner.namespace().unwrap(), "");
+    assert_eq!(inner.enclosing_type().map(|x| x.rid), Some(outer.rid));
+    assert!(outer.enclosing_type().is_none());
+    let nested = outer.nested_types().map(|x| x.rid).collect::<Vec<_>>();
+    assert_eq!(nested, vec![inner.rid]);
+    assert_eq!(inner.nested_types().count(), 0);
+}
+
+#[test]
+fn base_types() {
+    let assembly = hierarchy();
+    let base_name = |name: &str| {
+        let ty = assembly.find_type(name).unwrap();
+        ty.base_type().unwrap().map(|x| x.full_name().unwrap().unwrap())
+    };
+    assert_eq!(base_name("Shapes.Outer").as_deref(), Some("Shapes.Base"));
+    assert_eq!(base_name("Shapes.Base").as_deref(), Some("System.Object"));
+    assert_eq!(base_name("Shapes.Outer+Inner").as_deref(), Some("System.Object"));
+    assert_eq!(base_name("Shapes.IShape"), None);
+}
+
+#[test]
+fn interfaces() {
+    let assembly = hierarchy();
+    let interfaces = |name: &str| {
+        let ty = assembly.find_type(name).unwrap();
+        ty.interfaces()
+            .map(|x| x.unwrap().full_name().unwrap().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(interfaces("Shapes.Outer"), vec!["Shapes.IShape", "System.IDisposable"]);
+    // Those of the base types aren't repeated
+    assert!(interfaces("Shapes.Base").is_empty());
+    assert!(interfaces("Shapes.Outer+Inner").is_empty());
+}
+
+#[test]
+fn declaring_types() {
+    let assembly = hierarchy();
+    let main = assembly.entry_point().unwrap().unwrap();
+    assert_eq!(main.name().unwrap(), "Main");
+    assert_eq!(main.declaring_type().unwrap().full_name().unwrap(), "Shapes.Program");
+    let outer = assembly.find_type("Shapes.Outer").unwrap();
+    let methods = outer.methods().map(|x| x.name().unwrap()).collect::<Vec<_>>();
+    assert_eq!(methods, vec!["Area", "Dispose", ".ctor"]);
+    assert!(outer
+        .methods()
+        .all(|x| x.declaring_type().map(|x| x.rid) == Some(outer.rid)));
+}
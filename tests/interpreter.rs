@@ -0,0 +1,150 @@
+//! Runs the images in `tests/fixtures` and checks the exit code of their entry point.
+//!
+//! Each `<name>.exe` is a small hand-assembled image equivalent to the `<name>.cs` next to it, the
+//! source notes the exit code or the exception the image is expected to produce. Assemblies they refer
+//! to are looked for next to them, `bcl/` holds a small mscorlib and System.Core for the images using
+//! List<T> and LINQ.
+
+use dotnet_rs::interpreter;
+use dotnet_rs::resolver::DirectoryResolver;
+use dotnet_rs::{Assembly, Error};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::rc::Rc;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+        .with_extension("exe")
+}
+
+fn run(name: &str) -> Result<i32, Error> {
+    let path = fixture(name);
+    interpreter::run_with_resolver(
+        Rc::new(Assembly::from_path(&path)?),
+        DirectoryResolver::for_application(&path),
+    )
+}
+
+fn check(cases: &[(&str, i32)]) {
+    for &(name, code) in cases {
+        match run(name) {
+            Ok(exit) => assert_eq!(exit, code, "{}", name),
+            Err(error) => panic!("{}: {}", name, error),
+        }
+    }
+}
+
+fn unhandled(name: &str) -> String {
+    match run(name) {
+        Err(Error::UnhandledException { exception, .. }) => exception,
+        other => panic!("{}: expected an unhandled exception, got {:?}", name, other),
+    }
+}
+
+#[test]
+fn arithmetic() {
+    check(&[("conv", 202), ("wrap", 1), ("long", 55), ("nan", 2)]);
+    assert_eq!(unhandled("div0"), "System.DivideByZeroException");
+    assert_eq!(unhandled("ovf"), "System.OverflowException");
+}
+
+#[test]
+fn branches() {
+    check(&[("loop", 55), ("switch", 7)]);
+}
+
+#[test]
+fn calls() {
+    check(&[("fib", 109), ("byref", 42), ("consumer", 15)]);
+}
+
+#[test]
+fn statics() {
+    check(&[("statics", 83), ("initializer", 7)]);
+}
+
+#[test]
+fn finally() {
+    check(&[("finally", 35)]);
+}
+
+#[test]
+fn dispatch() {
+    check(&[
+        ("override", 133),
+        ("interface", 193),
+        ("boxed", 44),
+        ("constrained", 11),
+        ("virtftn", 21),
+        ("cast", 3),
+        ("outside", 173),
+    ]);
+    assert_eq!(unhandled("badcast"), "System.InvalidCastException");
+    for name in &["sealed", "unimplemented"] {
+        match run(name) {
+            Err(Error::TypeLoad { .. }) => {}
+            other => panic!("{}: expected a type load failure, got {:?}", name, other),
+        }
+    }
+}
+
+#[test]
+fn arrays() {
+    check(&[("arrays", 167), ("covariance", 175)]);
+    assert_eq!(unhandled("bounds"), "System.IndexOutOfRangeException");
+    assert_eq!(unhandled("negative"), "System.OverflowException");
+    assert_eq!(unhandled("oversized"), "System.OutOfMemoryException");
+    assert_eq!(unhandled("mismatch"), "System.ArrayTypeMismatchException");
+}
+
+#[test]
+fn strings() {
+    check(&[("strings", 7)]);
+}
+
+#[test]
+fn layout() {
+    check(&[("valuefields", 177), ("layout", 254)]);
+    for name in &["overlap", "huge", "wide"] {
+        match run(name) {
+            Err(Error::TypeLoad { .. }) => {}
+            other => panic!("{}: expected a type load failure, got {:?}", name, other),
+        }
+    }
+}
+
+#[test]
+fn generics() {
+    check(&[("pair", 110), ("genericstatics", 34), ("gvm", 142)]);
+    for name in &["structconstraint", "newconstraint", "baseconstraint"] {
+        match run(name) {
+            Err(Error::TypeLoad { .. }) => {}
+            other => panic!("{}: expected a type load failure, got {:?}", name, other),
+        }
+    }
+    check(&[("bcl/list", 1), ("bcl/linq", 23)]);
+}
+
+#[test]
+fn garbage_collection() {
+    check(&[("weak", 3), ("finalizer", 1), ("suppress", 0)]);
+}
+
+#[test]
+fn run_command() {
+    let status = Command::new(env!("CARGO_BIN_EXE_dotnet-rs"))
+        .arg("run")
+        .arg(fixture("fib"))
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(109));
+    let output = Command::new(env!("CARGO_BIN_EXE_dotnet-rs"))
+        .arg("run")
+        .arg(fixture("div0"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("System.DivideByZeroException"));
+}
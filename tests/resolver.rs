@@ -0,0 +1,90 @@
+//! Resolves the references `tests/fixtures/consumer.exe` makes to `library.dll` next to it, directly
+//! and through the type forwarder in `forwarder.dll`.
+
+use dotnet_rs::resolver::{AssemblyResolver, Definition, DirectoryResolver};
+use dotnet_rs::{Assembly, Error};
+use std::path::Path;
+use std::rc::Rc;
+
+fn consumer() -> (Rc<Assembly<'static>>, DirectoryResolver) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/consumer.exe");
+    let resolver = DirectoryResolver::for_application(&path);
+    (Rc::new(Assembly::from_path(&path).unwrap()), resolver)
+}
+
+/// The defining assembly, the token and the name of a definition.
+fn describe(definition: &Definition) -> (String, u32, String) {
+    let name = if let Some(ty) = definition.type_definition() {
+        ty.full_name().unwrap()
+    } else if let Some(method) = definition.method() {
+        method.name().unwrap().to_string()
+    } else {
+        definition.field().unwrap().name().unwrap().to_string()
+    };
+    (
+        definition.assembly.name().unwrap().to_string(),
+        definition.token.raw(),
+        name,
+    )
+}
+
+fn expected(assembly: &str, token: u32, name: &str) -> (String, u32, String) {
+    (assembly.to_string(), token, name.to_string())
+}
+
+#[test]
+fn type_refs() {
+    let (assembly, resolver) = consumer();
+    let widget = resolver.resolve_type_ref(&assembly, 3).unwrap();
+    assert_eq!(describe(&widget), expected("library", 0x0200_0002, "Lib.Widget"));
+    let part = resolver.resolve_type_ref(&assembly, 4).unwrap();
+    assert_eq!(describe(&part), expected("library", 0x0200_0003, "Lib.Widget+Part"));
+    // Both resolve to the same loaded image
+    assert!(Rc::ptr_eq(&widget.assembly, &part.assembly));
+}
+
+#[test]
+fn forwarded_types() {
+    let (assembly, resolver) = consumer();
+    let moved = resolver.resolve_type_ref(&assembly, 5).unwrap();
+    assert_eq!(describe(&moved), expected("library", 0x0200_0004, "Lib.Moved"));
+    let constructor = resolver.resolve_member_ref(&assembly, 7).unwrap();
+    assert_eq!(describe(&constructor), expected("library", 0x0600_0006, ".ctor"));
+}
+
+#[test]
+fn member_refs() {
+    let (assembly, resolver) = consumer();
+    let cases = [
+        (1, expected("library", 0x0600_0004, ".ctor")),
+        // Overloads are told apart by their signatures
+        (2, expected("library", 0x0600_0001, "Get")),
+        (3, expected("library", 0x0600_0002, "Get")),
+        (4, expected("library", 0x0600_0003, "Twice")),
+        (5, expected("library", 0x0400_0001, "size")),
+        (6, expected("library", 0x0400_0002, "next")),
+        (8, expected("library", 0x0600_0005, ".ctor")),
+    ];
+    for (rid, definition) in &cases {
+        assert_eq!(
+            &describe(&resolver.resolve_member_ref(&assembly, *rid).unwrap()),
+            definition
+        );
+    }
+    match resolver.resolve_member_ref(&assembly, 9) {
+        Err(Error::UnresolvedMember { parent, name }) => {
+            assert_eq!((parent.as_str(), name.as_str()), ("Lib.Widget", "Missing"))
+        }
+        other => panic!("expected an unresolved member, got {:?}", other.map(|x| describe(&x))),
+    }
+}
+
+#[test]
+fn names_stay_in_the_probed_directories() {
+    let (assembly, resolver) = consumer();
+    // `../fixtures/library` would find library.dll again if it were joined to the directory
+    match resolver.resolve_type_ref(&assembly, 6) {
+        Err(Error::UnresolvedAssembly(name)) => assert!(name.starts_with("../fixtures/library,"), "{}", name),
+        other => panic!("expected an unresolved assembly, got {:?}", other.map(|x| describe(&x))),
+    }
+}